@@ -1,37 +1,310 @@
-use crate::{expect_tok, lexer::tokens::Token, parser::Parser, parser_error};
+use crate::span::Span;
 
-use super::{types::Type, Ident};
+use super::{stmt::BlockStmt, types::Type, Ident};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression<'ast> {
-    LiteralString(&'ast str),
-    LiteralChar(char),
+    LiteralString(StringLiteral<'ast>),
+    LiteralChar(CharLiteral<'ast>),
 
-    LiteralShort(i16),
-    LiteralInt(i32),
-    LiteralLong(i64),
-    LiteralFloat(f32),
-    LiteralDouble(f64),
+    LiteralShort { value: i16, span: Span },
+    LiteralInt { value: i32, span: Span },
+    LiteralUInt { value: u32, span: Span },
+    LiteralLong { value: i64, span: Span },
+    LiteralULong { value: u64, span: Span },
+    LiteralLongLong { value: i64, span: Span },
+    LiteralULongLong { value: u64, span: Span },
+    /// Integer with a `z` (signed size type) or `uz` (`size_t`) suffix
+    LiteralSize { value: u64, is_unsigned: bool, span: Span },
+    /// Integer with a `wb` or `uwb` suffix of type `_BitInt(width)`
+    LiteralBitInt { value: u128, width: u16, is_unsigned: bool, span: Span },
+    LiteralFloat { value: f32, span: Span },
+    LiteralDouble { value: f64, span: Span },
+    LiteralLongDouble { value: f64, span: Span },
+    /// `true` or `false` (C23)
+    LiteralBool { value: bool, span: Span },
+    /// `nullptr` (C23)
+    Nullptr { span: Span },
 
-    Ident(Ident<'ast>),
+    Ident { name: Ident<'ast>, span: Span },
 
     Prefix(PrefixExpr<'ast>),
     Infix(InfixExpr<'ast>),
     Post(PostExpr<'ast>),
 
     Call(CallExpr<'ast>),
+    /// `val[index]`
+    Index(IndexExpr<'ast>),
+    /// `val.member` or `val->member`
+    Member(MemberExpr<'ast>),
+    /// `_Generic(control, type: value, default: value)` (C11)
+    Generic(GenericExpr<'ast>),
+    /// `cond ? then : otherwise`
+    Conditional(ConditionalExpr<'ast>),
+    /// `left, right`, which evaluates to `right`
+    Comma(CommaExpr<'ast>),
+
+    /// `sizeof` applied to a parenthesized type name
+    SizeOfType { data_type: Type<'ast>, span: Span },
+    /// `_Alignof` or `alignof` (C23) applied to a parenthesized type name
+    AlignOfType { data_type: Type<'ast>, span: Span },
+    /// `(type){initializers}`, an unnamed object
+    CompoundLiteral {
+        data_type: Type<'ast>,
+        /// Always a braced list
+        initializer: &'ast Initializer<'ast>,
+        span: Span,
+    },
+
+    /// `({ ... })`, which evaluates to its last expression statement. A GNU
+    /// extension
+    Statement { block: BlockStmt<'ast>, span: Span },
+    /// `&&label`, the address of a label. A GNU extension
+    LabelAddr { label: Ident<'ast>, span: Span },
+    /// `__builtin_va_arg(list, type)`
+    VaArg {
+        list: &'ast Expression<'ast>,
+        data_type: Type<'ast>,
+        span: Span,
+    },
+    /// `__builtin_offsetof(type, member)`
+    OffsetOf {
+        data_type: Type<'ast>,
+        /// Path to the member, starting with a [Designator::Member]
+        member: Vec<Designator<'ast>>,
+        span: Span,
+    },
+
+    /// Placeholder for an expression that could not be parsed
+    Error { span: Span },
+}
+
+impl Expression<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::LiteralShort { span, .. }
+            | Expression::LiteralInt { span, .. }
+            | Expression::LiteralUInt { span, .. }
+            | Expression::LiteralLong { span, .. }
+            | Expression::LiteralULong { span, .. }
+            | Expression::LiteralLongLong { span, .. }
+            | Expression::LiteralULongLong { span, .. }
+            | Expression::LiteralSize { span, .. }
+            | Expression::LiteralBitInt { span, .. }
+            | Expression::LiteralFloat { span, .. }
+            | Expression::LiteralDouble { span, .. }
+            | Expression::LiteralLongDouble { span, .. }
+            | Expression::LiteralBool { span, .. }
+            | Expression::Nullptr { span }
+            | Expression::Ident { span, .. }
+            | Expression::SizeOfType { span, .. }
+            | Expression::AlignOfType { span, .. }
+            | Expression::CompoundLiteral { span, .. }
+            | Expression::Statement { span, .. }
+            | Expression::LabelAddr { span, .. }
+            | Expression::VaArg { span, .. }
+            | Expression::OffsetOf { span, .. }
+            | Expression::Error { span } => *span,
+            Expression::LiteralString(literal) => literal.span,
+            Expression::LiteralChar(literal) => literal.span,
+            Expression::Prefix(expr) => expr.span,
+            Expression::Infix(expr) => expr.span,
+            Expression::Post(expr) => expr.span,
+            Expression::Call(expr) => expr.span,
+            Expression::Index(expr) => expr.span,
+            Expression::Member(expr) => expr.span,
+            Expression::Generic(expr) => expr.span,
+            Expression::Conditional(expr) => expr.span,
+            Expression::Comma(expr) => expr.span,
+        }
+    }
+}
+
+/// Encoding prefix of a character or string literal, which determines the
+/// type of its elements
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    /// No prefix, `char`
+    Plain,
+    /// `u8`, `char8_t` (`char` before C23)
+    Utf8,
+    /// `u`, `char16_t`
+    Utf16,
+    /// `U`, `char32_t`
+    Utf32,
+    /// `L`, `wchar_t`
+    Wide,
+}
+
+impl Encoding {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Encoding::Plain => "",
+            Encoding::Utf8 => "u8",
+            Encoding::Utf16 => "u",
+            Encoding::Utf32 => "U",
+            Encoding::Wide => "L",
+        }
+    }
+
+    /// Name of the element type
+    pub fn element_type(&self) -> &'static str {
+        match self {
+            Encoding::Plain => "char",
+            Encoding::Utf8 => "char8_t",
+            Encoding::Utf16 => "char16_t",
+            Encoding::Utf32 => "char32_t",
+            Encoding::Wide => "wchar_t",
+        }
+    }
+
+    /// Size of an element in bytes. `wchar_t` is assumed to have 32 bits
+    pub fn element_size(&self) -> usize {
+        match self {
+            Encoding::Plain | Encoding::Utf8 => 1,
+            Encoding::Utf16 => 2,
+            Encoding::Utf32 | Encoding::Wide => 4,
+        }
+    }
+}
+
+/// One or more adjacent string literals, concatenated
+#[derive(Debug, PartialEq, Clone)]
+pub struct StringLiteral<'ast> {
+    pub encoding: Encoding,
+    /// Source spelling of each concatenated literal, with prefix and quotes
+    pub spelling: &'ast [&'ast str],
+    /// Decoded elements without the terminating null. Each element is a code
+    /// unit of the element type, e.g. a byte for [Encoding::Plain]
+    pub value: &'ast [u32],
+    pub span: Span,
+}
+
+impl StringLiteral<'_> {
+    /// The decoded elements in little endian byte order
+    pub fn bytes(&self) -> Vec<u8> {
+        let size = self.encoding.element_size();
+        self.value
+            .iter()
+            .flat_map(|unit| unit.to_le_bytes().into_iter().take(size))
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CharLiteral<'ast> {
+    pub encoding: Encoding,
+    /// Source spelling with prefix and quotes
+    pub spelling: &'ast str,
+    /// Decoded value. Unprefixed multi-character constants like `'ab'`
+    /// combine their bytes, most significant first
+    pub value: u32,
+    pub span: Span,
+}
+
+/// Initial value of a variable
+#[derive(Debug, PartialEq, Clone)]
+pub enum Initializer<'ast> {
+    Expr(Expression<'ast>),
+    /// Braced list, possibly empty (C23)
+    List {
+        items: Vec<InitializerItem<'ast>>,
+        span: Span,
+    },
+}
+
+impl Initializer<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Expr(expr) => expr.span(),
+            Initializer::List { span, .. } => *span,
+        }
+    }
+}
+
+/// Element of a braced initializer list, like `.x = 1` or `[3] = 2`
+#[derive(Debug, PartialEq, Clone)]
+pub struct InitializerItem<'ast> {
+    /// Empty for positional items
+    pub designators: Vec<Designator<'ast>>,
+    pub value: Initializer<'ast>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Designator<'ast> {
+    /// `.name`
+    Member { name: Ident<'ast>, span: Span },
+    /// `[index]`
+    Index { index: Expression<'ast>, span: Span },
+    /// `[first ... last]`, a GNU extension
+    Range {
+        first: Expression<'ast>,
+        last: Expression<'ast>,
+        span: Span,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct CallExpr<'ast> {
     pub val: &'ast Expression<'ast>,
     pub args: Vec<Expression<'ast>>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndexExpr<'ast> {
+    pub val: &'ast Expression<'ast>,
+    pub index: &'ast Expression<'ast>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemberExpr<'ast> {
+    pub val: &'ast Expression<'ast>,
+    pub member: Ident<'ast>,
+    /// `->`, which accesses the member of the struct `val` points to
+    pub is_arrow: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenericExpr<'ast> {
+    /// Operand whose type selects the association
+    pub control: &'ast Expression<'ast>,
+    pub associations: Vec<GenericAssociation<'ast>>,
+    pub span: Span,
+}
+
+/// `type: value` in a generic selection
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenericAssociation<'ast> {
+    /// `None` for the `default` association
+    pub data_type: Option<Type<'ast>>,
+    pub value: Expression<'ast>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConditionalExpr<'ast> {
+    pub cond: &'ast Expression<'ast>,
+    pub then: &'ast Expression<'ast>,
+    pub otherwise: &'ast Expression<'ast>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CommaExpr<'ast> {
+    pub left: &'ast Expression<'ast>,
+    pub right: &'ast Expression<'ast>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct PrefixExpr<'ast> {
     pub val: &'ast Expression<'ast>,
     pub op: PreOperator<'ast>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -39,12 +312,14 @@ pub struct InfixExpr<'ast> {
     pub left: &'ast Expression<'ast>,
     pub right: &'ast Expression<'ast>,
     pub op: InOperator,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct PostExpr<'ast> {
     pub val: &'ast Expression<'ast>,
     pub op: PostOperator,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -56,35 +331,16 @@ pub enum PreOperator<'ast> {
     Deref,
     SizeOf,
     AddrOf,
+    /// `_Alignof` applied to an expression, a GNU extension
     AlignOf,
+    /// `__extension__`, which silences pedantic diagnostics about GNU
+    /// extensions in the operand
+    Extension,
     Cast(Type<'ast>),
     Incr,
     Decr,
 }
 
-impl<'op, 'a, 's> PreOperator<'op> {
-    /// Cur token is the expr
-    pub(crate) fn end_expr(&self, parser: &mut Parser<'a, 's>)
-    where
-        's: 'a,
-    {
-        if let PreOperator::SizeOf = self {
-            if let Some(peek_tok) = parser.peek_tok() {
-                if expect_tok!(peek_tok, &Token::RParent, |tok| {
-                    parser_error!(
-                        "Expected closin parenthsis after sizeof(...), received: {:#?}",
-                        tok
-                    )
-                }) {
-                    parser.next_tok();
-                }
-            } else {
-                parser_error!("Expected closing parenthesis after sizeof(...) received None")
-            }
-        }
-    }
-}
-
 #[derive(Debug, PartialEq, Clone)]
 pub enum InOperator {
     // Arithmetic Operators
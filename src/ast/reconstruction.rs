@@ -1,166 +1,860 @@
-use std::fmt::{write, Display};
+use std::fmt::Display;
 
-use crate::ast::expr::InOperator;
+use crate::{
+    ast::expr::{InOperator, PostOperator, PreOperator},
+    lexer::tokens::Token,
+};
 
 use super::{
-    expr::{Expression, InfixExpr}, stmt::{BlockStmt, Field, FunctionStmt, Statement, VariableStmt}, types::Type
+    expr::{Designator, Expression, InfixExpr, Initializer, InitializerItem},
+    stmt::{
+        Alignment, AsmOperand, AsmStmt, Attribute, BlockStmt, CaseStmt, DataStorageClass, DeclSpecifiers, EnumStmt,
+        Enumerator, Field, ForInit, ForStmt, FunctionStmt, IfStmt, InitDeclarator, Statement, StructStmt,
+        TypedefDeclarator, TypedefStmt, UnionStmt, VariableStmt,
+    },
+    types::{ArraySize, Param, ParamStyle, PrimitiveKind, Qualifiers, Type, TypeOfOperand},
 };
 
 impl Display for Statement<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Statement::Struct(struct_stmt) => todo!(),
-            Statement::Enum(enum_stmt) => todo!(),
-            Statement::Union(union_stmt) => todo!(),
-            Statement::Label(label_stmt) => todo!(),
+            Statement::Struct(struct_stmt) => write!(f, "{struct_stmt};"),
+            Statement::Enum(enum_stmt) => write!(f, "{enum_stmt};"),
+            Statement::Union(union_stmt) => write!(f, "{union_stmt};"),
+            Statement::Label(label_stmt) => write!(f, "{}: {}", label_stmt.name, label_stmt.body),
             Statement::Function(function_stmt) => function_stmt.fmt(f),
             Statement::Variable(variable_stmt) => variable_stmt.fmt(f),
-            Statement::If(if_stmt) => todo!(),
-            Statement::Switch(switch_stmt) => todo!(),
-            Statement::While(while_stmt) => todo!(),
-            Statement::DoWhile(do_while_stmt) => todo!(),
-            Statement::For(for_stmt) => todo!(),
-            Statement::Typedef(typedef_stmt) => todo!(),
-            Statement::Return(return_stmt) => todo!(),
-            Statement::Break(break_stmt) => todo!(),
-            Statement::Continue(continue_stmt) => todo!(),
-            Statement::Goto(goto_stmt) => todo!(),
-            Statement::Block(block_stmt) => todo!(),
+            Statement::If(if_stmt) => if_stmt.fmt(f),
+            Statement::Switch(switch_stmt) => write!(f, "switch ({}) {}", switch_stmt.cond, switch_stmt.body),
+            Statement::Case(case_stmt) => case_stmt.fmt(f),
+            Statement::Default(default_stmt) => write!(f, "default: {}", default_stmt.body),
+            Statement::While(while_stmt) => write!(f, "while ({}) {}", while_stmt.cond, while_stmt.body),
+            Statement::DoWhile(do_while_stmt) => {
+                write!(f, "do {} while ({});", do_while_stmt.body, do_while_stmt.cond)
+            }
+            Statement::For(for_stmt) => for_stmt.fmt(f),
+            Statement::Typedef(typedef_stmt) => typedef_stmt.fmt(f),
+            Statement::StaticAssert(static_assert) => {
+                write!(f, "_Static_assert({}", operand(&static_assert.cond, ASSIGN))?;
+                if let Some(message) = &static_assert.message {
+                    write!(f, ", {}", message.spelling.join(" "))?;
+                }
+                f.write_str(");")
+            }
+            Statement::Attribute(attribute_stmt) => {
+                write!(f, "{};", attributes_to_string(&attribute_stmt.attributes))
+            }
+            Statement::Return(return_stmt) => match &return_stmt.val {
+                Some(val) => write!(f, "return {val};"),
+                None => f.write_str("return;"),
+            },
+            Statement::Break(_) => f.write_str("break;"),
+            Statement::Continue(_) => f.write_str("continue;"),
+            Statement::Goto(goto_stmt) => write!(f, "goto {};", goto_stmt.label),
+            Statement::ComputedGoto(goto_stmt) => write!(f, "goto *{};", operand(&goto_stmt.target, PREFIX)),
+            Statement::Asm(asm_stmt) => asm_stmt.fmt(f),
+            Statement::Block(block_stmt) => block_stmt.fmt(f),
             Statement::Expression(expression) => f.write_str(&(expression.to_string()+";")),
+            Statement::Empty(_) => f.write_str(";"),
+            Statement::Error(_) => f.write_str("/* error */;"),
         }
     }
 }
 
 impl Display for FunctionStmt<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = params_to_string(&self.params, self.is_variadic, self.style);
+        let mut declarator = declarator_to_string(&self.ret_data_type, format!("{}({params})", self.name));
+        // Declaration list of old-style definitions
+        if self.style == ParamStyle::Identifiers {
+            for param in &self.params {
+                declarator.push_str(&format!(" {param};"));
+            }
+        }
         write!(
             f,
-            "{ret_type} {func_name}({args}) {block}",
-            ret_type = self.ret_data_type,
-            func_name = self.name,
-            args = args_to_string(&self.args),
-            block = body_to_string(self.body.as_ref().unwrap()),
+            "{specifiers}{declarator}{block}",
+            specifiers = self.specifiers,
+            block = match &self.body {
+                Some(body) => body_to_string(body),
+                None => String::from(";"),
+            },
         )
     }
 }
 
-fn args_to_string(args: &[Field<'_>]) -> String {
-    let mut str = String::new();
-    for arg in args {
-        str.push_str(&arg.to_string());
-        str.push(',');
+impl Display for AsmStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("asm")?;
+        if self.is_volatile {
+            f.write_str(" volatile")?;
+        }
+        if self.is_inline {
+            f.write_str(" inline")?;
+        }
+        if self.is_goto {
+            f.write_str(" goto")?;
+        }
+        write!(f, "({}", self.template.spelling.join(" "))?;
+        let sections = [
+            self.outputs.iter().map(AsmOperand::to_string).collect::<Vec<_>>(),
+            self.inputs.iter().map(AsmOperand::to_string).collect(),
+            self.clobbers.iter().map(|clobber| clobber.spelling.join(" ")).collect(),
+            self.labels.iter().map(|label| label.to_string()).collect(),
+        ];
+        // Trailing empty sections are omitted
+        let count = sections.iter().rposition(|section| !section.is_empty()).map_or(0, |last| last + 1);
+        for section in &sections[..count] {
+            f.write_str(" :")?;
+            if !section.is_empty() {
+                write!(f, " {}", section.join(", "))?;
+            }
+        }
+        f.write_str(");")
     }
-    str.remove(str.len() - 1);
-    str
+}
+
+impl Display for AsmOperand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name {
+            write!(f, "[{name}] ")?;
+        }
+        write!(f, "{}({})", self.constraint.spelling.join(" "), self.value)
+    }
+}
+
+/// Writes each specifier followed by a space, so the declarator can follow
+impl Display for DeclSpecifiers<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_extension {
+            f.write_str("__extension__ ")?;
+        }
+        if !self.attributes.is_empty() {
+            write!(f, "{} ", attributes_to_string(&self.attributes))?;
+        }
+        if self.data_storage_class != DataStorageClass::None {
+            write!(f, "{} ", self.data_storage_class.keyword())?;
+        }
+        if self.is_thread_local {
+            f.write_str("_Thread_local ")?;
+        }
+        if self.is_constexpr {
+            f.write_str("constexpr ")?;
+        }
+        if self.is_inline {
+            f.write_str("inline ")?;
+        }
+        if self.is_noreturn {
+            f.write_str("_Noreturn ")?;
+        }
+        for alignment in &self.alignment {
+            match alignment {
+                Alignment::Expr { value, .. } => write!(f, "_Alignas({value}) ")?,
+                Alignment::Type { data_type, .. } => write!(f, "_Alignas({data_type}) ")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spells the attributes in specifiers of their syntax, grouping adjacent
+/// attributes of the same syntax
+fn attributes_to_string(attributes: &[Attribute<'_>]) -> String {
+    let specifiers: Vec<_> = attributes
+        .chunk_by(|a, b| a.is_gnu == b.is_gnu)
+        .map(|group| {
+            let list: Vec<_> = group.iter().map(Attribute::to_string).collect();
+            if group[0].is_gnu {
+                format!("__attribute__(({}))", list.join(", "))
+            } else {
+                format!("[[{}]]", list.join(", "))
+            }
+        })
+        .collect();
+    specifiers.join(" ")
+}
+
+/// Spells attributes after a declarator with a leading space
+fn trailing_attributes(attributes: &[Attribute<'_>]) -> String {
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", attributes_to_string(attributes))
+    }
+}
+
+impl Display for Attribute<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(prefix) = self.prefix {
+            write!(f, "{prefix}::")?;
+        }
+        f.write_str(self.name)?;
+        if let Some(args) = &self.args {
+            let args: Vec<_> = args.iter().map(Token::to_string).collect();
+            write!(f, "({})", args.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn params_to_string(params: &[Param<'_>], is_variadic: bool, style: ParamStyle) -> String {
+    match style {
+        ParamStyle::Prototype if params.is_empty() && !is_variadic => return String::from("void"),
+        ParamStyle::Identifiers => {
+            let names: Vec<_> = params.iter().map(|param| param.name.unwrap_or_default()).collect();
+            return names.join(", ");
+        }
+        _ => (),
+    }
+    let mut params: Vec<String> = params.iter().map(Param::to_string).collect();
+    if is_variadic {
+        params.push(String::from("..."));
+    }
+    params.join(", ")
 }
 
 fn body_to_string(block: &BlockStmt<'_>) -> String {
-    let mut str = String::from(" {");
-    for stmt in &block.block {
-        str.push_str(&stmt.to_string());
+    format!(" {block}")
+}
+
+impl Display for BlockStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        for stmt in &self.block {
+            stmt.fmt(f)?;
+        }
+        f.write_str("}")
+    }
+}
+
+impl Display for IfStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.cond {
+            Some(cond) => write!(f, "if ({cond}) {}", self.body)?,
+            None => self.body.fmt(f)?,
+        }
+        match self.alt {
+            Some(alt) => write!(f, " else {alt}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for CaseStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "case {}", self.value)?;
+        if let Some(range_end) = &self.range_end {
+            write!(f, " ... {range_end}")?;
+        }
+        write!(f, ": {}", self.body)
+    }
+}
+
+impl Display for ForStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("for (")?;
+        match &self.init {
+            // A single declaration, unlike the one statement per declarator
+            // of [VariableStmt]
+            Some(ForInit::Declaration(declaration)) => {
+                let declarators = declaration.declarators.iter().map(init_declarator_parts);
+                let declaration = single_declaration(&declaration.specifiers, declarators);
+                write!(f, "{declaration};")?;
+            }
+            Some(ForInit::Expr(init)) => write!(f, "{init};")?,
+            None => f.write_str(";")?,
+        }
+        if let Some(cond) = &self.cond {
+            write!(f, " {cond}")?;
+        }
+        f.write_str(";")?;
+        if let Some(increment) = &self.increment {
+            write!(f, " {increment}")?;
+        }
+        write!(f, ") {}", self.body)
+    }
+}
+
+/// Writes the struct specifier, with the member list if it is defined
+impl Display for StructStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&tag_to_string("struct", self.name, &self.attributes))?;
+        fields_to_string(f, self.fields.as_deref())
+    }
+}
+
+/// Writes the union specifier, with the member list if it is defined
+impl Display for UnionStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&tag_to_string("union", self.name, &self.attributes))?;
+        fields_to_string(f, self.fields.as_deref())
+    }
+}
+
+fn fields_to_string(f: &mut std::fmt::Formatter<'_>, fields: Option<&[Field<'_>]>) -> std::fmt::Result {
+    let Some(fields) = fields else {
+        return Ok(());
+    };
+    f.write_str(" {")?;
+    for field in fields {
+        write!(f, "{field};")?;
+    }
+    f.write_str("}")
+}
+
+/// Writes the enum specifier, with the enumerator list if it is defined
+impl Display for EnumStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&tag_to_string("enum", self.name, &self.attributes))?;
+        if let Some(underlying_type) = &self.underlying_type {
+            write!(f, " : {underlying_type}")?;
+        }
+        if let Some(variants) = &self.variants {
+            let variants: Vec<_> = variants.iter().map(Enumerator::to_string).collect();
+            write!(f, " {{{}}}", variants.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Enumerator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)?;
+        match &self.value {
+            Some(value) => write!(f, " = {}", operand(value, ASSIGN)),
+            None => Ok(()),
+        }
     }
-    str.push('}');
-    str
 }
 
 impl Display for Field<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{type_} {name}",
-            type_ = self.field_type,
-            name = self.name
-        )
+        let name = self.name.unwrap_or_default();
+        f.write_str(&declarator_to_string(&self.field_type, name.to_string()))?;
+        f.write_str(&trailing_attributes(&self.attributes))?;
+        match &self.bit_width {
+            Some(width) => write!(f, " : {width}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Param<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.name.unwrap_or_default();
+        f.write_str(&declarator_to_string(&self.data_type, name.to_string()))?;
+        f.write_str(&trailing_attributes(&self.attributes))
     }
 }
 
 impl Display for Type<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Type::Ident(id) => f.write_str(id),
-            Type::Pointer {
-                data_type: type_,
-                is_const,
-                is_restricted,
-            } => write!(
-                f,
-                "{}{type_}*{}",
-                if *is_const { " const" } else { "" },
-                if *is_restricted { " restrict" } else { "" }
-            ),
-            Type::Array { data_type: type_, size } => write!(
-                f,
-                "{type_}[{}]",
-                if let Some(size) = size {
-                    size.to_string()
-                } else {
-                    String::new()
-                }
-            ),
-            Type::Struct(_) => todo!(),
-            Type::Union(_) => todo!(),
-            Type::Enum(_) => todo!(),
+        f.write_str(&declarator_to_string(self, String::new()))
+    }
+}
+
+/// Spells the type in C declaration syntax around `inner`, which is the
+/// declared name or the part of the declarator already spelled. Empty for
+/// type names
+fn declarator_to_string(data_type: &Type<'_>, inner: String) -> String {
+    let (base, declarator) = split_declarator(data_type, inner);
+    let mut str = base_to_string(base);
+    // `int[3]` rather than `int [3]`
+    if !declarator.is_empty() && !declarator.starts_with('[') {
+        str.push(' ');
+    }
+    str.push_str(&declarator);
+    str
+}
+
+/// Splits a type into the type that is not derived from another one, and
+/// the declarator around `inner` that derives `data_type` from it
+fn split_declarator<'t, 'ast>(data_type: &'t Type<'ast>, inner: String) -> (&'t Type<'ast>, String) {
+    match data_type {
+        Type::Pointer {
+            data_type, qualifiers, ..
+        } => {
+            let mut declarator = format!("*{qualifiers}");
+            if !qualifiers.is_empty() && !inner.is_empty() {
+                declarator.push(' ');
+            }
+            declarator.push_str(&inner);
+            // `*` binds weaker than the suffixes of arrays and functions
+            if let Type::Array { .. } | Type::Function { .. } = data_type {
+                declarator = format!("({declarator})");
+            }
+            split_declarator(data_type, declarator)
+        }
+        Type::Array {
+            data_type,
+            size,
+            is_static,
+            qualifiers,
+            ..
+        } => {
+            let mut brackets = Vec::new();
+            if *is_static {
+                brackets.push(String::from("static"));
+            }
+            if !qualifiers.is_empty() {
+                brackets.push(qualifiers.to_string());
+            }
+            match size {
+                ArraySize::Unspecified => (),
+                ArraySize::Star => brackets.push(String::from("*")),
+                ArraySize::Expr(size) => brackets.push(size.to_string()),
+            }
+            split_declarator(data_type, format!("{inner}[{}]", brackets.join(" ")))
+        }
+        Type::Function {
+            return_type,
+            params,
+            is_variadic,
+            style,
+            ..
+        } => split_declarator(return_type, format!("{inner}({})", params_to_string(params, *is_variadic, *style))),
+        _ => (data_type, inner),
+    }
+}
+
+/// Spells a type that is not derived from another one
+fn base_to_string(data_type: &Type<'_>) -> String {
+    let qualifiers = data_type.qualifiers();
+    let mut str = if qualifiers.is_empty() {
+        String::new()
+    } else {
+        format!("{qualifiers} ")
+    };
+    match data_type {
+        Type::Primitive { kind, .. } => str.push_str(&kind.to_string()),
+        Type::Ident { name, .. } => str.push_str(name),
+        Type::Struct { name, definition, .. } => match definition {
+            Some(definition) => str.push_str(&definition.to_string()),
+            None => str.push_str(&tag_to_string("struct", *name, &[])),
+        },
+        Type::Union { name, definition, .. } => match definition {
+            Some(definition) => str.push_str(&definition.to_string()),
+            None => str.push_str(&tag_to_string("union", *name, &[])),
+        },
+        Type::Enum { name, definition, .. } => match definition {
+            Some(definition) => str.push_str(&definition.to_string()),
+            None => str.push_str(&tag_to_string("enum", *name, &[])),
+        },
+        Type::TypeOf { operand, is_unqual, .. } => {
+            str.push_str(if *is_unqual { "typeof_unqual" } else { "typeof" });
+            match operand {
+                TypeOfOperand::Expr(expr) => str.push_str(&format!("({expr})")),
+                TypeOfOperand::Type(data_type) => str.push_str(&format!("({data_type})")),
+            }
         }
+        Type::Auto { .. } => str.push_str("auto"),
+        // Derived types are normally spelled by their declarator
+        Type::Pointer { .. } | Type::Array { .. } | Type::Function { .. } => {
+            return declarator_to_string(data_type, String::new())
+        }
+    }
+    str
+}
+
+impl Display for Qualifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keywords = [
+            (Qualifiers::CONST, "const"),
+            (Qualifiers::VOLATILE, "volatile"),
+            (Qualifiers::RESTRICT, "restrict"),
+            (Qualifiers::ATOMIC, "_Atomic"),
+        ];
+        let keywords: Vec<_> = keywords
+            .into_iter()
+            .filter(|(qualifier, _)| self.contains(*qualifier))
+            .map(|(_, keyword)| keyword)
+            .collect();
+        f.write_str(&keywords.join(" "))
     }
 }
 
+impl Display for PrimitiveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PrimitiveKind::Void => "void",
+            PrimitiveKind::Bool => "_Bool",
+            PrimitiveKind::Char => "char",
+            PrimitiveKind::SignedChar => "signed char",
+            PrimitiveKind::UnsignedChar => "unsigned char",
+            PrimitiveKind::Short => "short",
+            PrimitiveKind::UnsignedShort => "unsigned short",
+            PrimitiveKind::Int => "int",
+            PrimitiveKind::UnsignedInt => "unsigned int",
+            PrimitiveKind::Long => "long",
+            PrimitiveKind::UnsignedLong => "unsigned long",
+            PrimitiveKind::LongLong => "long long",
+            PrimitiveKind::UnsignedLongLong => "unsigned long long",
+            PrimitiveKind::Int128 => "__int128",
+            PrimitiveKind::UnsignedInt128 => "unsigned __int128",
+            PrimitiveKind::BitInt { width, is_unsigned } => {
+                let sign = if *is_unsigned { "unsigned " } else { "" };
+                return write!(f, "{sign}_BitInt({width})");
+            }
+            PrimitiveKind::Float => "float",
+            PrimitiveKind::Double => "double",
+            PrimitiveKind::LongDouble => "long double",
+            PrimitiveKind::FloatComplex => "float _Complex",
+            PrimitiveKind::DoubleComplex => "double _Complex",
+            PrimitiveKind::LongDoubleComplex => "long double _Complex",
+        })
+    }
+}
+
+/// Spells the keyword, attributes and tag of a struct, union or enum
+/// specifier
+fn tag_to_string(keyword: &str, name: Option<&str>, attributes: &[Attribute<'_>]) -> String {
+    let mut str = format!("{keyword}{}", trailing_attributes(attributes));
+    if let Some(name) = name {
+        str.push_str(&format!(" {name}"));
+    }
+    str
+}
+
 impl Display for Expression<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expression::LiteralString(str) => write!(f, "\"{str}\""),
-            Expression::LiteralChar(_) => todo!(),
-            Expression::LiteralShort(_) => todo!(),
-            Expression::LiteralInt(_) => todo!(),
-            Expression::LiteralLong(_) => todo!(),
-            Expression::LiteralFloat(_) => todo!(),
-            Expression::LiteralDouble(_) => todo!(),
-            Expression::Ident(id) => id.fmt(f),
-            Expression::Prefix(prefix_expr) => todo!(),
+            Expression::LiteralString(literal) => f.write_str(&literal.spelling.join(" ")),
+            Expression::LiteralChar(literal) => f.write_str(literal.spelling),
+            Expression::LiteralShort { value, .. } => write!(f, "{value}"),
+            Expression::LiteralInt { value, .. } => write!(f, "{value}"),
+            Expression::LiteralUInt { value, .. } => write!(f, "{value}u"),
+            Expression::LiteralLong { value, .. } => write!(f, "{value}l"),
+            Expression::LiteralULong { value, .. } => write!(f, "{value}ul"),
+            Expression::LiteralLongLong { value, .. } => write!(f, "{value}ll"),
+            Expression::LiteralULongLong { value, .. } => write!(f, "{value}ull"),
+            Expression::LiteralSize { value, is_unsigned, .. } => {
+                write!(f, "{value}{}z", if *is_unsigned { "u" } else { "" })
+            }
+            Expression::LiteralBitInt { value, is_unsigned, .. } => {
+                write!(f, "{value}{}wb", if *is_unsigned { "u" } else { "" })
+            }
+            Expression::LiteralFloat { value, .. } => write!(f, "{value:?}f"),
+            Expression::LiteralDouble { value, .. } => write!(f, "{value:?}"),
+            Expression::LiteralLongDouble { value, .. } => write!(f, "{value:?}l"),
+            Expression::LiteralBool { value, .. } => write!(f, "{value}"),
+            Expression::Nullptr { .. } => f.write_str("nullptr"),
+            Expression::Ident { name, .. } => name.fmt(f),
+            Expression::Prefix(prefix_expr) => {
+                let val = operand(prefix_expr.val, PREFIX);
+                let op = match &prefix_expr.op {
+                    PreOperator::Pos => "+",
+                    PreOperator::Neg => "-",
+                    PreOperator::Not => "!",
+                    PreOperator::BNot => "~",
+                    PreOperator::Deref => "*",
+                    PreOperator::AddrOf => "&",
+                    PreOperator::Incr => "++",
+                    PreOperator::Decr => "--",
+                    PreOperator::Extension => "__extension__ ",
+                    // `sizeof(a + b)` and `sizeof x`
+                    PreOperator::SizeOf | PreOperator::AlignOf => {
+                        let keyword = match prefix_expr.op {
+                            PreOperator::SizeOf => "sizeof",
+                            _ => "_Alignof",
+                        };
+                        let separator = if val.starts_with('(') { "" } else { " " };
+                        return write!(f, "{keyword}{separator}{val}");
+                    }
+                    PreOperator::Cast(data_type) => return write!(f, "({data_type}){val}"),
+                };
+                // `- -x` rather than `--x`
+                let separator = match (op.chars().last(), val.chars().next()) {
+                    (Some(last), Some(first)) if last == first && matches!(first, '+' | '-' | '&') => " ",
+                    _ => "",
+                };
+                write!(f, "{op}{separator}{val}")
+            }
             Expression::Infix(infix_expr) => infix_expr.fmt(f),
-            Expression::Post(post_expr) => todo!(),
-            Expression::Call(call_expr) => todo!(),
+            Expression::Post(post_expr) => write!(
+                f,
+                "{}{}",
+                operand(post_expr.val, POSTFIX),
+                match post_expr.op {
+                    PostOperator::Incr => "++",
+                    PostOperator::Decr => "--",
+                }
+            ),
+            Expression::Call(call_expr) => {
+                let args: Vec<_> = call_expr.args.iter().map(|arg| operand(arg, ASSIGN)).collect();
+                write!(f, "{}({})", operand(call_expr.val, POSTFIX), args.join(", "))
+            }
+            Expression::Index(index_expr) => {
+                write!(f, "{}[{}]", operand(index_expr.val, POSTFIX), index_expr.index)
+            }
+            Expression::Member(member_expr) => write!(
+                f,
+                "{}{}{}",
+                operand(member_expr.val, POSTFIX),
+                if member_expr.is_arrow { "->" } else { "." },
+                member_expr.member
+            ),
+            Expression::Generic(generic_expr) => {
+                let mut parts = vec![operand(generic_expr.control, ASSIGN)];
+                for association in &generic_expr.associations {
+                    let value = operand(&association.value, ASSIGN);
+                    parts.push(match &association.data_type {
+                        Some(data_type) => format!("{data_type}: {value}"),
+                        None => format!("default: {value}"),
+                    });
+                }
+                write!(f, "_Generic({})", parts.join(", "))
+            }
+            Expression::Conditional(conditional_expr) => write!(
+                f,
+                "{} ? {} : {}",
+                operand(conditional_expr.cond, LOGICAL_OR),
+                conditional_expr.then,
+                operand(conditional_expr.otherwise, CONDITIONAL)
+            ),
+            Expression::Comma(comma_expr) => {
+                write!(f, "{}, {}", comma_expr.left, operand(comma_expr.right, ASSIGN))
+            }
+            Expression::SizeOfType { data_type, .. } => write!(f, "sizeof({data_type})"),
+            Expression::AlignOfType { data_type, .. } => write!(f, "_Alignof({data_type})"),
+            Expression::CompoundLiteral {
+                data_type, initializer, ..
+            } => write!(f, "({data_type}){initializer}"),
+            Expression::Statement { block, .. } => write!(f, "({block})"),
+            Expression::LabelAddr { label, .. } => write!(f, "&&{label}"),
+            Expression::VaArg { list, data_type, .. } => {
+                write!(f, "__builtin_va_arg({}, {data_type})", operand(list, ASSIGN))
+            }
+            Expression::OffsetOf { data_type, member, .. } => {
+                write!(f, "__builtin_offsetof({data_type}, ")?;
+                for (i, designator) in member.iter().enumerate() {
+                    match designator {
+                        // `a.b` rather than `.a.b`
+                        Designator::Member { name, .. } if i == 0 => f.write_str(name)?,
+                        _ => designator.fmt(f)?,
+                    }
+                }
+                f.write_str(")")
+            }
+            Expression::Error { .. } => f.write_str("/* error */"),
         }
     }
 }
 
 impl Display for InfixExpr<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {} {}", self.left, match self.op {
+        let binding = in_op_binding(&self.op);
+        // Assignments are right associative and need a unary expression on
+        // the left
+        let (left, right) = if binding == ASSIGN {
+            (operand(self.left, PREFIX), operand(self.right, ASSIGN))
+        } else {
+            (operand(self.left, binding), operand(self.right, binding + 1))
+        };
+        let op = match self.op {
             InOperator::Add => "+",
             InOperator::Sub => "-",
             InOperator::Mul => "*",
             InOperator::Div => "/",
             InOperator::Mod => "%",
-            InOperator::LSh => todo!(),
-            InOperator::RSh => todo!(),
-            InOperator::BAnd => todo!(),
-            InOperator::BOr => todo!(),
-            InOperator::BXor => todo!(),
-            InOperator::Eq => todo!(),
-            InOperator::Neq => todo!(),
-            InOperator::LT => todo!(),
-            InOperator::GT => todo!(),
-            InOperator::LTE => todo!(),
-            InOperator::GTE => todo!(),
-            InOperator::And => todo!(),
-            InOperator::Or => todo!(),
+            InOperator::LSh => "<<",
+            InOperator::RSh => ">>",
+            InOperator::BAnd => "&",
+            InOperator::BOr => "|",
+            InOperator::BXor => "^",
+            InOperator::Eq => "==",
+            InOperator::Neq => "!=",
+            InOperator::LT => "<",
+            InOperator::GT => ">",
+            InOperator::LTE => "<=",
+            InOperator::GTE => ">=",
+            InOperator::And => "&&",
+            InOperator::Or => "||",
             InOperator::Assign => "=",
-            InOperator::AssignAdd => todo!(),
-            InOperator::AssignSub => todo!(),
-            InOperator::AssignMul => todo!(),
-            InOperator::AssignDiv => todo!(),
-            InOperator::AssignMod => todo!(),
-            InOperator::AssignLsh => todo!(),
-            InOperator::AssignRsh => todo!(),
-            InOperator::AssingBAnd => todo!(),
-            InOperator::AssignBOr => todo!(),
-            InOperator::AssignBXor => todo!(),
-        }, self.right)
+            InOperator::AssignAdd => "+=",
+            InOperator::AssignSub => "-=",
+            InOperator::AssignMul => "*=",
+            InOperator::AssignDiv => "/=",
+            InOperator::AssignMod => "%=",
+            InOperator::AssignLsh => "<<=",
+            InOperator::AssignRsh => ">>=",
+            InOperator::AssingBAnd => "&=",
+            InOperator::AssignBOr => "|=",
+            InOperator::AssignBXor => "^=",
+        };
+        write!(f, "{left} {op} {right}")
+    }
+}
+
+// How tightly expressions bind their operands, from the comma operator to
+// primary expressions
+const COMMA: u8 = 1;
+const ASSIGN: u8 = 2;
+const CONDITIONAL: u8 = 3;
+const LOGICAL_OR: u8 = 4;
+const PREFIX: u8 = 14;
+const POSTFIX: u8 = 15;
+const PRIMARY: u8 = 16;
+
+fn binding(expr: &Expression<'_>) -> u8 {
+    match expr {
+        Expression::Comma(_) => COMMA,
+        Expression::Conditional(_) => CONDITIONAL,
+        Expression::Infix(infix_expr) => in_op_binding(&infix_expr.op),
+        Expression::Prefix(_) | Expression::SizeOfType { .. } | Expression::AlignOfType { .. } => PREFIX,
+        Expression::Post(_)
+        | Expression::Call(_)
+        | Expression::Index(_)
+        | Expression::Member(_)
+        | Expression::CompoundLiteral { .. } => POSTFIX,
+        _ => PRIMARY,
+    }
+}
+
+fn in_op_binding(op: &InOperator) -> u8 {
+    match op {
+        InOperator::Or => LOGICAL_OR,
+        InOperator::And => 5,
+        InOperator::BOr => 6,
+        InOperator::BXor => 7,
+        InOperator::BAnd => 8,
+        InOperator::Eq | InOperator::Neq => 9,
+        InOperator::LT | InOperator::GT | InOperator::LTE | InOperator::GTE => 10,
+        InOperator::LSh | InOperator::RSh => 11,
+        InOperator::Add | InOperator::Sub => 12,
+        InOperator::Mul | InOperator::Div | InOperator::Mod => 13,
+        _ => ASSIGN,
+    }
+}
+
+/// Spells the operand, parenthesized if it binds less tightly than `min`
+fn operand(expr: &Expression<'_>, min: u8) -> String {
+    if binding(expr) < min {
+        format!("({expr})")
+    } else {
+        expr.to_string()
     }
 }
 
 impl Display for VariableStmt<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {} = {};", self.data_type, self.name, self.val.as_ref().unwrap())
+        // Repeating a struct, union or enum definition would redefine it
+        let first = self.declarators.first();
+        if first.is_some_and(|declarator| defines_tag(&declarator.data_type)) {
+            let declarators = self.declarators.iter().map(init_declarator_parts);
+            return write!(f, "{};", single_declaration(&self.specifiers, declarators));
+        }
+        for declarator in &self.declarators {
+            write!(f, "{}{declarator};", self.specifiers)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypedefStmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let first = self.declarators.first();
+        if first.is_some_and(|declarator| defines_tag(&declarator.data_type)) {
+            let declarators = self.declarators.iter().map(|declarator| {
+                let attributes = trailing_attributes(&declarator.attributes);
+                (&declarator.data_type, declarator.name, attributes)
+            });
+            return write!(f, "{};", single_declaration(&self.specifiers, declarators));
+        }
+        for declarator in &self.declarators {
+            write!(f, "{}{declarator};", self.specifiers)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypedefDeclarator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&declarator_to_string(&self.data_type, self.name.to_string()))?;
+        f.write_str(&trailing_attributes(&self.attributes))
+    }
+}
+
+/// Spells declarators sharing their specifiers as one declaration, without
+/// the `;`. Each declarator is given by its type, name and the text after it
+fn single_declaration<'t, 'ast: 't>(
+    specifiers: &DeclSpecifiers<'_>,
+    declarators: impl IntoIterator<Item = (&'t Type<'ast>, &'t str, String)>,
+) -> String {
+    let mut base = None;
+    let declarators: Vec<String> = declarators
+        .into_iter()
+        .map(|(data_type, name, after)| {
+            let (data_type, str) = split_declarator(data_type, name.to_string());
+            base.get_or_insert(data_type);
+            str + &after
+        })
+        .collect();
+    let base = base.map(base_to_string).unwrap_or_default();
+    format!("{specifiers}{base} {}", declarators.join(", "))
+}
+
+/// The parts of an init declarator for [single_declaration]
+fn init_declarator_parts<'t, 'ast>(declarator: &'t InitDeclarator<'ast>) -> (&'t Type<'ast>, &'t str, String) {
+    let mut after = trailing_attributes(&declarator.attributes);
+    if let Some(init) = &declarator.init {
+        after.push_str(&format!(" = {init}"));
+    }
+    (&declarator.data_type, declarator.name, after)
+}
+
+/// Whether the type the declarator derives from defines a struct, union or
+/// enum in place
+fn defines_tag(data_type: &Type<'_>) -> bool {
+    let (base, _) = split_declarator(data_type, String::new());
+    match base {
+        Type::Struct { definition, .. } => definition.is_some(),
+        Type::Union { definition, .. } => definition.is_some(),
+        Type::Enum { definition, .. } => definition.is_some(),
+        _ => false,
+    }
+}
+
+impl Display for InitDeclarator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&declarator_to_string(&self.data_type, self.name.to_string()))?;
+        f.write_str(&trailing_attributes(&self.attributes))?;
+        match &self.init {
+            Some(init) => write!(f, " = {init}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Initializer<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Initializer::Expr(expr) => f.write_str(&operand(expr, ASSIGN)),
+            Initializer::List { items, .. } => {
+                let items: Vec<String> = items.iter().map(InitializerItem::to_string).collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+        }
+    }
+}
+
+impl Display for InitializerItem<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for designator in &self.designators {
+            designator.fmt(f)?;
+        }
+        if !self.designators.is_empty() {
+            f.write_str(" = ")?;
+        }
+        self.value.fmt(f)
+    }
+}
+
+impl Display for Designator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Designator::Member { name, .. } => write!(f, ".{name}"),
+            Designator::Index { index, .. } => write!(f, "[{index}]"),
+            Designator::Range { first, last, .. } => write!(f, "[{first} ... {last}]"),
+        }
     }
 }
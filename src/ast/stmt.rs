@@ -1,6 +1,8 @@
 use std::fmt::Display;
 
-use super::{types::Type, expr::Expression, Ident};
+use crate::{lexer::tokens::Token, span::Span};
+
+use super::{types::{Param, ParamStyle, Type}, expr::{Expression, Initializer, StringLiteral}, Ident};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement<'ast> {
@@ -17,6 +19,8 @@ pub enum Statement<'ast> {
     // Control flow
     If(IfStmt<'ast>),
     Switch(SwitchStmt<'ast>),
+    Case(CaseStmt<'ast>),
+    Default(DefaultStmt<'ast>),
 
     // Loops
     While(WhileStmt<'ast>),
@@ -24,60 +28,147 @@ pub enum Statement<'ast> {
     For(ForStmt<'ast>),
 
     Typedef(TypedefStmt<'ast>),
+    /// `_Static_assert(cond, message);` (C11)
+    StaticAssert(StaticAssertStmt<'ast>),
+    /// Attributes without an entity, like `[[fallthrough]];` (C23)
+    Attribute(AttributeStmt<'ast>),
 
     Return(ReturnStmt<'ast>),
-    Break(BreakStmt<'ast>),
-    Continue(ContinueStmt<'ast>),
+    Break(BreakStmt),
+    Continue(ContinueStmt),
     Goto(GotoStmt<'ast>),
+    /// `goto *target;`, a GNU extension
+    ComputedGoto(ComputedGotoStmt<'ast>),
+    /// `asm(...)`, a GNU extension
+    Asm(AsmStmt<'ast>),
 
     Block(BlockStmt<'ast>),
 
     Expression(Expression<'ast>),
+    Empty(EmptyStmt),
+
+    /// Placeholder for a statement that could not be parsed
+    Error(ErrorStmt),
+}
+
+impl Statement<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Struct(stmt) => stmt.span,
+            Statement::Enum(stmt) => stmt.span,
+            Statement::Union(stmt) => stmt.span,
+            Statement::Label(stmt) => stmt.span,
+            Statement::Function(stmt) => stmt.span,
+            Statement::Variable(stmt) => stmt.span,
+            Statement::If(stmt) => stmt.span,
+            Statement::Switch(stmt) => stmt.span,
+            Statement::Case(stmt) => stmt.span,
+            Statement::Default(stmt) => stmt.span,
+            Statement::While(stmt) => stmt.span,
+            Statement::DoWhile(stmt) => stmt.span,
+            Statement::For(stmt) => stmt.span,
+            Statement::Typedef(stmt) => stmt.span,
+            Statement::StaticAssert(stmt) => stmt.span,
+            Statement::Attribute(stmt) => stmt.span,
+            Statement::Return(stmt) => stmt.span,
+            Statement::Break(stmt) => stmt.span,
+            Statement::Continue(stmt) => stmt.span,
+            Statement::Goto(stmt) => stmt.span,
+            Statement::ComputedGoto(stmt) => stmt.span,
+            Statement::Asm(stmt) => stmt.span,
+            Statement::Block(stmt) => stmt.span,
+            Statement::Expression(expr) => expr.span(),
+            Statement::Empty(stmt) => stmt.span,
+            Statement::Error(stmt) => stmt.span,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct StructStmt<'ast> {
     pub name: Option<Ident<'ast>>,
-    pub fields: Vec<Field<'ast>>,
+    /// Attributes after the keyword or the closing bracket
+    pub attributes: Vec<Attribute<'ast>>,
+    /// `None` for forward declarations like `struct P;`
+    pub fields: Option<Vec<Field<'ast>>>,
+    pub span: Span,
 }
 
+/// Member of a struct or union
 #[derive(Debug, PartialEq, Clone)]
 pub struct Field<'ast> {
-    pub name: Ident<'ast>,
+    /// `None` for anonymous structs and unions and unnamed bit-fields
+    pub name: Option<Ident<'ast>>,
     pub field_type: Type<'ast>,
+    /// Width of a bit-field in bits
+    pub bit_width: Option<Expression<'ast>>,
+    /// Attributes after the declarator
+    pub attributes: Vec<Attribute<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct EnumStmt<'ast> {
     pub name: Option<Ident<'ast>>,
-    pub variants: Vec<Ident<'static>>,
+    /// Attributes after the keyword or the closing bracket
+    pub attributes: Vec<Attribute<'ast>>,
+    /// Fixed underlying type, as in `enum E : uint8_t` (C23)
+    pub underlying_type: Option<Type<'ast>>,
+    /// `None` for forward declarations
+    pub variants: Option<Vec<Enumerator<'ast>>>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Enumerator<'ast> {
+    pub name: Ident<'ast>,
+    pub value: Option<Expression<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct UnionStmt<'ast> {
     pub name: Option<Ident<'ast>>,
-    pub fields: Vec<Field<'ast>>,
+    /// Attributes after the keyword or the closing bracket
+    pub attributes: Vec<Attribute<'ast>>,
+    /// `None` for forward declarations like `union U;`
+    pub fields: Option<Vec<Field<'ast>>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionStmt<'ast> {
     pub name: Ident<'ast>,
-    pub is_volatile: bool,
-    pub should_inline: bool,
-    pub data_storage_class: DataStorageClass,
-    pub args: Vec<Field<'ast>>,
+    pub specifiers: DeclSpecifiers<'ast>,
+    /// Empty for `(void)`
+    pub params: Vec<Param<'ast>>,
+    /// Whether the parameter list ends with `...`
+    pub is_variadic: bool,
+    pub style: ParamStyle,
     pub ret_data_type: Type<'ast>,
+    /// `None` for declarations, `Some` for definitions
     pub body: Option<BlockStmt<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariableStmt<'ast> {
+    pub specifiers: DeclSpecifiers<'ast>,
+    /// The declared names, which share the specifiers
+    pub declarators: Vec<InitDeclarator<'ast>>,
+    pub span: Span,
+}
+
+/// One name declared by a declaration, such as `*b` or `c[3] = {1, 2, 3}`
+/// in `int a, *b, c[3] = {1, 2, 3};`
+#[derive(Debug, PartialEq, Clone)]
+pub struct InitDeclarator<'ast> {
     pub name: Ident<'ast>,
-    pub is_volatile: bool,
-    pub is_const: bool,
-    pub data_storage_class: DataStorageClass,
     pub data_type: Type<'ast>,
-    pub val: Option<Expression<'ast>>,
+    /// Attributes after the declarator
+    pub attributes: Vec<Attribute<'ast>>,
+    pub init: Option<Initializer<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -91,80 +182,241 @@ pub enum IfType {
 pub struct IfStmt<'ast> {
     pub if_type: IfType,
     pub cond: Option<Expression<'ast>>,
-    pub block: BlockStmt<'ast>,
+    /// Any statement, not necessarily a block
+    pub body: &'ast Statement<'ast>,
     pub alt: Option<&'ast IfStmt<'ast>>,
+    pub span: Span,
 }
 
+/// `switch (cond) body`. The `case` and `default` labels are statements
+/// anywhere inside `body`, not necessarily directly in it
 #[derive(Debug, PartialEq, Clone)]
 pub struct SwitchStmt<'ast> {
-    pub comp_val: Expression<'ast>,
-    pub cases: Vec<CaseStmt<'ast>>,
+    pub cond: Expression<'ast>,
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
 }
 
+/// `case value: body`, or `case value ... range_end: body` with GNU
+/// extensions. Execution falls through from `body` to the next statement
 #[derive(Debug, PartialEq, Clone)]
 pub struct CaseStmt<'ast> {
-    pub comp_val: Expression<'ast>,
-    pub block: BlockStmt<'ast>,
+    pub value: Expression<'ast>,
+    pub range_end: Option<Expression<'ast>>,
+    /// The statement the label is attached to
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
+}
+
+/// `default: body`
+#[derive(Debug, PartialEq, Clone)]
+pub struct DefaultStmt<'ast> {
+    /// The statement the label is attached to
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct WhileStmt<'ast> {
     pub cond: Expression<'ast>,
-    pub block: BlockStmt<'ast>,
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct DoWhileStmt<'ast> {
     pub cond: Expression<'ast>,
-    pub block: BlockStmt<'ast>,
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
 }
 
+/// `for (init; cond; increment) body`, where each clause may be empty
 #[derive(Debug, PartialEq, Clone)]
 pub struct ForStmt<'ast> {
-    pub init_stmt: &'ast Statement<'ast>,
-    pub comp_expr: Expression<'ast>,
-    pub update_stmt: &'ast Statement<'ast>,
-    pub block: BlockStmt<'ast>,
+    pub init: Option<ForInit<'ast>>,
+    /// Evaluated before each iteration. The loop is infinite without it
+    pub cond: Option<Expression<'ast>>,
+    /// Evaluated after each iteration
+    pub increment: Option<Expression<'ast>>,
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
+}
+
+/// First clause of a `for` loop
+#[derive(Debug, PartialEq, Clone)]
+pub enum ForInit<'ast> {
+    /// Declaration of variables scoped to the loop (C99)
+    Declaration(VariableStmt<'ast>),
+    Expr(Expression<'ast>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct TypedefStmt<'ast> {
+    /// The `typedef` storage class together with the attributes and other
+    /// specifiers before the declarators
+    pub specifiers: DeclSpecifiers<'ast>,
+    /// The declared names, which share the specifiers
+    pub declarators: Vec<TypedefDeclarator<'ast>>,
+    pub span: Span,
+}
+
+/// One name declared by a typedef, such as `*B` in `typedef int A, *B;`
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypedefDeclarator<'ast> {
     pub name: Ident<'ast>,
-    pub data_type: &'ast Statement<'ast>,
+    pub data_type: Type<'ast>,
+    /// Attributes after the declarator
+    pub attributes: Vec<Attribute<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ReturnStmt<'ast> {
-    pub val: Expression<'ast>,
+    /// `None` for a bare `return;`
+    pub val: Option<Expression<'ast>>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct BreakStmt<'ast> {
-    pub label: Option<Ident<'ast>>,
+pub struct BreakStmt {
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct ContinueStmt<'ast> {
-    pub label: Option<Ident<'ast>>,
+pub struct ContinueStmt {
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct GotoStmt<'ast> {
-    pub label: Option<Ident<'ast>>,
+    pub label: Ident<'ast>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ComputedGotoStmt<'ast> {
+    /// Address of the label to jump to, usually from `&&label`
+    pub target: Expression<'ast>,
+    pub span: Span,
+}
+
+/// `asm qualifiers (template : outputs : inputs : clobbers : labels);`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsmStmt<'ast> {
+    pub is_volatile: bool,
+    pub is_inline: bool,
+    /// `asm goto`, which may jump to one of `labels`
+    pub is_goto: bool,
+    pub template: StringLiteral<'ast>,
+    pub outputs: Vec<AsmOperand<'ast>>,
+    pub inputs: Vec<AsmOperand<'ast>>,
+    /// Registers and `"memory"` that the assembly modifies
+    pub clobbers: Vec<StringLiteral<'ast>>,
+    pub labels: Vec<Ident<'ast>>,
+    pub span: Span,
+}
+
+/// `[name] "constraint" (value)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsmOperand<'ast> {
+    /// Symbolic name the template can refer to as `%[name]`
+    pub name: Option<Ident<'ast>>,
+    pub constraint: StringLiteral<'ast>,
+    pub value: Expression<'ast>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct BlockStmt<'ast> {
     pub block: Vec<Statement<'ast>>,
+    pub span: Span,
 }
 
+/// `name: body`, the target of a `goto`
 #[derive(Debug, PartialEq, Clone)]
 pub struct LabelStmt<'ast> {
     pub name: Ident<'ast>,
+    /// The statement the label is attached to
+    pub body: &'ast Statement<'ast>,
+    pub span: Span,
+}
+
+/// The null statement `;` where a statement is required, e.g. after a label
+#[derive(Debug, PartialEq, Clone)]
+pub struct EmptyStmt {
+    pub span: Span,
+}
+
+/// The tokens skipped while recovering from a syntax error
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorStmt {
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct StaticAssertStmt<'ast> {
+    pub cond: Expression<'ast>,
+    /// Optional since C23
+    pub message: Option<StringLiteral<'ast>>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttributeStmt<'ast> {
+    pub attributes: Vec<Attribute<'ast>>,
+    pub span: Span,
+}
+
+/// Attribute in a `[[...]]` specifier (C23), like `deprecated("use g")` or
+/// `gnu::packed`, or in a GNU `__attribute__((...))` specifier
+#[derive(Debug, PartialEq, Clone)]
+pub struct Attribute<'ast> {
+    /// Given in an `__attribute__((...))` specifier
+    pub is_gnu: bool,
+    /// Vendor namespace before the `::`
+    pub prefix: Option<Ident<'ast>>,
+    pub name: Ident<'ast>,
+    /// Tokens between the parentheses, whose meaning depends on the
+    /// attribute
+    pub args: Option<Vec<Token<'ast>>>,
+    pub span: Span,
+}
+
+/// Declaration specifiers other than the type and its qualifiers, which are
+/// part of the declared types
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeclSpecifiers<'ast> {
+    /// Attributes, which apply to the declared entities
+    pub attributes: Vec<Attribute<'ast>>,
+    /// `__extension__`, which silences pedantic diagnostics about GNU
+    /// extensions in the declaration
+    pub is_extension: bool,
+    pub data_storage_class: DataStorageClass,
+    /// `_Thread_local`, or `thread_local` (C23)
+    pub is_thread_local: bool,
+    /// `constexpr` (C23)
+    pub is_constexpr: bool,
+    pub is_inline: bool,
+    /// `_Noreturn`
+    pub is_noreturn: bool,
+    /// `_Alignas` specifiers, the strictest of which applies
+    pub alignment: Vec<Alignment<'ast>>,
+    pub span: Span,
+}
+
+/// Operand of an `_Alignas` specifier
+#[derive(Debug, PartialEq, Clone)]
+pub enum Alignment<'ast> {
+    /// `_Alignas(constant-expression)`
+    Expr { value: Expression<'ast>, span: Span },
+    /// `_Alignas(type-name)`, the alignment of the type
+    Type { data_type: Type<'ast>, span: Span },
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DataStorageClass {
+    /// `typedef`, which is a storage class in the grammar
+    Typedef,
     Static,
     Extern,
     Register,
@@ -172,6 +424,20 @@ pub enum DataStorageClass {
     None,
 }
 
+impl DataStorageClass {
+    /// The keyword spelling the storage class, empty for [DataStorageClass::None]
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            DataStorageClass::Typedef => "typedef",
+            DataStorageClass::Static => "static",
+            DataStorageClass::Extern => "extern",
+            DataStorageClass::Register => "register",
+            DataStorageClass::Auto => "auto",
+            DataStorageClass::None => "",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CompositeDataType {
     Struct,
@@ -1,24 +1,232 @@
-use super::Ident;
+use std::ops::BitOr;
+
+use crate::span::Span;
+
+use super::{
+    expr::Expression,
+    stmt::{Attribute, EnumStmt, StructStmt, UnionStmt},
+    Ident,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Type<'ast> {
-    /// The regular type
-    Ident(Ident<'ast>),
+    /// Arithmetic types and `void`
+    Primitive {
+        kind: PrimitiveKind,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// Typedef name, or a type name the parser does not know
+    Ident {
+        name: Ident<'ast>,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
     /// Pointer to a type
     Pointer {
         data_type: &'ast Type<'ast>,
-        is_const: bool,
-        is_restricted: bool,
+        /// Qualifiers after the `*`, which apply to the pointer itself
+        qualifiers: Qualifiers,
+        span: Span,
     },
     /// Array of a type
     Array {
         data_type: &'ast Type<'ast>,
-        size: Option<usize>,
-    },
-    /// Struct pointer
-    Struct(Ident<'ast>),
-    /// Union pointer
-    Union(Ident<'ast>),
-    /// Enum Pointer
-    Enum(Ident<'ast>),
+        size: ArraySize<'ast>,
+        /// `static` in the brackets of an array parameter, which guarantees
+        /// at least `size` elements
+        is_static: bool,
+        /// Qualifiers in the brackets of an array parameter, which apply to
+        /// the pointer it is adjusted to
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// Function returning a type
+    Function {
+        return_type: &'ast Type<'ast>,
+        /// Empty for `(void)`
+        params: Vec<Param<'ast>>,
+        /// Whether the parameter list ends with `...`
+        is_variadic: bool,
+        style: ParamStyle,
+        span: Span,
+    },
+    /// Struct, defined in place if `definition` is present
+    Struct {
+        name: Option<Ident<'ast>>,
+        definition: Option<&'ast StructStmt<'ast>>,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// Union, defined in place if `definition` is present
+    Union {
+        name: Option<Ident<'ast>>,
+        definition: Option<&'ast UnionStmt<'ast>>,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// Enum, defined in place if `definition` is present
+    Enum {
+        name: Option<Ident<'ast>>,
+        definition: Option<&'ast EnumStmt<'ast>>,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// `typeof(operand)` or `typeof_unqual(operand)` (C23)
+    TypeOf {
+        operand: TypeOfOperand<'ast>,
+        /// `typeof_unqual`, which drops the qualifiers of the operand type
+        is_unqual: bool,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    /// Type inferred from the initializer of a declaration with `auto` and
+    /// no type specifier (C23)
+    Auto { qualifiers: Qualifiers, span: Span },
+}
+
+impl Type<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Type::Primitive { span, .. }
+            | Type::Ident { span, .. }
+            | Type::Pointer { span, .. }
+            | Type::Array { span, .. }
+            | Type::Function { span, .. }
+            | Type::Struct { span, .. }
+            | Type::Union { span, .. }
+            | Type::Enum { span, .. }
+            | Type::TypeOf { span, .. }
+            | Type::Auto { span, .. } => *span,
+        }
+    }
+
+    /// Qualifiers of the outermost type. Function types are never qualified
+    pub fn qualifiers(&self) -> Qualifiers {
+        match self {
+            Type::Primitive { qualifiers, .. }
+            | Type::Ident { qualifiers, .. }
+            | Type::Pointer { qualifiers, .. }
+            | Type::Array { qualifiers, .. }
+            | Type::Struct { qualifiers, .. }
+            | Type::Union { qualifiers, .. }
+            | Type::Enum { qualifiers, .. }
+            | Type::TypeOf { qualifiers, .. }
+            | Type::Auto { qualifiers, .. } => *qualifiers,
+            Type::Function { .. } => Qualifiers::NONE,
+        }
+    }
+}
+
+/// Operand of `typeof`, whose type is named
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeOfOperand<'ast> {
+    Expr(&'ast Expression<'ast>),
+    Type(&'ast Type<'ast>),
+}
+
+/// Set of the type qualifiers `const`, `volatile`, `restrict` and `_Atomic`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Qualifiers(u8);
+
+impl Qualifiers {
+    pub const NONE: Qualifiers = Qualifiers(0);
+    pub const CONST: Qualifiers = Qualifiers(1);
+    pub const VOLATILE: Qualifiers = Qualifiers(1 << 1);
+    pub const RESTRICT: Qualifiers = Qualifiers(1 << 2);
+    pub const ATOMIC: Qualifiers = Qualifiers(1 << 3);
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, other: Qualifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Adds the qualifiers, returning whether any of them was not present yet
+    pub fn insert(&mut self, other: Qualifiers) -> bool {
+        let is_new = !self.contains(other);
+        self.0 |= other.0;
+        is_new
+    }
+}
+
+impl BitOr for Qualifiers {
+    type Output = Qualifiers;
+
+    fn bitor(self, rhs: Qualifiers) -> Qualifiers {
+        Qualifiers(self.0 | rhs.0)
+    }
+}
+
+/// Canonical form of the type specifiers of a primitive type, which may be
+/// written in any order, e.g. `long unsigned int long`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PrimitiveKind {
+    Void,
+    /// `_Bool`, or `bool` (C23)
+    Bool,
+    Char,
+    SignedChar,
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    /// `_BitInt(width)` (C23)
+    BitInt { width: u16, is_unsigned: bool },
+    /// `__int128`, a GNU extension
+    Int128,
+    UnsignedInt128,
+    Float,
+    Double,
+    LongDouble,
+    FloatComplex,
+    DoubleComplex,
+    LongDoubleComplex,
+}
+
+/// Number of elements between the brackets of an array declarator
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArraySize<'ast> {
+    /// `[]`, an incomplete array
+    Unspecified,
+    /// `[*]`, a variable length array of unspecified size in a prototype
+    Star,
+    /// A constant expression, or any expression for variable length arrays
+    Expr(&'ast Expression<'ast>),
+}
+
+/// How the parameters of a function are declared
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParamStyle {
+    /// Parameter types in the parentheses, `(void)` if there are none. Empty
+    /// parentheses are a prototype too since C23
+    Prototype,
+    /// `()` before C23, which says nothing about the parameters
+    Unspecified,
+    /// Old-style definition like `int f(a, b) int a; char *b; {...}`, whose
+    /// parameter types are declared between the parentheses and the body
+    Identifiers,
+}
+
+/// Parameter of a function type. Parameters of prototypes without a body may
+/// be unnamed
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param<'ast> {
+    pub name: Option<Ident<'ast>>,
+    /// Type as declared
+    pub data_type: Type<'ast>,
+    /// Type of the parameter inside the function. Arrays are adjusted to
+    /// pointers to their elements, qualified by the qualifiers in the
+    /// brackets, and functions to function pointers
+    pub adjusted_type: Type<'ast>,
+    /// Attributes after the declarator
+    pub attributes: Vec<Attribute<'ast>>,
+    pub span: Span,
 }
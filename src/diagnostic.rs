@@ -0,0 +1,272 @@
+use std::fmt::Display;
+
+use colored::Colorize;
+
+use crate::span::{LineIndex, SourceMap, Span};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Stable identifier of a kind of diagnostic
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ErrorCode {
+    // Lexer
+    /// A character that does not start any token
+    UnknownCharacter,
+    /// A block comment that is not closed before the end of the input
+    UnterminatedComment,
+
+    // Preprocessor
+    /// A malformed or unknown preprocessing directive
+    InvalidDirective,
+    /// A file named by `#include` that could not be found or read
+    IncludeNotFound,
+    /// A macro defined again with a different replacement list
+    MacroRedefined,
+    /// A function-like macro invoked with the wrong arguments
+    InvalidMacroArguments,
+    /// `##` that does not form a valid token
+    InvalidPaste,
+    /// An `#if` without `#endif`, or a conditional directive without `#if`
+    UnbalancedConditional,
+    /// An invalid `#if` or `#elif` expression
+    InvalidConditionExpression,
+    /// Emitted by `#error`
+    ErrorDirective,
+    /// Emitted by `#warning`
+    WarningDirective,
+
+    // Parser
+    /// A token that does not fit the grammar at this position
+    UnexpectedToken,
+    /// The input ended in the middle of a construct
+    UnexpectedEof,
+    ExpectedExpression,
+    ExpectedType,
+    ExpectedIdentifier,
+    /// A literal whose spelling or value is invalid
+    InvalidLiteral,
+    /// A specifier or qualifier that was given more than once
+    DuplicateSpecifier,
+    /// The input nests deeper than the parser is willing to recurse
+    NestingTooDeep,
+    /// A preprocessor directive the parser skipped over
+    IgnoredDirective,
+    /// A language extension that is not enabled in the parser options
+    ExtensionDisabled,
+    /// A statement that is not allowed where it appears, like `case`
+    /// outside of a `switch`
+    MisplacedStatement,
+    /// Type specifiers that do not name a type together, like `short long`
+    InvalidTypeSpecifiers,
+    /// A construct that is newer than the C standard selected in the parser
+    /// options
+    UnavailableInStandard,
+    /// A malformed parameter list, like `void` next to other parameters
+    InvalidParameters,
+    /// Specifiers that cannot appear together, like `static extern`
+    ConflictingSpecifiers,
+    /// A construct the parser does not support yet
+    Unsupported,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnknownCharacter => "E0001",
+            ErrorCode::UnterminatedComment => "E0002",
+            ErrorCode::InvalidDirective => "E0050",
+            ErrorCode::IncludeNotFound => "E0051",
+            ErrorCode::MacroRedefined => "E0052",
+            ErrorCode::InvalidMacroArguments => "E0053",
+            ErrorCode::InvalidPaste => "E0054",
+            ErrorCode::UnbalancedConditional => "E0055",
+            ErrorCode::InvalidConditionExpression => "E0056",
+            ErrorCode::ErrorDirective => "E0057",
+            ErrorCode::WarningDirective => "E0058",
+            ErrorCode::UnexpectedToken => "E0100",
+            ErrorCode::UnexpectedEof => "E0101",
+            ErrorCode::ExpectedExpression => "E0102",
+            ErrorCode::ExpectedType => "E0103",
+            ErrorCode::ExpectedIdentifier => "E0104",
+            ErrorCode::InvalidLiteral => "E0105",
+            ErrorCode::DuplicateSpecifier => "E0106",
+            ErrorCode::NestingTooDeep => "E0107",
+            ErrorCode::IgnoredDirective => "E0108",
+            ErrorCode::ExtensionDisabled => "E0109",
+            ErrorCode::MisplacedStatement => "E0110",
+            ErrorCode::InvalidTypeSpecifiers => "E0111",
+            ErrorCode::UnavailableInStandard => "E0112",
+            ErrorCode::InvalidParameters => "E0113",
+            ErrorCode::ConflictingSpecifiers => "E0114",
+            ErrorCode::Unsupported => "E0199",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error, warning or note produced while lexing or parsing
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+    /// Descriptions of the tokens or constructs that would have been valid
+    pub expected: Vec<String>,
+    /// Description of what was encountered instead
+    pub found: Option<String>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: ErrorCode, severity: Severity, span: Span, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity,
+            span,
+            message: message.into(),
+            expected: Vec::new(),
+            found: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: ErrorCode, span: Span, message: impl Into<String>) -> Self {
+        Self::new(code, Severity::Error, span, message)
+    }
+
+    pub fn warning(code: ErrorCode, span: Span, message: impl Into<String>) -> Self {
+        Self::new(code, Severity::Warning, span, message)
+    }
+
+    pub fn with_expected(mut self, expected: impl Into<String>) -> Self {
+        self.expected.push(expected.into());
+        self
+    }
+
+    pub fn with_found(mut self, found: impl Into<String>) -> Self {
+        self.found = Some(found.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Adds notes naming the macro expansions and `#include` directives that
+    /// produced the text at the span of the diagnostic
+    pub fn with_provenance(mut self, map: &SourceMap) -> Self {
+        for expansion in map.expansion_chain(self.span) {
+            let note = match map.location(expansion.definition) {
+                Some(location) => format!("in expansion of macro `{}` defined at {location}", expansion.name),
+                None => format!("in expansion of macro `{}`", expansion.name),
+            };
+            self.notes.push(note);
+        }
+        let expanded = map.expansion_span(self.span);
+        for include in map.include_chain(expanded.file) {
+            if let Some(location) = map.location(include) {
+                self.notes.push(format!("in file included from {location}"));
+            }
+        }
+        self
+    }
+
+    /// Renders the diagnostic together with the offending source line
+    pub fn render(&self, src: &str) -> String {
+        self.render_source(src, "")
+    }
+
+    /// Renders the diagnostic with the source line taken from the file the
+    /// span points into
+    pub fn render_with(&self, map: &SourceMap) -> String {
+        match map.file(self.span.file) {
+            Some(file) => self.render_source(file.src, &format!("{}:", file.name)),
+            None => self.render_source("", ""),
+        }
+    }
+
+    fn render_source(&self, src: &str, file_prefix: &str) -> String {
+        let index = LineIndex::new(src);
+        let start = index.line_col(self.span.start);
+        let gutter = start.line.to_string().len();
+        let severity = match self.severity {
+            Severity::Note => self.severity.to_string().cyan(),
+            Severity::Warning => self.severity.to_string().yellow(),
+            Severity::Error => self.severity.to_string().red(),
+        };
+
+        let mut out = format!("{severity}[{}]: {}\n", self.code, self.message);
+        out.push_str(&format!(
+            "{:gutter$}{} {file_prefix}{}:{}\n",
+            "",
+            "-->".blue(),
+            start.line,
+            start.col
+        ));
+        if let Some(line_span) = index.line_span(start.line) {
+            let line = line_span.source_text(src).trim_end_matches('\r');
+            let underline_len = self
+                .span
+                .end
+                .min(line_span.end)
+                .saturating_sub(self.span.start)
+                .max(1);
+            out.push_str(&format!("{:gutter$} {}\n", "", "|".blue()));
+            out.push_str(&format!("{} {} {line}\n", start.line, "|".blue()));
+            out.push_str(&format!(
+                "{:gutter$} {} {:pad$}{}\n",
+                "",
+                "|".blue(),
+                "",
+                "^".repeat(underline_len).red(),
+                pad = start.col - 1
+            ));
+        }
+        if !self.expected.is_empty() {
+            out.push_str(&format!(
+                "{:gutter$} = expected: {}\n",
+                "",
+                self.expected.join(", ")
+            ));
+        }
+        if let Some(found) = &self.found {
+            out.push_str(&format!("{:gutter$} = found: {found}\n", ""));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("{:gutter$} = note: {note}\n", ""));
+        }
+        out
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
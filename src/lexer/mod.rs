@@ -1,27 +1,284 @@
+use std::borrow::Cow;
+
 use logos::Logos;
 
-use self::tokens::Token;
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode},
+    span::{FileId, LineIndex, SourceMap, Span},
+};
+
+use self::tokens::{SpannedToken, Token};
 
 pub mod tokens;
 
 pub struct Lexer<'a> {
     pub input: &'a str,
-    pub tokens: Vec<Token<'a>>,
+    pub tokens: Vec<SpannedToken<'a>>,
+    /// Errors encountered while lexing. Invalid input is skipped
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files and macro expansions the tokens come from, set by the
+    /// preprocessor
+    pub source_map: Option<SourceMap<'a>>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
-        let tokens = Token::lexer(input)
-            .map(|tok| match tok {
-                Ok(Token::LitString(str)) => Token::LitString(trim_str_tok(str)),
+        Self::with_file(input, FileId::MAIN)
+    }
+
+    /// Lexes `input`, which is the source text of `file`
+    pub fn with_file(input: &'a str, file: FileId) -> Self {
+        let mut tokens = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut prev_end = 0;
+        for (tok, span) in Token::lexer(input).spanned() {
+            let span = Span::in_file(span.start, span.end, file);
+            let tok = match tok {
                 Ok(tok) => tok,
-                Err(_) => panic!(),
-            })
-            .collect();
-        Self { input, tokens }
+                // Terminated block comments are skipped, so this one is not
+                Err(_) if span.source_text(input).starts_with("/*") => {
+                    diagnostics.push(Diagnostic::error(
+                        ErrorCode::UnterminatedComment,
+                        Span::in_file(span.start, span.start + 2, file),
+                        "unterminated block comment",
+                    ));
+                    break;
+                }
+                Err(_) => {
+                    diagnostics.push(
+                        Diagnostic::error(
+                            ErrorCode::UnknownCharacter,
+                            span,
+                            format!("unknown character `{}`", span.source_text(input)),
+                        )
+                        .with_found(format!("`{}`", span.source_text(input))),
+                    );
+                    continue;
+                }
+            };
+            let gap = &input[prev_end..span.start];
+            tokens.push(SpannedToken {
+                tok,
+                span,
+                first_on_line: tokens.is_empty() || gap_has_newline(gap),
+                leading_space: !unsplice(gap).is_empty(),
+            });
+            prev_end = span.end;
+        }
+        let tokens = glue_spliced_tokens(input, tokens);
+
+        Self {
+            input,
+            tokens,
+            diagnostics,
+            source_map: None,
+        }
+    }
+
+    /// Builds a [LineIndex] for the lexed input
+    pub fn line_index(&self) -> LineIndex {
+        LineIndex::new(self.input)
+    }
+}
+
+/// Removes line splices (a backslash directly followed by a line break)
+pub fn unsplice(str: &str) -> Cow<'_, str> {
+    if !str.contains('\\') {
+        return Cow::Borrowed(str);
+    }
+    let mut out = String::with_capacity(str.len());
+    let mut rest = str;
+    while let Some(index) = rest.find('\\') {
+        out.push_str(&rest[..index]);
+        match strip_splice(&rest[index..]) {
+            Some(after) => rest = after,
+            None => {
+                out.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
     }
+    out.push_str(rest);
+    Cow::Owned(out)
 }
 
-fn trim_str_tok(str: &str) -> &str {
-    &str[1..str.len()-1]
+fn strip_splice(str: &str) -> Option<&str> {
+    let after = str.strip_prefix('\\')?;
+    after
+        .strip_prefix('\n')
+        .or_else(|| after.strip_prefix("\r\n"))
+}
+
+/// Whether the whitespace and comments between two tokens contain a line
+/// break that is neither spliced nor inside a block comment
+fn gap_has_newline(gap: &str) -> bool {
+    let mut rest = gap;
+    while let Some(char) = rest.chars().next() {
+        if let Some(comment) = rest.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => rest = &comment[end + 2..],
+                None => return false,
+            }
+        } else if rest.starts_with("//") {
+            // The comment runs up to the first line break that is not spliced
+            let mut chars = rest.char_indices().peekable();
+            let mut end = rest.len();
+            while let Some((index, char)) = chars.next() {
+                match char {
+                    '\\' => {
+                        if let Some(after) = strip_splice(&rest[index..]) {
+                            let skip = rest.len() - after.len();
+                            while chars.peek().is_some_and(|(next, _)| *next < skip) {
+                                chars.next();
+                            }
+                        }
+                    }
+                    '\n' => {
+                        end = index;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+            return end < rest.len();
+        } else if let Some(after) = strip_splice(rest) {
+            rest = after;
+        } else if char == '\n' {
+            return true;
+        } else {
+            rest = &rest[char.len_utf8()..];
+        }
+    }
+    false
+}
+
+/// Glues tokens that are only separated by line splices back together, if
+/// their spelling without the splices forms a single token. Glued literals
+/// and identifiers keep their spelling including the splices, see [unsplice]
+fn glue_spliced_tokens<'a>(input: &'a str, tokens: Vec<SpannedToken<'a>>) -> Vec<SpannedToken<'a>> {
+    let only_splices = |prev: &SpannedToken, next: &SpannedToken| {
+        let gap = &input[prev.span.end..next.span.start];
+        !gap.is_empty() && unsplice(gap).is_empty()
+    };
+    if !tokens.windows(2).any(|toks| only_splices(&toks[0], &toks[1])) {
+        return tokens;
+    }
+
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        let mut last = index;
+        while last + 1 < tokens.len() && only_splices(&tokens[last], &tokens[last + 1]) {
+            last += 1;
+        }
+        if last > index {
+            let span = tokens[index].span.to(tokens[last].span);
+            let raw = span.source_text(input);
+            if let Some(tok) = lex_single(raw) {
+                out.push(SpannedToken {
+                    tok,
+                    span,
+                    ..tokens[index]
+                });
+                index = last + 1;
+                continue;
+            }
+        }
+        out.extend_from_slice(&tokens[index..=last]);
+        index = last + 1;
+    }
+    out
+}
+
+/// Lexes `raw` without its line splices. Returns the token if it forms
+/// exactly one, with any text pointing into `raw`
+fn lex_single(raw: &str) -> Option<Token<'_>> {
+    let cleaned = unsplice(raw);
+    let mut lexer = Token::lexer(&cleaned);
+    let tok = lexer.next()?.ok()?;
+    if lexer.span() != (0..cleaned.len()) || lexer.next().is_some() {
+        return None;
+    }
+    Some(match tok {
+        Token::LitString(_) => Token::LitString(raw),
+        Token::LitInt(_) => Token::LitInt(raw),
+        Token::LitFloat(_) => Token::LitFloat(raw),
+        Token::LitChar(_) => Token::LitChar(raw),
+        Token::Ident(_) => Token::Ident(raw),
+        Token::Auto => Token::Auto,
+        Token::Const => Token::Const,
+        Token::Static => Token::Static,
+        Token::Register => Token::Register,
+        Token::Volatile => Token::Volatile,
+        Token::Restrict => Token::Restrict,
+        Token::Inline => Token::Inline,
+        Token::Signed => Token::Signed,
+        Token::Unsigned => Token::Unsigned,
+        Token::Break => Token::Break,
+        Token::Continue => Token::Continue,
+        Token::Goto => Token::Goto,
+        Token::Return => Token::Return,
+        Token::Enum => Token::Enum,
+        Token::Struct => Token::Struct,
+        Token::Union => Token::Union,
+        Token::If => Token::If,
+        Token::Else => Token::Else,
+        Token::Do => Token::Do,
+        Token::For => Token::For,
+        Token::While => Token::While,
+        Token::Switch => Token::Switch,
+        Token::Case => Token::Case,
+        Token::Default => Token::Default,
+        Token::Extern => Token::Extern,
+        Token::Sizeof => Token::Sizeof,
+        Token::Typedef => Token::Typedef,
+        Token::Assign => Token::Assign,
+        Token::AssignAdd => Token::AssignAdd,
+        Token::AssignSub => Token::AssignSub,
+        Token::AssignMul => Token::AssignMul,
+        Token::AssignDiv => Token::AssignDiv,
+        Token::AssignMod => Token::AssignMod,
+        Token::AssignBAnd => Token::AssignBAnd,
+        Token::AssignBOr => Token::AssignBOr,
+        Token::AssignXor => Token::AssignXor,
+        Token::AssignLSh => Token::AssignLSh,
+        Token::AssignRSh => Token::AssignRSh,
+        Token::Equals => Token::Equals,
+        Token::NEquals => Token::NEquals,
+        Token::LTEquals => Token::LTEquals,
+        Token::GTEquals => Token::GTEquals,
+        Token::LessThan => Token::LessThan,
+        Token::GreaterThan => Token::GreaterThan,
+        Token::ExclamMark => Token::ExclamMark,
+        Token::And => Token::And,
+        Token::Or => Token::Or,
+        Token::BOr => Token::BOr,
+        Token::XOr => Token::XOr,
+        Token::BNot => Token::BNot,
+        Token::LeftShift => Token::LeftShift,
+        Token::RightShift => Token::RightShift,
+        Token::Plus => Token::Plus,
+        Token::Minus => Token::Minus,
+        Token::Divide => Token::Divide,
+        Token::Mod => Token::Mod,
+        Token::Increment => Token::Increment,
+        Token::Decrement => Token::Decrement,
+        Token::Comma => Token::Comma,
+        Token::Semicolon => Token::Semicolon,
+        Token::Dot => Token::Dot,
+        Token::Ellipsis => Token::Ellipsis,
+        Token::Arrow => Token::Arrow,
+        Token::Ampersand => Token::Ampersand,
+        Token::Asterisk => Token::Asterisk,
+        Token::QuestionMark => Token::QuestionMark,
+        Token::Colon => Token::Colon,
+        Token::LSquare => Token::LSquare,
+        Token::RSquare => Token::RSquare,
+        Token::LParent => Token::LParent,
+        Token::RParent => Token::RParent,
+        Token::LCurly => Token::LCurly,
+        Token::RCurly => Token::RCurly,
+        Token::Hash => Token::Hash,
+        Token::HashHash => Token::HashHash,
+    })
 }
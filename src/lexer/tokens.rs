@@ -1,7 +1,18 @@
+use std::fmt::Display;
+
 use logos::Logos;
 
+use crate::span::Span;
+
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Logos)]
-#[logos(skip r#"(?:\/\/[^\n]*|\t|\s|\f|\n)*"#)]
+// Whitespace
+#[logos(skip r"\s+")]
+// Line splices (translation phase 2) between tokens
+#[logos(skip r"\\\r?\n")]
+// Line comments, which continue onto the next line after a line splice
+#[logos(skip r"//(?:[^\n\\]|\\\r?\n|\\[^\n])*")]
+// Block comments
+#[logos(skip r"/\*[^*]*\*+(?:[^/*][^*]*\*+)*/")]
 pub enum Token<'a> {
     // Keywords
 
@@ -78,13 +89,20 @@ pub enum Token<'a> {
     Typedef,
 
     // Literals
-    #[regex(r#""(?:\\.|[^\\"])*""#)]
+    /// String literal including its encoding prefix and quotes
+    #[regex(r#"(?:u8|[uUL])?"(?:\\(?:.|\r?\n)|[^\\"\n])*""#)]
     LitString(&'a str),
-    #[regex(r"-?[0-9]+")]
+    /// Decimal, octal, hexadecimal or binary integer with an optional suffix,
+    /// which is validated by the parser. Digits may be separated by `'`
+    #[regex(r"(?:[0-9](?:'?[0-9])*|0[xX][0-9a-fA-F](?:'?[0-9a-fA-F])*|0[bB][01](?:'?[01])*)[a-zA-Z_0-9]*")]
     LitInt(&'a str),
-    #[regex("-?[0-9]+\\.[0-9]+")]
+    /// Decimal or hexadecimal floating constant with an optional suffix
+    #[regex(r"(?:[0-9](?:'?[0-9])*\.(?:[0-9](?:'?[0-9])*)?|\.[0-9](?:'?[0-9])*)(?:[eE][+-]?[0-9](?:'?[0-9])*)?[a-zA-Z_0-9]*", priority = 5)]
+    #[regex(r"[0-9](?:'?[0-9])*[eE][+-]?[0-9](?:'?[0-9])*[a-zA-Z_0-9]*", priority = 5)]
+    #[regex(r"0[xX](?:[0-9a-fA-F](?:'?[0-9a-fA-F])*)?(?:\.(?:[0-9a-fA-F](?:'?[0-9a-fA-F])*)?)?[pP][+-]?[0-9](?:'?[0-9])*[a-zA-Z_0-9]*", priority = 5)]
     LitFloat(&'a str),
-    #[regex(r#"'[^\\']'"#)]
+    /// Character constant including its encoding prefix and quotes
+    #[regex(r#"(?:u8|[uUL])?'(?:\\(?:.|\r?\n)|[^\\'\n])*'"#)]
     LitChar(&'a str),
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*")]
     Ident(&'a str),
@@ -110,9 +128,9 @@ pub enum Token<'a> {
     AssignBOr,
     #[token("^=")]
     AssignXor,
-    #[token(">>=")]
-    AssignLSh,
     #[token("<<=")]
+    AssignLSh,
+    #[token(">>=")]
     AssignRSh,
 
     // Comparison
@@ -179,6 +197,8 @@ pub enum Token<'a> {
     Semicolon,
     #[token(".")]
     Dot,
+    #[token("...")]
+    Ellipsis,
     #[token("->")]
     Arrow,
     #[token("&")]
@@ -190,17 +210,142 @@ pub enum Token<'a> {
     #[token(":")]
     Colon,
 
-    // Brackets
+    // Brackets, including their digraph spellings
     #[token("[")]
+    #[token("<:")]
     LSquare,
     #[token("]")]
+    #[token(":>")]
     RSquare,
     #[token("(")]
     LParent,
     #[token(")")]
     RParent,
     #[token("{")]
+    #[token("<%")]
     LCurly,
     #[token("}")]
+    #[token("%>")]
     RCurly,
+
+    // Preprocessor
+    /// Symbol: #
+    #[token("#")]
+    #[token("%:")]
+    Hash,
+    /// Symbol: ##
+    #[token("##")]
+    #[token("%:%:")]
+    HashHash,
+}
+
+/// A token together with its location in the source text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub tok: Token<'a>,
+    /// Where the token is spelled. For tokens produced by a macro,
+    /// `span.expansion` leads to the invocation, see
+    /// [SourceMap::expansion_chain](crate::span::SourceMap::expansion_chain)
+    pub span: Span,
+    /// Whether this is the first token of a logical source line, i.e. no
+    /// other token precedes it on the line once line splices are removed.
+    /// A `#` with this flag starts a preprocessing directive
+    pub first_on_line: bool,
+    /// Whether whitespace or a comment precedes this token
+    pub leading_space: bool,
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'a> Token<'a> {
+    /// Spelling of the token. Literals and identifiers are spelled as
+    /// written, digraphs are spelled as the punctuator they stand for
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Token::LitString(str)
+            | Token::LitInt(str)
+            | Token::LitFloat(str)
+            | Token::LitChar(str)
+            | Token::Ident(str) => str,
+            Token::Auto => "auto",
+            Token::Const => "const",
+            Token::Static => "static",
+            Token::Register => "register",
+            Token::Volatile => "volatile",
+            Token::Restrict => "restrict",
+            Token::Inline => "inline",
+            Token::Signed => "signed",
+            Token::Unsigned => "unsigned",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Goto => "goto",
+            Token::Return => "return",
+            Token::Enum => "enum",
+            Token::Struct => "struct",
+            Token::Union => "union",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::Do => "do",
+            Token::For => "for",
+            Token::While => "while",
+            Token::Switch => "switch",
+            Token::Case => "case",
+            Token::Default => "default",
+            Token::Extern => "extern",
+            Token::Sizeof => "sizeof",
+            Token::Typedef => "typedef",
+            Token::Assign => "=",
+            Token::AssignAdd => "+=",
+            Token::AssignSub => "-=",
+            Token::AssignMul => "*=",
+            Token::AssignDiv => "/=",
+            Token::AssignMod => "%=",
+            Token::AssignBAnd => "&=",
+            Token::AssignBOr => "|=",
+            Token::AssignXor => "^=",
+            Token::AssignLSh => "<<=",
+            Token::AssignRSh => ">>=",
+            Token::Equals => "==",
+            Token::NEquals => "!=",
+            Token::LTEquals => "<=",
+            Token::GTEquals => ">=",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::ExclamMark => "!",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::BOr => "|",
+            Token::XOr => "^",
+            Token::BNot => "~",
+            Token::LeftShift => "<<",
+            Token::RightShift => ">>",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Divide => "/",
+            Token::Mod => "%",
+            Token::Increment => "++",
+            Token::Decrement => "--",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Dot => ".",
+            Token::Ellipsis => "...",
+            Token::Arrow => "->",
+            Token::Ampersand => "&",
+            Token::Asterisk => "*",
+            Token::QuestionMark => "?",
+            Token::Colon => ":",
+            Token::LSquare => "[",
+            Token::RSquare => "]",
+            Token::LParent => "(",
+            Token::RParent => ")",
+            Token::LCurly => "{",
+            Token::RCurly => "}",
+            Token::Hash => "#",
+            Token::HashHash => "##",
+        }
+    }
 }
@@ -1,8 +1,13 @@
 use ast::stmt::Statement;
 
 pub mod ast;
+pub mod diagnostic;
 pub mod lexer;
 pub mod parser;
+pub mod preprocessor;
+pub mod span;
+pub mod translation_unit;
+#[cfg(test)]
 mod tests;
 
 pub fn ast_to_string(ast: Vec<Statement<'_>>) -> String {
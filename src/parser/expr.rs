@@ -1,16 +1,16 @@
-use std::collections::HashMap;
-
 use crate::{
     ast::expr::{
-        CallExpr, Expression, InOperator, InfixExpr, PostExpr, PostOperator, PreOperator,
-        PrefixExpr,
+        CallExpr, CommaExpr, ConditionalExpr, Designator, Expression, GenericAssociation, GenericExpr, InOperator,
+        IndexExpr, InfixExpr, Initializer, InitializerItem, MemberExpr, PostExpr, PostOperator, PreOperator, PrefixExpr,
     },
-    expect_tok,
+    ast::types::Type,
+    diagnostic::ErrorCode,
     lexer::tokens::Token,
     parser_error,
+    span::Span,
 };
 
-use super::Parser;
+use super::{CStandard, Parser};
 
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -33,22 +33,27 @@ pub(super) enum Precedence {
     Postfix,
 }
 
-#[repr(u8)]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub(super) enum PrecedencePos {
-    Pre,
-    Post,
-}
-
 impl<'a, 's: 'a> Parser<'a, 's> {
     pub(super) fn parse_expr(&mut self, prec: Precedence) -> Option<Expression<'a>> {
+        if !self.enter_nested() {
+            return None;
+        }
+        let expr = self.parse_expr_inner(prec);
+        self.leave_nested();
+        expr
+    }
+
+    fn parse_expr_inner(&mut self, prec: Precedence) -> Option<Expression<'a>> {
         let prefix = self.parse_prefix();
+        self.parse_operators(prefix, prec)
+    }
 
-        let mut left_expression = prefix;
+    /// Parses the operators binding tighter than `prec` that follow the
+    /// already parsed `left_expression`
+    fn parse_operators(&mut self, left_expression: Option<Expression<'a>>, prec: Precedence) -> Option<Expression<'a>> {
+        let mut left_expression = left_expression;
 
-        while !self.peek_is_end()
-            && prec < self.get_precedence(self.peek_tok()?, PrecedencePos::Post)
-        {
+        while !self.peek_is_end() && prec < self.get_precedence(self.peek_tok()?) {
             self.next_tok();
             left_expression = self.parse_infix(left_expression?);
         }
@@ -57,27 +62,71 @@ impl<'a, 's: 'a> Parser<'a, 's> {
     }
 
     fn parse_prefix(&mut self) -> Option<Expression<'a>> {
-        match self.cur_tok()? {
-            Token::LitString(str) => Some(Expression::LiteralString(*str)),
-            Token::LitInt(int) => Some(Expression::LiteralInt(int.parse().unwrap())),
-            Token::LitFloat(float) => Some(Expression::LiteralFloat(float.parse().unwrap())),
-            Token::LitChar(char) => Some(Expression::LiteralChar(char.parse().unwrap())),
-            Token::Ident(ident) => Some(Expression::Ident(*ident)),
-            Token::BOr => todo!(),
-            Token::XOr => todo!(),
-            Token::BNot
-            | Token::Sizeof
-            | Token::ExclamMark
-            | Token::Plus
-            | Token::Asterisk
-            | Token::Ampersand
-            | Token::LParent
-            | Token::Increment
-            | Token::Decrement
-            | Token::Minus => self.parse_prefix_expr(),
-            tok => {
-                parser_error!("Cannot parse expression or statement from: {tok:?}");
-                panic!()
+        let span = self.cur_span();
+        match self.cur_tok() {
+            Some(Token::LitString(_)) => self.parse_string_literal(),
+            Some(Token::LitInt(int)) => {
+                let int = *int;
+                self.parse_int_literal(int, span)
+            }
+            Some(Token::LitFloat(float)) => {
+                let float = *float;
+                self.parse_float_literal(float, span)
+            }
+            Some(Token::LitChar(char)) => {
+                let char = *char;
+                self.parse_char_literal(char, span)
+            }
+            Some(Token::Ident(name @ ("_Alignof" | "alignof" | "__alignof__"))) if self.is_keyword(name) => {
+                self.parse_alignof()
+            }
+            Some(Token::Ident("__extension__")) => {
+                self.require_gnu(span, "`__extension__`");
+                let val = self.parse_next_expr(Precedence::Prefix, "after `__extension__`");
+                Some(Expression::Prefix(PrefixExpr {
+                    op: PreOperator::Extension,
+                    val: self.arena.alloc(val),
+                    span: span.to(self.cur_span()),
+                }))
+            }
+            Some(Token::Ident("__builtin_va_arg")) => self.parse_va_arg(),
+            Some(Token::Ident("__builtin_offsetof")) => self.parse_offsetof(),
+            Some(Token::Ident("_Generic")) => self.parse_generic(),
+            Some(Token::Ident(name @ ("true" | "false"))) if self.is_keyword(name) => Some(Expression::LiteralBool {
+                value: *name == "true",
+                span,
+            }),
+            Some(Token::Ident("nullptr")) if self.is_keyword("nullptr") => Some(Expression::Nullptr { span }),
+            Some(Token::Ident(name)) => Some(Expression::Ident { name, span }),
+            Some(Token::And) => self.parse_label_addr(),
+            Some(Token::LParent) if self.peek_tok() == Some(&Token::LCurly) => self.parse_stmt_expr(),
+            // A cast or compound literal if a type name follows
+            Some(Token::LParent) if self.starts_type_name(self.tok_index + 1) => self.parse_cast_expr(),
+            Some(Token::LParent) => self.parse_grouped_expr(),
+            Some(
+                Token::BNot
+                | Token::Sizeof
+                | Token::ExclamMark
+                | Token::Plus
+                | Token::Asterisk
+                | Token::Ampersand
+                | Token::Increment
+                | Token::Decrement
+                | Token::Minus,
+            ) => self.parse_prefix_expr(),
+            Some(tok) => {
+                let tok = *tok;
+                parser_error!(
+                    self,
+                    ErrorCode::ExpectedExpression,
+                    span,
+                    "expected expression, found `{tok}`"
+                );
+                None
+            }
+            None => {
+                self.unexpected_eof(&["expression"], "");
+                None
             }
         }
     }
@@ -86,107 +135,523 @@ impl<'a, 's: 'a> Parser<'a, 's> {
         let args = self.parse_call_args()?;
         self.next_tok();
         Some(CallExpr {
+            span: left.span().to(self.cur_span()),
             val: self.arena.alloc(left),
             args,
         })
     }
 
+    /// Cur token is the left parenthesis of `({`. Ends on the right
+    /// parenthesis
+    fn parse_stmt_expr(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        self.require_gnu(start, "statement expressions");
+        self.next_tok();
+        let block = self.parse_block(Token::RCurly)?;
+        if !self.expect_peek(Token::RParent, "to close statement expression") {
+            return None;
+        }
+        Some(Expression::Statement {
+            block,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is `&&`. Ends on the label
+    fn parse_label_addr(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        self.require_gnu(start, "label addresses");
+        let Some(Token::Ident(label)) = self.peek_tok() else {
+            self.unexpected_peek(&["label"], "after `&&`");
+            return None;
+        };
+        let label = *label;
+        self.next_tok();
+        Some(Expression::LabelAddr {
+            label,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is `__builtin_va_arg`. Ends on the right parenthesis
+    fn parse_va_arg(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        self.require_gnu(start, "`__builtin_va_arg`");
+        if !self.expect_peek(Token::LParent, "after `__builtin_va_arg`") {
+            return None;
+        }
+        let list = self.parse_next_expr(Precedence::Comma, "as argument list");
+        if !self.expect_peek(Token::Comma, "after argument list") {
+            return None;
+        }
+        self.next_tok();
+        let data_type = self.parse_type()?;
+        if !self.expect_peek(Token::RParent, "to close `__builtin_va_arg`") {
+            return None;
+        }
+        Some(Expression::VaArg {
+            list: self.arena.alloc(list),
+            data_type,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is `__builtin_offsetof`. Ends on the right parenthesis
+    fn parse_offsetof(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        self.require_gnu(start, "`__builtin_offsetof`");
+        if !self.expect_peek(Token::LParent, "after `__builtin_offsetof`") {
+            return None;
+        }
+        self.next_tok();
+        let data_type = self.parse_type()?;
+        if !self.expect_peek(Token::Comma, "after type in `__builtin_offsetof`") {
+            return None;
+        }
+        let mut member = Vec::new();
+        // The path starts with a member name without a `.`
+        self.next_tok();
+        let name = self.expect_cur_ident("as member in `__builtin_offsetof`")?;
+        member.push(Designator::Member {
+            name,
+            span: self.cur_span(),
+        });
+        loop {
+            let designator_start = self.peek_span();
+            match self.peek_tok() {
+                Some(Token::Dot) => {
+                    self.next_tok();
+                    self.next_tok();
+                    let name = self.expect_cur_ident("after `.` in `__builtin_offsetof`")?;
+                    member.push(Designator::Member {
+                        name,
+                        span: designator_start.to(self.cur_span()),
+                    });
+                }
+                Some(Token::LSquare) => {
+                    self.next_tok();
+                    let index = self.parse_next_expr(Precedence::Lowest, "as array index");
+                    if !self.expect_peek(Token::RSquare, "after array index") {
+                        return None;
+                    }
+                    member.push(Designator::Index {
+                        index,
+                        span: designator_start.to(self.cur_span()),
+                    });
+                }
+                _ => break,
+            }
+        }
+        if !self.expect_peek(Token::RParent, "to close `__builtin_offsetof`") {
+            return None;
+        }
+        Some(Expression::OffsetOf {
+            data_type,
+            member,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is the left parenthesis. Ends on the right parenthesis
+    fn parse_grouped_expr(&mut self) -> Option<Expression<'a>> {
+        let expr = self.parse_next_expr(Precedence::Lowest, "in parentheses");
+        if !self.expect_peek(Token::RParent, "to close `(`") {
+            return None;
+        }
+        Some(expr)
+    }
+
+    /// Cur token is the left parenthesis. Ends on the last token before the
+    /// right parenthesis
     fn parse_call_args(&mut self) -> Option<Vec<Expression<'a>>> {
         let mut args = Vec::new();
+        if let Some(Token::RParent) = self.peek_tok() {
+            return Some(args);
+        }
         loop {
-            self.next_tok();
-            let expr = self.parse_expr(Precedence::Lowest)?;
+            let expr = self.parse_next_expr(Precedence::Comma, "as function call argument");
             args.push(expr);
-            match self.peek_tok()? {
-                Token::Comma => {
+            match self.peek_tok() {
+                Some(Token::Comma) => {
                     self.next_tok();
                 }
-                Token::RParent => return Some(args),
-                tok => parser_error!(
-                    "Encountered invalid token after function call parameter: {tok:?}"
-                ),
+                Some(Token::RParent) => return Some(args),
+                _ => {
+                    self.unexpected_peek(&["`,`", "`)`"], "after function call argument");
+                    return None;
+                }
             }
         }
     }
 
     fn parse_infix(&mut self, left_expr: Expression<'a>) -> Option<Expression<'a>> {
         match self.cur_tok()? {
-            Token::Equals
-            | Token::Plus
-            | Token::Minus
-            | Token::Asterisk
-            | Token::Divide
-            | Token::LessThan
-            | Token::GreaterThan
-            | Token::LTEquals
-            | Token::GTEquals => self.parse_infix_expr(left_expr),
             Token::LParent => Some(Expression::Call(self.parse_call_expr(left_expr)?)),
-            // Token::LSquare => self.parse_index_expr(left),
             Token::Increment => Some(Expression::Post(PostExpr {
+                span: left_expr.span().to(self.cur_span()),
                 val: self.arena.alloc(left_expr),
                 op: PostOperator::Incr,
             })),
             Token::Decrement => Some(Expression::Post(PostExpr {
+                span: left_expr.span().to(self.cur_span()),
                 val: self.arena.alloc(left_expr),
                 op: PostOperator::Decr,
             })),
-            _ => panic!("Invalid for parsing an infix expr: {:#?}", left_expr),
+            Token::LSquare => self.parse_index_expr(left_expr),
+            Token::Dot | Token::Arrow => self.parse_member_expr(left_expr),
+            Token::QuestionMark => self.parse_conditional_expr(left_expr),
+            Token::Comma => {
+                let right = self.parse_next_expr(Precedence::Comma, "after `,`");
+                Some(Expression::Comma(CommaExpr {
+                    span: left_expr.span().to(right.span()),
+                    left: self.arena.alloc(left_expr),
+                    right: self.arena.alloc(right),
+                }))
+            }
+            tok if Self::tok_to_in_op(tok).is_some() => self.parse_infix_expr(left_expr),
+            tok => {
+                let tok = *tok;
+                self.unsupported(self.cur_span(), &format!("the `{tok}` operator"))
+            }
         }
     }
 
     fn parse_infix_expr(&mut self, left_expr: Expression<'a>) -> Option<Expression<'a>> {
         let op = Self::tok_to_in_op(self.cur_tok()?)?;
-        let prec = self.get_precedence(self.cur_tok()?, PrecedencePos::Post);
-        self.next_tok();
-        let right_expr = self.parse_expr(prec)?;
+        let tok = *self.cur_tok()?;
+        let prec = match self.get_precedence(&tok) {
+            // Right associative, `a = b = c` assigns `c` to `b` first
+            Precedence::Assign => Precedence::Comma,
+            prec => prec,
+        };
+        let right_expr = self.parse_next_expr(prec, &format!("after `{tok}`"));
         Some(Expression::Infix(InfixExpr {
+            span: left_expr.span().to(right_expr.span()),
             left: self.arena.alloc(left_expr),
             right: self.arena.alloc(right_expr),
             op,
         }))
     }
 
+    /// Cur token is the left square bracket. Ends on the right square bracket
+    fn parse_index_expr(&mut self, left_expr: Expression<'a>) -> Option<Expression<'a>> {
+        let index = self.parse_next_expr(Precedence::Lowest, "as array index");
+        if !self.expect_peek(Token::RSquare, "to close `[`") {
+            return None;
+        }
+        Some(Expression::Index(IndexExpr {
+            span: left_expr.span().to(self.cur_span()),
+            val: self.arena.alloc(left_expr),
+            index: self.arena.alloc(index),
+        }))
+    }
+
+    /// Cur token is the `.` or `->`. Ends on the member name
+    fn parse_member_expr(&mut self, left_expr: Expression<'a>) -> Option<Expression<'a>> {
+        let tok = *self.cur_tok()?;
+        let Some(Token::Ident(member)) = self.peek_tok() else {
+            self.unexpected_peek(&["identifier"], &format!("after `{tok}`"));
+            return None;
+        };
+        let member = *member;
+        self.next_tok();
+        Some(Expression::Member(MemberExpr {
+            span: left_expr.span().to(self.cur_span()),
+            val: self.arena.alloc(left_expr),
+            member,
+            is_arrow: tok == Token::Arrow,
+        }))
+    }
+
+    /// Cur token is the `?`. Ends on the last token of the expression after
+    /// the `:`
+    fn parse_conditional_expr(&mut self, left_expr: Expression<'a>) -> Option<Expression<'a>> {
+        let then = self.parse_next_expr(Precedence::Lowest, "after `?`");
+        if !self.expect_peek(Token::Colon, "in conditional expression") {
+            return None;
+        }
+        // Right associative, `a ? b : c ? d : e` nests in the last operand
+        let otherwise = self.parse_next_expr(Precedence::Assign, "after `:`");
+        Some(Expression::Conditional(ConditionalExpr {
+            span: left_expr.span().to(otherwise.span()),
+            cond: self.arena.alloc(left_expr),
+            then: self.arena.alloc(then),
+            otherwise: self.arena.alloc(otherwise),
+        }))
+    }
+
     fn parse_prefix_expr(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        if self.cur_tok() == Some(&Token::Sizeof)
+            && self.peek_tok() == Some(&Token::LParent)
+            && self.starts_type_name(self.tok_index + 2)
+        {
+            return self.parse_sizeof_type();
+        }
         let op = match self.cur_tok()? {
             Token::Plus => PreOperator::Pos,
             Token::Minus => PreOperator::Neg,
             Token::ExclamMark => PreOperator::Not,
             Token::BNot => PreOperator::BNot,
             Token::Asterisk => PreOperator::Deref,
-            Token::Sizeof => self.parse_sizeof_expr()?,
+            Token::Sizeof => PreOperator::SizeOf,
             Token::Ampersand => PreOperator::AddrOf,
-            // AlignOf
-            Token::LParent => self.parse_cast_expr()?,
             Token::Increment => PreOperator::Incr,
             Token::Decrement => PreOperator::Decr,
-            other => panic!("Expected operator, got: {other:?} instead"),
+            _ => {
+                self.unexpected_cur(&["prefix operator"], "");
+                return None;
+            }
         };
+        let val = self.parse_next_expr(Precedence::Prefix, "as operand");
+        Some(Expression::Prefix(PrefixExpr {
+            op,
+            val: self.arena.alloc(val),
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the sizeof keyword, followed by a parenthesized type name.
+    /// Ends on the right parenthesis, or on the last token of the operand if
+    /// the type starts a compound literal
+    fn parse_sizeof_type(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
         self.next_tok();
-        let val = self.arena.alloc(self.parse_expr(Precedence::Prefix)?);
-        op.end_expr(self);
-        Some(Expression::Prefix(PrefixExpr { op, val }))
+        let paren = self.cur_span();
+        let data_type = self.parse_parenthesized_type("after type in `sizeof`")?;
+        if self.peek_tok() != Some(&Token::LCurly) {
+            return Some(Expression::SizeOfType {
+                data_type,
+                span: start.to(self.cur_span()),
+            });
+        }
+        // `sizeof (int[]){1, 2}` is the size of the compound literal
+        let literal = self.parse_compound_literal(paren, data_type);
+        let val = self.parse_operators(literal, Precedence::Prefix)?;
+        Some(Expression::Prefix(PrefixExpr {
+            op: PreOperator::SizeOf,
+            val: self.arena.alloc(val),
+            span: start.to(self.cur_span()),
+        }))
     }
 
-    // Cur token is sizeof keyword
-    fn parse_sizeof_expr(&mut self) -> Option<PreOperator<'a>> {
+    /// Cur token is `_Alignof`. Ends on the right parenthesis after the type
+    /// name, or on the last token of the operand
+    fn parse_alignof(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        let keyword = *self.cur_tok()?;
+        match keyword {
+            Token::Ident("_Alignof") => self.require_standard(CStandard::C11, start, "`_Alignof`"),
+            Token::Ident("__alignof__") => self.require_gnu(start, "`__alignof__`"),
+            _ => (),
+        }
+        if self.peek_tok() == Some(&Token::LParent) && self.starts_type_name(self.tok_index + 2) {
+            self.next_tok();
+            let data_type = self.parse_parenthesized_type(&format!("after type in `{keyword}`"))?;
+            return Some(Expression::AlignOfType {
+                data_type,
+                span: start.to(self.cur_span()),
+            });
+        }
+        if keyword != Token::Ident("__alignof__") {
+            self.require_gnu(start, &format!("`{keyword}` applied to an expression"));
+        }
+        let val = self.parse_next_expr(Precedence::Prefix, "as operand");
+        Some(Expression::Prefix(PrefixExpr {
+            op: PreOperator::AlignOf,
+            val: self.arena.alloc(val),
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the left parenthesis before a type name. Ends on the last
+    /// token of the cast operand or of the compound literal
+    fn parse_cast_expr(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        let data_type = self.parse_parenthesized_type("after type for cast")?;
+        if self.peek_tok() == Some(&Token::LCurly) {
+            return self.parse_compound_literal(start, data_type);
+        }
+        let val = self.parse_next_expr(Precedence::Prefix, "as operand of cast");
+        Some(Expression::Prefix(PrefixExpr {
+            op: PreOperator::Cast(data_type),
+            val: self.arena.alloc(val),
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the left parenthesis. Ends on the right parenthesis
+    fn parse_parenthesized_type(&mut self, context: &str) -> Option<Type<'a>> {
         self.next_tok();
-        Some(PreOperator::SizeOf)
+        let data_type = self.parse_type()?;
+        if !self.expect_peek(Token::RParent, context) {
+            return None;
+        }
+        Some(data_type)
     }
 
-    /// Cur token is a left parenthesis
-    fn parse_cast_expr(&mut self) -> Option<PreOperator<'a>> {
+    /// Cur token is `_Generic`. Ends on the right parenthesis
+    fn parse_generic(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        self.require_standard(CStandard::C11, start, "`_Generic`");
+        if !self.expect_peek(Token::LParent, "after `_Generic`") {
+            return None;
+        }
+        let control = self.parse_next_expr(Precedence::Comma, "as generic selection operand");
+        let mut associations = Vec::new();
+        while self.peek_tok() == Some(&Token::Comma) {
+            self.next_tok();
+            let association_start = self.peek_span();
+            let data_type = match self.peek_tok() {
+                Some(Token::Default) => {
+                    self.next_tok();
+                    None
+                }
+                _ if self.starts_type_name(self.tok_index + 1) => {
+                    self.next_tok();
+                    Some(self.parse_type()?)
+                }
+                _ => {
+                    self.unexpected_peek(&["type name", "`default`"], "in generic association");
+                    return None;
+                }
+            };
+            if !self.expect_peek(Token::Colon, "after generic association type") {
+                return None;
+            }
+            let value = self.parse_next_expr(Precedence::Comma, "as generic association value");
+            associations.push(GenericAssociation {
+                data_type,
+                value,
+                span: association_start.to(self.cur_span()),
+            });
+        }
+        if associations.is_empty() {
+            self.unexpected_peek(&["`,`"], "after generic selection operand");
+            return None;
+        }
+        if !self.expect_peek(Token::RParent, "to close generic selection") {
+            return None;
+        }
+        Some(Expression::Generic(GenericExpr {
+            control: self.arena.alloc(control),
+            associations,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the right parenthesis after the type name. Ends on the
+    /// right bracket
+    fn parse_compound_literal(&mut self, start: Span, data_type: Type<'a>) -> Option<Expression<'a>> {
+        self.require_standard(CStandard::C99, start, "compound literals");
+        let initializer = self.parse_initializer("as compound literal")?;
+        Some(Expression::CompoundLiteral {
+            data_type,
+            initializer: self.arena.alloc(initializer),
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is the token before the initializer. Ends on its last token
+    pub(super) fn parse_initializer(&mut self, context: &str) -> Option<Initializer<'a>> {
+        if self.peek_tok() != Some(&Token::LCurly) {
+            return Some(Initializer::Expr(self.parse_next_expr(Precedence::Comma, context)));
+        }
         self.next_tok();
-        let _type = self.parse_type()?;
+        if !self.enter_nested() {
+            return None;
+        }
+        let list = self.parse_initializer_list();
+        self.leave_nested();
+        list
+    }
+
+    /// Cur token is the left bracket. Ends on the right bracket
+    fn parse_initializer_list(&mut self) -> Option<Initializer<'a>> {
+        let start = self.cur_span();
+        let mut items = Vec::new();
+        while self.peek_tok() != Some(&Token::RCurly) {
+            let item_start = self.peek_span();
+            let designators = self.parse_designators()?;
+            if !designators.is_empty() {
+                self.require_standard(CStandard::C99, item_start.to(self.cur_span()), "designated initializers");
+            }
+            let value = self.parse_initializer("in initializer list")?;
+            items.push(InitializerItem {
+                designators,
+                value,
+                span: item_start.to(self.cur_span()),
+            });
+            match self.peek_tok() {
+                // Trailing commas are allowed
+                Some(Token::Comma) => self.next_tok(),
+                Some(Token::RCurly) => break,
+                _ => {
+                    self.unexpected_peek(&["`,`", "`}`"], "in initializer list");
+                    return None;
+                }
+            }
+        }
         self.next_tok();
-        if expect_tok!(self.peek_tok()?, Token::RParent, |tok| {
-            parser_error!(
-                "Expected right parenthesis after type for cast, received token: {:#?} instead",
-                tok
-            )
-        }) {
-            self.next_tok();
+        if items.is_empty() {
+            self.require_standard(CStandard::C23, start.to(self.cur_span()), "empty initializers");
         }
-        Some(PreOperator::Cast(_type))
+        Some(Initializer::List {
+            items,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is the token before the first designator. Ends on the `=`
+    /// after the designators, if there are any
+    fn parse_designators(&mut self) -> Option<Vec<Designator<'a>>> {
+        let mut designators = Vec::new();
+        loop {
+            let start = self.peek_span();
+            match self.peek_tok() {
+                Some(Token::Dot) => {
+                    self.next_tok();
+                    let Some(Token::Ident(name)) = self.peek_tok() else {
+                        self.unexpected_peek(&["identifier"], "after `.` in designator");
+                        return None;
+                    };
+                    let name = *name;
+                    self.next_tok();
+                    designators.push(Designator::Member {
+                        name,
+                        span: start.to(self.cur_span()),
+                    });
+                }
+                Some(Token::LSquare) => {
+                    self.next_tok();
+                    let index = self.parse_next_expr(Precedence::Comma, "as array designator");
+                    let last = if self.peek_tok() == Some(&Token::Ellipsis) {
+                        self.next_tok();
+                        self.require_gnu(self.cur_span(), "designator ranges");
+                        Some(self.parse_next_expr(Precedence::Comma, "as end of designator range"))
+                    } else {
+                        None
+                    };
+                    if !self.expect_peek(Token::RSquare, "after array designator") {
+                        return None;
+                    }
+                    let span = start.to(self.cur_span());
+                    designators.push(match last {
+                        Some(last) => Designator::Range {
+                            first: index,
+                            last,
+                            span,
+                        },
+                        None => Designator::Index { index, span },
+                    });
+                }
+                _ => break,
+            }
+        }
+        if !designators.is_empty() && !self.expect_peek(Token::Assign, "after designator") {
+            return None;
+        }
+        Some(designators)
     }
 
     fn tok_to_in_op(tok: &Token) -> Option<InOperator> {
@@ -208,7 +673,7 @@ impl<'a, 's: 'a> Parser<'a, 's> {
             Token::GTEquals => Some(InOperator::GTE),
             Token::LessThan => Some(InOperator::LT),
             Token::GreaterThan => Some(InOperator::GT),
-            Token::And => Some(InOperator::Add),
+            Token::And => Some(InOperator::And),
             Token::Or => Some(InOperator::Or),
             Token::BOr => Some(InOperator::BOr),
             Token::XOr => Some(InOperator::BXor),
@@ -220,48 +685,40 @@ impl<'a, 's: 'a> Parser<'a, 's> {
             Token::Asterisk => Some(InOperator::Mul),
             Token::Divide => Some(InOperator::Div),
             Token::Mod => Some(InOperator::Mod),
-            _ => todo!(),
+            _ => None,
         }
     }
 
-    fn get_precedence(&self, token: &Token<'a>, pos: PrecedencePos) -> Precedence {
-        match pos {
-            PrecedencePos::Pre => match token {
-                Token::Increment | Token::Decrement | Token::Plus| Token::Minus | Token::ExclamMark | Token::BNot | Token::LParent | Token::Asterisk | Token::Ampersand | Token::Sizeof /* | _Alignof */  => Precedence::Prefix,
-                _ => Precedence::Lowest,
-            },
-            PrecedencePos::Post => match token {
-                Token::Asterisk | Token::Divide | Token::Mod => Precedence::Mul,
-                Token::Plus | Token::Minus => Precedence::Add,
-                Token::GreaterThan | Token::GTEquals | Token::LessThan | Token::LTEquals => {
-                    Precedence::Relational
-                }
-                Token::Equals | Token::NEquals => Precedence::Equals,
-                Token::Ampersand => Precedence::BAnd,
-                Token::XOr => Precedence::BXor,
-                Token::BOr => Precedence::BOr,
-                Token::And => Precedence::And,
-                Token::Or => Precedence::Or,
-                Token::QuestionMark | Token::Colon => Precedence::Ternary,
-                Token::Assign
-                | Token::AssignAdd
-                | Token::AssignSub
-                | Token::AssignMul
-                | Token::AssignDiv
-                | Token::AssignMod
-                | Token::AssignLSh
-                | Token::AssignRSh
-                | Token::AssignBAnd
-                | Token::AssignXor
-                | Token::AssignBOr => Precedence::Assign,
-                Token::Increment
-                | Token::Decrement
-                | Token::LParent
-                | Token::LSquare
-                | Token::Dot
-                | Token::Arrow => Precedence::Postfix,
-                _ => Precedence::Lowest,
-            },
+    /// Binding power of `token` as an infix or postfix operator
+    fn get_precedence(&self, token: &Token<'a>) -> Precedence {
+        match token {
+            Token::Comma => Precedence::Comma,
+            Token::Asterisk | Token::Divide | Token::Mod => Precedence::Mul,
+            Token::Plus | Token::Minus => Precedence::Add,
+            Token::LeftShift | Token::RightShift => Precedence::Shift,
+            Token::GreaterThan | Token::GTEquals | Token::LessThan | Token::LTEquals => Precedence::Relational,
+            Token::Equals | Token::NEquals => Precedence::Equals,
+            Token::Ampersand => Precedence::BAnd,
+            Token::XOr => Precedence::BXor,
+            Token::BOr => Precedence::BOr,
+            Token::And => Precedence::And,
+            Token::Or => Precedence::Or,
+            Token::QuestionMark => Precedence::Ternary,
+            Token::Assign
+            | Token::AssignAdd
+            | Token::AssignSub
+            | Token::AssignMul
+            | Token::AssignDiv
+            | Token::AssignMod
+            | Token::AssignLSh
+            | Token::AssignRSh
+            | Token::AssignBAnd
+            | Token::AssignXor
+            | Token::AssignBOr => Precedence::Assign,
+            Token::Increment | Token::Decrement | Token::LParent | Token::LSquare | Token::Dot | Token::Arrow => {
+                Precedence::Postfix
+            }
+            _ => Precedence::Lowest,
         }
     }
 }
@@ -0,0 +1,93 @@
+//! Entry points for parsing a single expression, type name, declaration or
+//! statement instead of a whole translation unit
+
+use bumpalo::Bump;
+
+use crate::{
+    ast::{expr::Expression, stmt::{EmptyStmt, Statement}, types::Type},
+    lexer::{tokens::Token, Lexer},
+};
+
+use super::{expr::Precedence, ParseResult, Parser, ParserOptions};
+
+/// Parses `src` as an expression, which may contain the comma operator.
+/// `typedefs` are the names that denote types, e.g. in casts
+pub fn parse_expression<'a>(
+    src: &'a str,
+    arena: &'a Bump,
+    options: ParserOptions,
+    typedefs: &[&str],
+) -> ParseResult<Option<Expression<'a>>> {
+    parse_fragment(src, arena, options, typedefs, "expression", |parser| {
+        parser.parse_expr(Precedence::Lowest)
+    })
+}
+
+/// Parses `src` as a type name like `const char *[4]`, which has no
+/// declared identifier
+pub fn parse_type_name<'a>(
+    src: &'a str,
+    arena: &'a Bump,
+    options: ParserOptions,
+    typedefs: &[&str],
+) -> ParseResult<Option<Type<'a>>> {
+    parse_fragment(src, arena, options, typedefs, "type name", Parser::parse_type)
+}
+
+/// Parses `src` as a declaration including its `;`, or as a function
+/// definition
+pub fn parse_declaration<'a>(
+    src: &'a str,
+    arena: &'a Bump,
+    options: ParserOptions,
+    typedefs: &[&str],
+) -> ParseResult<Option<Statement<'a>>> {
+    parse_fragment(src, arena, options, typedefs, "declaration", |parser| {
+        match parser.cur_tok() {
+            Some(Token::Ident(name @ ("_Static_assert" | "static_assert"))) if parser.is_keyword(name) => {
+                parser.parse_static_assert()
+            }
+            _ => parser.parse_declaration(),
+        }
+    })
+}
+
+/// Parses `src` as a single statement, which may be a declaration
+pub fn parse_statement<'a>(
+    src: &'a str,
+    arena: &'a Bump,
+    options: ParserOptions,
+    typedefs: &[&str],
+) -> ParseResult<Option<Statement<'a>>> {
+    parse_fragment(src, arena, options, typedefs, "statement", |parser| match parser.cur_tok() {
+        // `parse_stmt` skips null statements
+        Some(Token::Semicolon) => Some(Statement::Empty(EmptyStmt {
+            span: parser.cur_span(),
+        })),
+        _ => parser.parse_stmt(),
+    })
+}
+
+/// Parses all of `src` with `parse`, which starts on the first token and
+/// ends on the last token of the fragment. Tokens after it are reported
+fn parse_fragment<'a, T>(
+    src: &'a str,
+    arena: &'a Bump,
+    options: ParserOptions,
+    typedefs: &[&str],
+    what: &str,
+    parse: impl FnOnce(&mut Parser<'a, 'a>) -> Option<T>,
+) -> ParseResult<Option<T>> {
+    let mut parser = Parser::with_options(Lexer::new(src), arena, options);
+    parser.declare_typedefs(typedefs.iter().copied());
+    let ast = if parser.cur_tok().is_none() {
+        parser.unexpected_eof(&[what], "");
+        None
+    } else {
+        parse(&mut parser)
+    };
+    if ast.is_some() && parser.peek_tok().is_some() {
+        parser.unexpected_peek(&["end of input"], &format!("after {what}"));
+    }
+    parser.finish(ast)
+}
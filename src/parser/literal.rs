@@ -0,0 +1,565 @@
+use crate::{
+    ast::expr::{CharLiteral, Encoding, Expression, StringLiteral},
+    diagnostic::ErrorCode,
+    lexer::tokens::Token,
+    parser_error, parser_warn,
+    span::Span,
+};
+
+use super::{CStandard, Parser};
+
+/// Type of an integer constant. The parser assumes an LP64 data model:
+/// `int` has 32 bits, `long` and `long long` have 64 bits
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum IntType {
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+}
+
+impl IntType {
+    fn max(self) -> u128 {
+        match self {
+            IntType::Int => i32::MAX as u128,
+            IntType::UInt => u32::MAX as u128,
+            IntType::Long | IntType::LongLong => i64::MAX as u128,
+            IntType::ULong | IntType::ULongLong => u64::MAX as u128,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum IntWidth {
+    Default,
+    Long,
+    LongLong,
+    Size,
+    BitInt,
+}
+
+/// Value and suffix of an integer literal
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct IntLiteral {
+    pub(crate) value: u128,
+    is_decimal: bool,
+    pub(crate) is_unsigned: bool,
+    width: IntWidth,
+}
+
+impl<'a, 's: 'a> Parser<'a, 's> {
+    /// Builds the literal expression of the type C gives the integer constant
+    pub(super) fn parse_int_literal(&mut self, spelling: &str, span: Span) -> Option<Expression<'a>> {
+        let literal = self.parse_int_value(spelling, span)?;
+        let IntLiteral { value, .. } = literal;
+        match literal.width {
+            IntWidth::Size => {
+                let max = match literal.is_unsigned {
+                    true => u64::MAX as u128,
+                    false => i64::MAX as u128,
+                };
+                if value > max {
+                    return self.literal_too_large(spelling, span);
+                }
+                return Some(Expression::LiteralSize {
+                    value: value as u64,
+                    is_unsigned: literal.is_unsigned,
+                    span,
+                });
+            }
+            IntWidth::BitInt => {
+                let bits = (u128::BITS - value.leading_zeros()) as u16;
+                // A signed `_BitInt` needs a sign bit and at least two bits
+                let width = match literal.is_unsigned {
+                    true => bits.max(1),
+                    false => (bits + 1).max(2),
+                };
+                return Some(Expression::LiteralBitInt {
+                    value,
+                    width,
+                    is_unsigned: literal.is_unsigned,
+                    span,
+                });
+            }
+            _ => (),
+        }
+
+        let int_type = match literal
+            .candidates()
+            .iter()
+            .copied()
+            .find(|int_type| value <= int_type.max())
+        {
+            Some(int_type) => int_type,
+            // Decimal constants without `u` are only tried as signed types,
+            // but compilers fall back to the largest unsigned type
+            None if literal.is_decimal && !literal.is_unsigned && value <= IntType::ULongLong.max() => {
+                parser_warn!(
+                    self,
+                    ErrorCode::InvalidLiteral,
+                    span,
+                    "integer literal `{spelling}` is too large for a signed type and is treated as `unsigned long long`"
+                );
+                IntType::ULongLong
+            }
+            None => return self.literal_too_large(spelling, span),
+        };
+        Some(match int_type {
+            IntType::Int => Expression::LiteralInt { value: value as i32, span },
+            IntType::UInt => Expression::LiteralUInt { value: value as u32, span },
+            IntType::Long => Expression::LiteralLong { value: value as i64, span },
+            IntType::ULong => Expression::LiteralULong { value: value as u64, span },
+            IntType::LongLong => Expression::LiteralLongLong { value: value as i64, span },
+            IntType::ULongLong => Expression::LiteralULongLong { value: value as u64, span },
+        })
+    }
+
+    /// Parses the digits and suffix of an integer literal, reporting an
+    /// invalid spelling
+    pub(super) fn parse_int_value(&mut self, spelling: &str, span: Span) -> Option<IntLiteral> {
+        self.require_literal_standard(spelling, span);
+        match int_literal(spelling) {
+            Ok(literal) => Some(literal),
+            Err(message) => {
+                parser_error!(self, ErrorCode::InvalidLiteral, span, "{message}");
+                None
+            }
+        }
+    }
+
+    /// Reports the spellings of numeric literals added in C23
+    fn require_literal_standard(&mut self, spelling: &str, span: Span) {
+        if spelling.starts_with("0b") || spelling.starts_with("0B") {
+            self.require_standard(CStandard::C23, span, "binary literals");
+        }
+        if spelling.contains('\'') {
+            self.require_standard(CStandard::C23, span, "digit separators");
+        }
+    }
+
+    fn literal_too_large<T>(&mut self, spelling: &str, span: Span) -> Option<T> {
+        parser_error!(self, ErrorCode::InvalidLiteral, span, "{}", too_large(spelling));
+        None
+    }
+
+    /// Builds the literal expression for a decimal or hexadecimal floating
+    /// constant
+    pub(super) fn parse_float_literal(&mut self, spelling: &str, span: Span) -> Option<Expression<'a>> {
+        self.require_literal_standard(spelling, span);
+        let text = spelling.replace('\'', "");
+        let is_hex = text.starts_with("0x") || text.starts_with("0X");
+        // Suffixes contain no digits and the exponent is decimal
+        let end = text
+            .rfind(|char: char| char.is_ascii_digit() || char == '.')
+            .map_or(0, |end| end + 1);
+        let (number, suffix) = text.split_at(end);
+
+        let value = match is_hex {
+            true => parse_hex_float(&number[2..]),
+            false => number.parse::<f64>().ok(),
+        };
+        let Some(value) = value else {
+            parser_error!(
+                self,
+                ErrorCode::InvalidLiteral,
+                span,
+                "invalid floating literal `{spelling}`"
+            );
+            return None;
+        };
+        let expr = match suffix {
+            "" => Expression::LiteralDouble { value, span },
+            "f" | "F" => {
+                // Parse again to round to single precision only once
+                let value = match is_hex {
+                    true => value as f32,
+                    false => number.parse().unwrap_or(value as f32),
+                };
+                Expression::LiteralFloat { value, span }
+            }
+            "l" | "L" => Expression::LiteralLongDouble { value, span },
+            "df" | "dd" | "dl" | "DF" | "DD" | "DL" => {
+                return self.unsupported(span, "decimal floating literals");
+            }
+            _ => {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidLiteral,
+                    span,
+                    "invalid suffix `{suffix}` on floating literal `{spelling}`"
+                );
+                return None;
+            }
+        };
+        let is_infinite = match expr {
+            Expression::LiteralFloat { value, .. } => value.is_infinite(),
+            _ => value.is_infinite(),
+        };
+        if is_infinite {
+            parser_warn!(
+                self,
+                ErrorCode::InvalidLiteral,
+                span,
+                "floating literal `{spelling}` is too large for its type"
+            );
+        }
+        Some(expr)
+    }
+}
+
+impl<'a, 's: 'a> Parser<'a, 's> {
+    /// Cur token is the first string literal. Concatenates it with the ones
+    /// directly following it and ends on the last of them
+    pub(super) fn parse_string_literal(&mut self) -> Option<Expression<'a>> {
+        let start = self.cur_span();
+        let mut spelling = Vec::new();
+        let mut encoding = Encoding::Plain;
+        while let Some(Token::LitString(str)) = self.cur_tok() {
+            let str = *str;
+            let (piece_encoding, _) = split_encoding(str);
+            if piece_encoding != Encoding::Plain {
+                if encoding != Encoding::Plain && encoding != piece_encoding {
+                    parser_error!(
+                        self,
+                        ErrorCode::InvalidLiteral,
+                        self.cur_span(),
+                        "cannot concatenate `{}` and `{}` string literals",
+                        encoding.prefix(),
+                        piece_encoding.prefix()
+                    );
+                }
+                encoding = piece_encoding;
+            }
+            spelling.push(str);
+            if !matches!(self.peek_tok(), Some(Token::LitString(_))) {
+                break;
+            }
+            self.next_tok();
+        }
+        let span = start.to(self.cur_span());
+
+        // Escape sequences are decoded per literal, before concatenation
+        let mut value = Vec::new();
+        for str in &spelling {
+            let (_, text) = split_encoding(str);
+            value.extend(self.decode_escapes(&text[1..text.len() - 1], encoding, span)?);
+        }
+        Some(Expression::LiteralString(StringLiteral {
+            encoding,
+            spelling: self.arena.alloc_slice_copy(&spelling),
+            value: self.arena.alloc_slice_copy(&value),
+            span,
+        }))
+    }
+
+    pub(super) fn parse_char_literal(&mut self, spelling: &'a str, span: Span) -> Option<Expression<'a>> {
+        let mut warnings = Vec::new();
+        let result = char_literal(spelling, &mut warnings);
+        let (encoding, value) = self.report_literal(result, warnings, span)?;
+        Some(Expression::LiteralChar(CharLiteral {
+            encoding,
+            spelling,
+            value,
+            span,
+        }))
+    }
+
+    fn decode_escapes(&mut self, text: &str, encoding: Encoding, span: Span) -> Option<Vec<u32>> {
+        let mut warnings = Vec::new();
+        let result = decode_escapes(text, encoding, &mut warnings);
+        self.report_literal(result, warnings, span)
+    }
+
+    fn report_literal<T>(&mut self, result: Result<T, String>, warnings: Vec<String>, span: Span) -> Option<T> {
+        for warning in warnings {
+            parser_warn!(self, ErrorCode::InvalidLiteral, span, "{warning}");
+        }
+        match result {
+            Ok(value) => Some(value),
+            Err(message) => {
+                parser_error!(self, ErrorCode::InvalidLiteral, span, "{message}");
+                None
+            }
+        }
+    }
+}
+
+impl IntLiteral {
+    /// Types the constant may have, in the order they are tried (C17 6.4.4.1)
+    fn candidates(&self) -> &'static [IntType] {
+        use IntType::*;
+        match (self.is_unsigned, self.width, self.is_decimal) {
+            (false, IntWidth::Default, true) => &[Int, Long, LongLong],
+            (false, IntWidth::Default, false) => &[Int, UInt, Long, ULong, LongLong, ULongLong],
+            (true, IntWidth::Default, _) => &[UInt, ULong, ULongLong],
+            (false, IntWidth::Long, true) => &[Long, LongLong],
+            (false, IntWidth::Long, false) => &[Long, ULong, LongLong, ULongLong],
+            (true, IntWidth::Long, _) => &[ULong, ULongLong],
+            (false, IntWidth::LongLong, true) => &[LongLong],
+            (false, IntWidth::LongLong, false) => &[LongLong, ULongLong],
+            (true, IntWidth::LongLong, _) => &[ULongLong],
+            (_, IntWidth::Size | IntWidth::BitInt, _) => &[],
+        }
+    }
+}
+
+/// Splits an integer suffix into whether it is unsigned and its width. The
+/// `u` may come before or after the width, `ll` and `wb` may not mix cases
+fn parse_int_suffix(suffix: &str) -> Option<(bool, IntWidth)> {
+    let mut rest = suffix;
+    let mut is_unsigned = false;
+    if let Some(after) = strip_unsigned(rest) {
+        is_unsigned = true;
+        rest = after;
+    }
+    let width = [
+        ("ll", IntWidth::LongLong),
+        ("LL", IntWidth::LongLong),
+        ("l", IntWidth::Long),
+        ("L", IntWidth::Long),
+        ("z", IntWidth::Size),
+        ("Z", IntWidth::Size),
+        ("wb", IntWidth::BitInt),
+        ("WB", IntWidth::BitInt),
+    ]
+    .into_iter()
+    .find_map(|(spelling, width)| Some((rest.strip_prefix(spelling)?, width)));
+    let width = match width {
+        Some((after, width)) => {
+            rest = after;
+            width
+        }
+        None => IntWidth::Default,
+    };
+    if !is_unsigned {
+        if let Some(after) = strip_unsigned(rest) {
+            is_unsigned = true;
+            rest = after;
+        }
+    }
+    rest.is_empty().then_some((is_unsigned, width))
+}
+
+/// Parses the digits and suffix of an integer literal
+pub(crate) fn int_literal(spelling: &str) -> Result<IntLiteral, String> {
+    let digits = spelling.replace('\'', "");
+    let (radix, digits) = match digits.as_bytes() {
+        [b'0', b'x' | b'X', ..] => (16, &digits[2..]),
+        [b'0', b'b' | b'B', ..] => (2, &digits[2..]),
+        [b'0', ..] => (8, &digits[1..]),
+        _ => (10, &digits[..]),
+    };
+    let end = digits
+        .find(|char: char| !char.is_ascii_hexdigit() || (radix != 16 && char.is_ascii_alphabetic()))
+        .unwrap_or(digits.len());
+    let (digits, suffix) = digits.split_at(end);
+
+    let Some((is_unsigned, width)) = parse_int_suffix(suffix) else {
+        return Err(format!("invalid suffix `{suffix}` on integer literal `{spelling}`"));
+    };
+    if digits.is_empty() && radix != 8 {
+        return Err(format!("integer literal `{spelling}` has no digits"));
+    }
+    let mut value: u128 = 0;
+    for digit in digits.chars() {
+        let Some(digit) = digit.to_digit(radix) else {
+            let kind = match radix {
+                2 => "binary",
+                8 => "octal",
+                _ => "integer",
+            };
+            return Err(format!("invalid digit `{digit}` in {kind} literal `{spelling}`"));
+        };
+        value = value
+            .checked_mul(radix as u128)
+            .and_then(|value| value.checked_add(digit as u128))
+            .ok_or_else(|| too_large(spelling))?;
+    }
+    Ok(IntLiteral {
+        value,
+        is_decimal: radix == 10,
+        is_unsigned,
+        width,
+    })
+}
+
+fn too_large(spelling: &str) -> String {
+    format!("integer literal `{spelling}` is too large for any integer type")
+}
+
+fn strip_unsigned(suffix: &str) -> Option<&str> {
+    suffix.strip_prefix(['u', 'U'])
+}
+
+/// Computes the value of a hexadecimal floating constant without its `0x`
+/// prefix, e.g. `1.8p1`
+fn parse_hex_float(text: &str) -> Option<f64> {
+    let (mantissa, exponent) = text.split_once(['p', 'P'])?;
+    let exponent: i32 = exponent.parse().ok()?;
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+    let mut value: u64 = 0;
+    let mut scale = exponent;
+    for (index, digit) in int.chars().chain(frac.chars()).enumerate() {
+        let digit = digit.to_digit(16)?;
+        let is_frac = index >= int.len();
+        // Digits beyond the precision of a double only move the exponent
+        if value >> 60 != 0 {
+            if !is_frac {
+                scale = scale.saturating_add(4);
+            }
+            continue;
+        }
+        value = value << 4 | digit as u64;
+        if is_frac {
+            scale = scale.saturating_sub(4);
+        }
+    }
+    Some(value as f64 * 2f64.powi(scale))
+}
+
+/// Splits the encoding prefix off a character or string literal
+pub(crate) fn split_encoding(spelling: &str) -> (Encoding, &str) {
+    match spelling.find(['"', '\'']).map(|quote| spelling.split_at(quote)) {
+        Some(("u8", text)) => (Encoding::Utf8, text),
+        Some(("u", text)) => (Encoding::Utf16, text),
+        Some(("U", text)) => (Encoding::Utf32, text),
+        Some(("L", text)) => (Encoding::Wide, text),
+        _ => (Encoding::Plain, spelling),
+    }
+}
+
+/// Appends the code units encoding `char` in the element type
+fn encode_char(char: char, encoding: Encoding, units: &mut Vec<u32>) {
+    match encoding {
+        Encoding::Plain | Encoding::Utf8 => {
+            units.extend(char.encode_utf8(&mut [0; 4]).bytes().map(u32::from))
+        }
+        Encoding::Utf16 => units.extend(char.encode_utf16(&mut [0; 2]).iter().map(|&unit| unit as u32)),
+        Encoding::Utf32 | Encoding::Wide => units.push(char as u32),
+    }
+}
+
+/// Decodes a character constant into its encoding and value
+pub(crate) fn char_literal(spelling: &str, warnings: &mut Vec<String>) -> Result<(Encoding, u32), String> {
+    let (encoding, text) = split_encoding(spelling);
+    let units = decode_escapes(&text[1..text.len() - 1], encoding, warnings)?;
+    let value = match units[..] {
+        [] => return Err("empty character constant".to_string()),
+        [unit] => unit,
+        _ if encoding == Encoding::Plain => {
+            warnings.push(format!("multi-character character constant `{spelling}`"));
+            units.iter().fold(0u32, |value, unit| value << 8 | unit)
+        }
+        _ => {
+            return Err(format!(
+                "character constant `{spelling}` does not fit into a single `{}`",
+                encoding.element_type()
+            ))
+        }
+    };
+    Ok((encoding, value))
+}
+
+/// Decodes the text between the quotes of a literal into code units of the
+/// element type of `encoding`
+pub(crate) fn decode_escapes(
+    text: &str,
+    encoding: Encoding,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<u32>, String> {
+    let max_unit = match encoding.element_size() {
+        1 => u8::MAX as u32,
+        2 => u16::MAX as u32,
+        _ => u32::MAX,
+    };
+    let mut units = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            encode_char(char, encoding, &mut units);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            break;
+        };
+        let unit = match escape {
+            // Line splice
+            '\n' => continue,
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                continue;
+            }
+            '\'' | '"' | '?' | '\\' => escape as u64,
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'n' => 0x0a,
+            'r' => 0x0d,
+            't' => 0x09,
+            'v' => 0x0b,
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap() as u64;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|char| char.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit as u64;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                value
+            }
+            'x' => {
+                let mut value: u64 = 0;
+                let mut digits = 0;
+                while let Some(digit) = chars.peek().and_then(|char| char.to_digit(16)) {
+                    value = value.saturating_mul(16).saturating_add(digit as u64);
+                    digits += 1;
+                    chars.next();
+                }
+                if digits == 0 {
+                    return Err("`\\x` used with no following hex digits".to_string());
+                }
+                value
+            }
+            'u' | 'U' => {
+                let len = if escape == 'u' { 4 } else { 8 };
+                let digits: String = chars.by_ref().take(len).collect();
+                let code_point = match u32::from_str_radix(&digits, 16) {
+                    Ok(code_point) if digits.len() == len => code_point,
+                    _ => {
+                        return Err(format!(
+                            "incomplete universal character name `\\{escape}{digits}`"
+                        ))
+                    }
+                };
+                let Some(char) = char::from_u32(code_point) else {
+                    return Err(format!(
+                        "`\\{escape}{digits}` is not a valid universal character name"
+                    ));
+                };
+                encode_char(char, encoding, &mut units);
+                continue;
+            }
+            _ => {
+                warnings.push(format!("unknown escape sequence `\\{escape}`"));
+                encode_char(escape, encoding, &mut units);
+                continue;
+            }
+        };
+        if unit > max_unit as u64 {
+            return Err(format!(
+                "escape sequence out of range for `{}`",
+                encoding.element_type()
+            ));
+        }
+        units.push(unit as u32);
+    }
+    Ok(units)
+}
@@ -1,13 +1,5 @@
 #[macro_export]
 macro_rules! expect_tok {
-    ($tok:expr,$pat:pat,$fail:expr) => {{
-        if !matches!($tok, $pat) {
-            $fail($tok);
-            false
-        } else {
-            true
-        }
-    }};
     ($tok:expr,$pat:pat) => {{
         if !matches!($tok, $pat) {
             false
@@ -17,21 +9,23 @@ macro_rules! expect_tok {
     }}
 }
 
+/// Records an error diagnostic on the parser
 #[macro_export]
 macro_rules! parser_error {
-    ($($arg:tt)+) => {{
-        use colored::Colorize;
-
-        eprintln!("{}: {}", "Parser Error".red(), format_args!($($arg)+));
+    ($parser:expr,$code:expr,$span:expr,$($arg:tt)+) => {{
+        let diagnostic =
+            $crate::diagnostic::Diagnostic::error($code, $span, format!($($arg)+));
+        $parser.diagnostics.push(diagnostic);
     }};
 }
 
+/// Records a warning diagnostic on the parser
 #[macro_export]
 macro_rules! parser_warn {
-    ($($arg:tt)+) => {{
-        use colored::Colorize;
-
-        eprintln!("{}: {}", "Parser Warn".yellow(), format_args!($($arg)+));
+    ($parser:expr,$code:expr,$span:expr,$($arg:tt)+) => {{
+        let diagnostic =
+            $crate::diagnostic::Diagnostic::warning($code, $span, format!($($arg)+));
+        $parser.diagnostics.push(diagnostic);
     }};
 }
 
@@ -44,24 +38,38 @@ macro_rules! valid_var_or_func_mod {
 
 #[macro_export]
 macro_rules! encounter_modifier {
-    ($var:expr,$msg:expr) => {{
+    ($parser:expr,$var:expr,$msg:expr) => {{
         if !$var {
             $var = true;
         } else {
-            parser_warn!($msg);
+            let span = $parser.cur_span();
+            parser_warn!($parser, $crate::diagnostic::ErrorCode::DuplicateSpecifier, span, $msg);
         }
     }};
 }
 
 #[macro_export]
 macro_rules! encounter_dsc_modifier {
-    ($var:expr,$class:expr) => {{
+    ($parser:expr,$var:expr,$class:expr) => {{
+        let span = $parser.cur_span();
         if let DataStorageClass::None = $var {
             $var = $class;
+        } else if $var == $class {
+            parser_error!(
+                $parser,
+                $crate::diagnostic::ErrorCode::DuplicateSpecifier,
+                span,
+                "duplicate storage class specifier `{}`",
+                $class.keyword()
+            );
         } else {
             parser_error!(
-                "Encountered second data storage class specifier: {:?}",
-                $class
+                $parser,
+                $crate::diagnostic::ErrorCode::ConflictingSpecifiers,
+                span,
+                "storage class specifier `{}` conflicts with `{}`",
+                $class.keyword(),
+                $var.keyword()
             );
         }
     }};
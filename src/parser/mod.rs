@@ -1,86 +1,398 @@
-use std::collections::HashSet;
+use std::{borrow::Cow, collections::HashMap, fmt::Display};
 
 use bumpalo::Bump;
 
-use crate::{ast::{expr::Expression, stmt::*, *}, lexer::{tokens::Token, Lexer}};
+use crate::{
+    ast::{stmt::*, *},
+    diagnostic::{Diagnostic, ErrorCode},
+    lexer::{
+        tokens::{SpannedToken, Token},
+        unsplice, Lexer,
+    },
+    parser_error, parser_warn,
+    span::Span,
+};
 
 pub mod expr;
+mod fragment;
+pub(crate) mod literal;
 pub mod stmt;
 pub mod types;
 mod macros;
+mod recovery;
+
+pub use fragment::{parse_declaration, parse_expression, parse_statement, parse_type_name};
+
+/// How deep statements and expressions may nest before the parser gives up
+/// instead of risking a stack overflow
+const MAX_NESTING_DEPTH: usize = 256;
+
+/// Keywords introduced in C23, which are ordinary identifiers before
+const C23_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "bool",
+    "constexpr",
+    "false",
+    "nullptr",
+    "static_assert",
+    "thread_local",
+    "true",
+    "typeof",
+    "typeof_unqual",
+];
+
+/// Alternate spellings of keywords that GNU C accepts in every mode, mapped
+/// to the spelling the parser knows
+const GNU_ALTERNATE_KEYWORDS: &[(&str, Token<'static>)] = &[
+    ("__const", Token::Const),
+    ("__const__", Token::Const),
+    ("__volatile", Token::Volatile),
+    ("__volatile__", Token::Volatile),
+    ("__restrict", Token::Restrict),
+    ("__restrict__", Token::Restrict),
+    ("__inline", Token::Inline),
+    ("__inline__", Token::Inline),
+    ("__signed", Token::Signed),
+    ("__signed__", Token::Signed),
+    ("__asm", Token::Ident("__asm__")),
+    ("__typeof", Token::Ident("__typeof__")),
+    ("__alignof", Token::Ident("__alignof__")),
+    ("__attribute", Token::Ident("__attribute__")),
+];
+
+/// Language options of the parser
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Accept GNU extensions, such as case ranges, `__attribute__` and
+    /// `asm` statements. Without them, the constructs spelled with reserved
+    /// identifiers are still parsed but reported
+    pub gnu_extensions: bool,
+    /// Revision of the C standard, which decides the keywords and constructs
+    /// that are available
+    pub standard: CStandard,
+}
+
+/// Revision of the C standard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CStandard {
+    /// ANSI C, also known as C90
+    C89,
+    C99,
+    C11,
+    C17,
+    #[default]
+    C23,
+}
+
+impl Display for CStandard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CStandard::C89 => "C89",
+            CStandard::C99 => "C99",
+            CStandard::C11 => "C11",
+            CStandard::C17 => "C17",
+            CStandard::C23 => "C23",
+        })
+    }
+}
+
+impl CStandard {
+    /// Value of `__STDC_VERSION__`. C89 has none, so the value of its
+    /// amendment 1 is used
+    pub fn stdc_version(&self) -> &'static str {
+        match self {
+            CStandard::C89 => "199409L",
+            CStandard::C99 => "199901L",
+            CStandard::C11 => "201112L",
+            CStandard::C17 => "201710L",
+            CStandard::C23 => "202311L",
+        }
+    }
+}
 
 pub struct Parser<'a, 's> {
     pub lexer: Lexer<'s>,
-    pub variables: HashSet<Ident<'a>>,
-    pub types: HashSet<Ident<'a>>,
+    pub options: ParserOptions,
+    pub diagnostics: Vec<Diagnostic>,
     arena: &'a Bump,
+    /// The lexed tokens without preprocessing directives and line splices
+    tokens: Vec<SpannedToken<'a>>,
     tok_index: usize,
+    depth: usize,
+    /// Number of enclosing `switch` statements
+    switch_depth: usize,
+    /// Number of enclosing loops
+    loop_depth: usize,
+    /// Index of the token after the last [Expression::Error], which is not
+    /// reported again when it does not close or follow the expression
+    error_expr_end: Option<usize>,
+    /// Ordinary identifiers of each open scope, innermost last, mapped to
+    /// whether they are typedef names
+    scopes: Vec<HashMap<Ident<'a>, bool>>,
+}
+
+/// Output of the parser: the (possibly partial) AST together with every
+/// diagnostic that was encountered while producing it
+#[derive(Debug, Clone)]
+pub struct ParseResult<T> {
+    pub ast: T,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<T> ParseResult<T> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.is_error())
+    }
+
+    /// Converts into a [Result] that is only `Ok` if no errors were reported
+    pub fn into_result(self) -> Result<T, Vec<Diagnostic>> {
+        if self.has_errors() {
+            Err(self.diagnostics)
+        } else {
+            Ok(self.ast)
+        }
+    }
 }
 
 impl<'a, 's: 'a> Parser<'a, 's> {
     pub fn new(lexer: Lexer<'s>, arena: &'a Bump) -> Self {
-        Self {
+        Self::with_options(lexer, arena, ParserOptions::default())
+    }
+
+    pub fn with_options(lexer: Lexer<'s>, arena: &'a Bump, options: ParserOptions) -> Self {
+        let mut parser = Self {
+            tokens: Vec::with_capacity(lexer.tokens.len()),
             lexer,
+            options,
             tok_index: 0,
-            variables: HashSet::new(),
-            types: HashSet::new(),
+            diagnostics: Vec::new(),
             arena,
+            depth: 0,
+            switch_depth: 0,
+            loop_depth: 0,
+            error_expr_end: None,
+            scopes: vec![HashMap::new()],
+        };
+        if options.gnu_extensions {
+            parser.declare("__builtin_va_list", true);
+        }
+        parser.prepare_tokens();
+        parser
+    }
+
+    /// Skips preprocessing directives, which are handled by `Preprocessor`,
+    /// removes line splices from the spelling of identifiers and numbers and
+    /// maps alternate keywords of GNU C
+    fn prepare_tokens(&mut self) {
+        let mut index = 0;
+        while let Some(tok) = self.lexer.tokens.get(index).copied() {
+            if tok.tok == Token::Hash && tok.first_on_line {
+                let mut end = index + 1;
+                while self.lexer.tokens.get(end).is_some_and(|tok| !tok.first_on_line) {
+                    end += 1;
+                }
+                let span = tok.span.to(self.lexer.tokens[end - 1].span);
+                parser_warn!(
+                    self,
+                    ErrorCode::IgnoredDirective,
+                    span,
+                    "preprocessing directive is ignored by the parser, run the input through `Preprocessor` first"
+                );
+                index = end;
+                continue;
+            }
+            let cleaned = match tok.tok {
+                Token::Ident(name) => {
+                    let name = self.unspliced(name);
+                    GNU_ALTERNATE_KEYWORDS
+                        .iter()
+                        .find(|(alternate, _)| self.options.gnu_extensions && *alternate == name)
+                        .map_or(Token::Ident(name), |(_, keyword)| *keyword)
+                }
+                Token::LitInt(int) => Token::LitInt(self.unspliced(int)),
+                Token::LitFloat(float) => Token::LitFloat(self.unspliced(float)),
+                tok => tok,
+            };
+            self.tokens.push(SpannedToken { tok: cleaned, ..tok });
+            index += 1;
+        }
+    }
+
+    /// Allocates the spelling without line splices in the arena if needed
+    fn unspliced(&self, spelling: &'s str) -> &'a str {
+        match unsplice(spelling) {
+            Cow::Borrowed(spelling) => spelling,
+            Cow::Owned(spelling) => self.arena.alloc_str(&spelling),
         }
     }
 
-    pub fn parse(&mut self) -> Vec<Statement<'a>> {
+    /// Declares typedef names at file scope, for input that uses types
+    /// declared elsewhere
+    pub fn declare_typedefs<'n>(&mut self, names: impl IntoIterator<Item = &'n str>) {
+        for name in names {
+            let name = self.arena.alloc_str(name);
+            self.declare(name, true);
+        }
+    }
+
+    pub fn parse(&mut self) -> ParseResult<Vec<Statement<'a>>> {
         let mut out = Vec::new();
-        while let Some(stmt) = self.parse_stmt() {
-            out.push(stmt);
+        while self.cur_tok().is_some() {
+            match self.parse_stmt_recovering() {
+                Some(stmt) => out.push(stmt),
+                // Stray closing bracket
+                None if self.cur_tok().is_some() => {
+                    self.unexpected_cur(&["declaration"], "at top level")
+                }
+                None => break,
+            }
+            self.next_tok();
         }
-        out
+        self.finish(out)
+    }
+
+    /// Collects the diagnostics of the lexer and the parser, in source order
+    fn finish<T>(&mut self, ast: T) -> ParseResult<T> {
+        let mut diagnostics = std::mem::take(&mut self.lexer.diagnostics);
+        diagnostics.append(&mut self.diagnostics);
+        match &self.lexer.source_map {
+            Some(map) => {
+                diagnostics = diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.with_provenance(map))
+                    .collect();
+                diagnostics.sort_by_key(|diagnostic| map.translation_order(diagnostic.span));
+            }
+            None => diagnostics.sort_by_key(|diagnostic| diagnostic.span.start),
+        }
+        ParseResult { ast, diagnostics }
     }
 
     fn parse_ident(&mut self) -> Option<Statement<'a>> {
-        match self.peek_tok()? {
-            // Variable or function
-            Token::Ident(_) => self.parse_var_or_func(),
-            // Pointer type or multiplication
-            Token::Asterisk => self.parse_var_or_func(),
-            // variable specific keywords
-            Token::Const | Token::Register | Token::Auto => self.parse_variable(),
-            // Function specific keywords
-            Token::Inline => todo!(),
-            // Variable or function specifix keywords
-            Token::Static | Token::Extern | Token::Volatile => self.parse_var_or_func(),
+        match self.peek_tok() {
+            // Label
+            Some(Token::Colon) => self.parse_labeled_stmt(),
+            // Typedef names and built in types start declarations like
+            // `T * x;` and `int (*fp)(void);`
+            _ if self.starts_type_name(self.tok_index) => self.parse_declaration(),
+            // Declaration with a type that is not known, for example from a
+            // header that was not included
+            Some(
+                Token::Ident(_)
+                | Token::Const
+                | Token::Volatile
+                | Token::Restrict
+                | Token::Register
+                | Token::Auto
+                | Token::Inline
+                | Token::Static
+                | Token::Extern
+                | Token::Typedef,
+            ) => self.parse_declaration(),
             // Expression
             _ => self.parse_expr_stmt(),
         }
     }
 
-    /// Determines whether two identifier declare a variable or a function
-    /// [Parser::cur_tok] is the first ident (the type)
-    fn parse_var_or_func(&mut self) -> Option<Statement<'a>> {
-        // Peek ahead two tokens to determine
-        // if it is a variable or function
-        // based on whether we encounter
-        // lparent, assign or semicolon
-        let mut peek_ahead = 2;
-        loop {
-            match self.lexer.tokens.get(self.tok_index + peek_ahead)? {
-                Token::LSquare => break self.parse_variable(),
-                Token::LParent => break self.parse_function(),
-                Token::Comma => todo!("multi variable"),
-                Token::Assign | Token::Semicolon => break self.parse_variable(),
-                _ => (),
-            }
-            peek_ahead += 1;
+    /// Whether the identifier is a keyword in the selected standard. Only
+    /// keywords that the lexer does not know are checked. `asm` and `typeof`
+    /// are keywords in every standard with GNU extensions
+    pub(crate) fn is_keyword(&self, name: &str) -> bool {
+        match name {
+            "asm" => self.options.gnu_extensions,
+            "typeof" if self.options.gnu_extensions => true,
+            _ => !C23_KEYWORDS.contains(&name) || self.options.standard >= CStandard::C23,
+        }
+    }
+
+    /// Reports `feature` if GNU extensions are disabled
+    pub(crate) fn require_gnu(&mut self, span: Span, feature: &str) {
+        if !self.options.gnu_extensions {
+            parser_error!(self, ErrorCode::ExtensionDisabled, span, "use of {feature} is a GNU extension");
+        }
+    }
+
+    /// Reports `feature` if it was introduced after the selected standard
+    pub(crate) fn require_standard(&mut self, standard: CStandard, span: Span, feature: &str) {
+        if self.options.standard < standard {
+            parser_error!(
+                self,
+                ErrorCode::UnavailableInStandard,
+                span,
+                "use of {feature} requires {standard}, but {} is selected",
+                self.options.standard
+            );
+        }
+    }
+
+    /// Opens a block scope
+    pub(crate) fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Records an ordinary identifier in the innermost scope. Variables hide
+    /// typedef names of outer scopes
+    pub(crate) fn declare(&mut self, name: Ident<'a>, is_typedef: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, is_typedef);
         }
     }
 
+    /// Whether the identifier is a typedef name in the current scope
+    pub(crate) fn is_typedef_name(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .is_some_and(|is_typedef| *is_typedef)
+    }
+
     #[inline(always)]
     pub(crate) fn cur_tok(&self) -> Option<&Token<'a>> {
-        self.lexer.tokens.get(self.tok_index)
+        self.tokens.get(self.tok_index).map(|tok| &tok.tok)
+    }
+
+    /// Span of the current token
+    #[inline(always)]
+    pub(crate) fn cur_span(&self) -> Span {
+        self.span_at(self.tok_index)
+    }
+
+    /// Span of the token after the current one
+    #[inline(always)]
+    pub(crate) fn peek_span(&self) -> Span {
+        self.span_at(self.tok_index + 1)
+    }
+
+    /// Past the end of the input this is an empty span at the end of the
+    /// last token
+    fn span_at(&self, index: usize) -> Span {
+        match self.tokens.get(index) {
+            Some(tok) => tok.span,
+            None => match self.tokens.last() {
+                Some(tok) => Span {
+                    start: tok.span.end,
+                    ..tok.span
+                },
+                None => Span::default(),
+            },
+        }
     }
 
     #[inline(always)]
     pub(crate) fn peek_tok(&self) -> Option<&Token<'a>> {
-        self.lexer.tokens.get(self.tok_index + 1)
+        self.tokens.get(self.tok_index + 1).map(|tok| &tok.tok)
     }
 
     #[inline(always)]
@@ -93,8 +405,104 @@ impl<'a, 's: 'a> Parser<'a, 's> {
         matches!(self.peek_tok(), Some(Token::Semicolon) | None)
     }
 
-    #[inline(always)]
-    fn reset_variables(&mut self) {
-        self.variables.clear();
+    /// Advances onto the next token if it is `expected`, otherwise reports
+    /// it as unexpected
+    pub(crate) fn expect_peek(&mut self, expected: Token<'a>, context: &str) -> bool {
+        if self.peek_tok() == Some(&expected) {
+            self.next_tok();
+            true
+        } else {
+            self.unexpected_peek(&[&format!("`{expected}`")], context);
+            false
+        }
+    }
+
+    /// Reports the next token as not fitting the grammar
+    pub(crate) fn unexpected_peek(&mut self, expected: &[&str], context: &str) {
+        self.unexpected_at(self.tok_index + 1, expected, context)
+    }
+
+    /// Reports the current token as not fitting the grammar
+    pub(crate) fn unexpected_cur(&mut self, expected: &[&str], context: &str) {
+        self.unexpected_at(self.tok_index, expected, context)
+    }
+
+    fn unexpected_at(&mut self, index: usize, expected: &[&str], context: &str) {
+        if self.error_expr_end == Some(index) {
+            return;
+        }
+        let Some(tok) = self.tokens.get(index).map(|tok| tok.tok) else {
+            return self.unexpected_eof(expected, context);
+        };
+        // A missing name has its own code, like a missing expression or type
+        let code = match expected {
+            ["identifier"] => ErrorCode::ExpectedIdentifier,
+            _ => ErrorCode::UnexpectedToken,
+        };
+        let mut diagnostic = Diagnostic::error(
+            code,
+            self.span_at(index),
+            format!(
+                "expected {}{}, found `{tok}`",
+                expected.join(" or "),
+                with_context(context)
+            ),
+        )
+        .with_found(format!("`{tok}`"));
+        diagnostic.expected = expected.iter().map(|exp| exp.to_string()).collect();
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub(crate) fn unexpected_eof(&mut self, expected: &[&str], context: &str) {
+        if self.error_expr_end == Some(self.tokens.len()) {
+            return;
+        }
+        let mut diagnostic = Diagnostic::error(
+            ErrorCode::UnexpectedEof,
+            self.span_at(self.tokens.len()),
+            format!(
+                "unexpected end of input, expected {}{}",
+                expected.join(" or "),
+                with_context(context)
+            ),
+        )
+        .with_found("end of input");
+        diagnostic.expected = expected.iter().map(|exp| exp.to_string()).collect();
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Reports a construct that is valid C but not handled by the parser yet
+    pub(crate) fn unsupported<T>(&mut self, span: Span, what: &str) -> Option<T> {
+        parser_error!(self, ErrorCode::Unsupported, span, "{what} is not supported yet");
+        None
+    }
+
+    /// Enters a nested construct. Returns false and reports an error if the
+    /// nesting limit is exceeded, in which case [Parser::leave_nested] must
+    /// not be called
+    pub(crate) fn enter_nested(&mut self) -> bool {
+        if self.depth >= MAX_NESTING_DEPTH {
+            parser_error!(
+                self,
+                ErrorCode::NestingTooDeep,
+                self.cur_span(),
+                "nesting exceeds the maximum depth of {MAX_NESTING_DEPTH}"
+            );
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub(crate) fn leave_nested(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+fn with_context(context: &str) -> String {
+    if context.is_empty() {
+        String::new()
+    } else {
+        format!(" {context}")
     }
 }
@@ -0,0 +1,145 @@
+use crate::{
+    ast::{
+        expr::Expression,
+        stmt::{ErrorStmt, Statement},
+    },
+    diagnostic::Diagnostic,
+    lexer::tokens::Token,
+};
+
+use super::{expr::Precedence, Parser};
+
+impl<'a, 's: 'a> Parser<'a, 's> {
+    /// Parses a statement. If it is malformed, skips to the end of it and
+    /// returns a [Statement::Error] instead.
+    /// Returns `None` without an error if there is no statement left before
+    /// the end of the block or input, in which case the closing bracket is
+    /// not consumed
+    pub(super) fn parse_stmt_recovering(&mut self) -> Option<Statement<'a>> {
+        let start_index = self.tok_index;
+        let start = self.cur_span();
+        let diagnostic_count = self.diagnostics.len();
+        if let Some(stmt) = self.parse_stmt() {
+            return Some(stmt);
+        }
+        if self.diagnostics.len() == diagnostic_count {
+            if matches!(self.cur_tok(), Some(Token::RCurly) | None) {
+                return None;
+            }
+            self.unexpected_cur(&["statement"], "");
+        } else if let Some(first_error) = self.diagnostics[diagnostic_count..].iter().position(Diagnostic::is_error) {
+            // Keep only the first error of the failed attempt, so malformed
+            // input that is parsed again after resynchronizing does not
+            // report the same errors over and over
+            self.diagnostics.truncate(diagnostic_count + first_error + 1);
+        }
+        self.synchronize(start_index);
+        Some(Statement::Error(ErrorStmt {
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Skips the statement starting at `start_index`. Ends on its last
+    /// token: the terminating semicolon, the closing bracket of a block,
+    /// or the token before the next declaration or closing bracket of the
+    /// enclosing block. Always consumes at least one token
+    pub(super) fn synchronize(&mut self, start_index: usize) {
+        self.tok_index = start_index;
+        let mut brackets = 0usize;
+        let mut parens = 0usize;
+        while let Some(tok) = self.cur_tok() {
+            match tok {
+                Token::Semicolon if brackets == 0 && parens == 0 => return,
+                Token::LCurly => brackets += 1,
+                Token::RCurly if brackets == 0 => {
+                    if self.tok_index > start_index {
+                        self.tok_index -= 1;
+                    }
+                    return;
+                }
+                Token::RCurly => {
+                    brackets -= 1;
+                    if brackets == 0 && parens == 0 && self.peek_tok() != Some(&Token::Semicolon) {
+                        return;
+                    }
+                }
+                Token::LParent | Token::LSquare => parens += 1,
+                Token::RParent | Token::RSquare => parens = parens.saturating_sub(1),
+                tok if brackets == 0
+                    && parens == 0
+                    && self.tok_index > start_index
+                    && Self::starts_declaration(tok) =>
+                {
+                    self.tok_index -= 1;
+                    return;
+                }
+                _ => (),
+            }
+            self.next_tok();
+        }
+    }
+
+    /// Keywords that can only start a declaration or a `switch` label, used
+    /// as synchronization points
+    fn starts_declaration(tok: &Token) -> bool {
+        matches!(
+            tok,
+            Token::Typedef
+                | Token::Extern
+                | Token::Static
+                | Token::Inline
+                | Token::Register
+                | Token::Struct
+                | Token::Union
+                | Token::Enum
+                | Token::Case
+                | Token::Default
+        )
+    }
+
+    /// Parses the expression after the current token. If it is missing or
+    /// malformed, an [Expression::Error] is returned instead. A missing
+    /// expression consumes no tokens
+    pub(super) fn parse_next_expr(&mut self, prec: Precedence, context: &str) -> Expression<'a> {
+        if !self.peek_tok().is_some_and(Self::can_start_expr) {
+            self.unexpected_peek(&["expression"], context);
+            self.error_expr_end = Some(self.tok_index + 1);
+            return Expression::Error {
+                span: self.peek_span().start_point(),
+            };
+        }
+        self.next_tok();
+        let start = self.cur_span();
+        match self.parse_expr(prec) {
+            Some(expr) => expr,
+            None => {
+                self.error_expr_end = Some(self.tok_index + 1);
+                Expression::Error {
+                    span: start.to(self.cur_span()),
+                }
+            }
+        }
+    }
+
+    pub(super) fn can_start_expr(tok: &Token) -> bool {
+        matches!(
+            tok,
+            Token::LitString(_)
+                | Token::LitInt(_)
+                | Token::LitFloat(_)
+                | Token::LitChar(_)
+                | Token::Ident(_)
+                | Token::BNot
+                | Token::Sizeof
+                | Token::ExclamMark
+                | Token::Plus
+                | Token::Asterisk
+                | Token::Ampersand
+                | Token::LParent
+                | Token::And
+                | Token::Increment
+                | Token::Decrement
+                | Token::Minus
+        )
+    }
+}
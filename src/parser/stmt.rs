@@ -1,189 +1,615 @@
 use crate::{
     ast::{
+        expr::Expression,
+        expr::StringLiteral,
         stmt::{
-            CompositeDataType, DataStorageClass, Field, ForStmt, FunctionStmt, IfStmt, IfType,
-            VariableStmt, WhileStmt,
+            AsmOperand, AsmStmt, AttributeStmt, BreakStmt, CaseStmt, ComputedGotoStmt, ContinueStmt, DataStorageClass,
+            DeclSpecifiers, DefaultStmt, DoWhileStmt, EmptyStmt, EnumStmt, ForInit, ForStmt, FunctionStmt, GotoStmt,
+            IfStmt, IfType, InitDeclarator, LabelStmt, ReturnStmt, StaticAssertStmt, StructStmt, SwitchStmt,
+            TypedefDeclarator, TypedefStmt, UnionStmt, VariableStmt, WhileStmt,
         },
-        types::Type,
+        types::{Param, ParamStyle, PrimitiveKind, Qualifiers, Type},
     },
-    encounter_dsc_modifier, encounter_modifier, expect_tok,
+    diagnostic::ErrorCode,
+    encounter_modifier, expect_tok,
     lexer::tokens::Token,
     parser::expr::Precedence,
     parser_error, parser_warn,
+    span::Span,
 };
 
-use super::{BlockStmt, Parser, Statement};
+use super::{types::DeclaratorKind, BlockStmt, CStandard, Ident, Parser, Statement};
 
 impl<'a, 's: 'a> Parser<'a, 's> {
     pub fn parse_stmt(&mut self) -> Option<Statement<'a>> {
+        if !self.enter_nested() {
+            return None;
+        }
+        let stmt = self.parse_stmt_inner();
+        self.leave_nested();
+        stmt
+    }
+
+    fn parse_stmt_inner(&mut self) -> Option<Statement<'a>> {
         match self.cur_tok()? {
+            Token::Ident(name @ ("_Static_assert" | "static_assert")) if self.is_keyword(name) => {
+                self.parse_static_assert()
+            }
+            Token::Ident(name @ ("asm" | "__asm__")) if self.is_keyword(name) => self.parse_asm(),
+            Token::Ident("__extension__") => {
+                let mut index = self.tok_index;
+                while self.tokens.get(index).is_some_and(|tok| tok.tok == Token::Ident("__extension__")) {
+                    index += 1;
+                }
+                if self.starts_declaration_at(index) {
+                    self.parse_declaration()
+                } else {
+                    self.parse_expr_stmt()
+                }
+            }
+            _ if self.starts_attributes_at(self.tok_index) => self.parse_attributed_stmt(),
             Token::Ident(_) => self.parse_ident(),
-            Token::Auto | Token::Const | Token::Register => self.parse_variable(),
-            Token::Static | Token::Volatile | Token::Extern => self.parse_var_or_func(),
-            Token::Inline => self.parse_function(),
-            Token::Signed => todo!(),
-            Token::Unsigned => todo!(),
-            Token::Break => todo!(),
-            Token::Continue => todo!(),
-            Token::Goto => todo!(),
-            Token::Return => todo!(),
-            Token::Enum => todo!(),
-            Token::Struct => self.parse_var_or_func(),
-            Token::Union => todo!(),
+            Token::Auto
+            | Token::Const
+            | Token::Register
+            | Token::Static
+            | Token::Volatile
+            | Token::Restrict
+            | Token::Extern
+            | Token::Inline
+            | Token::Typedef
+            | Token::Signed
+            | Token::Unsigned => self.parse_declaration(),
+            Token::Break => self.parse_break(),
+            Token::Continue => self.parse_continue(),
+            Token::Goto => self.parse_goto(),
+            Token::Return => self.parse_return(),
+            Token::Struct | Token::Union | Token::Enum => self.parse_declaration(),
             Token::If => self.parse_if(IfType::If),
-            Token::Do => todo!(),
+            Token::Do => self.parse_do_while(),
             Token::For => self.parse_for(),
             Token::While => self.parse_while(),
-            Token::Switch => todo!(),
-            Token::Typedef => todo!(),
+            Token::Switch => self.parse_switch(),
+            Token::Case | Token::Default => self.parse_labeled_stmt(),
             Token::Semicolon => {
+                // Skip null statements in a loop, they do not nest
+                while self.peek_tok() == Some(&Token::Semicolon) {
+                    self.next_tok();
+                }
                 self.next_tok();
-                self.parse_stmt()
+                match self.cur_tok() {
+                    Some(Token::RCurly) | None => None,
+                    _ => self.parse_stmt_inner(),
+                }
             }
-            Token::LCurly => self
-                .parse_block(Token::RCurly)
-                .map(|block| Statement::Block(block)),
+            Token::LCurly => self.parse_block(Token::RCurly).map(Statement::Block),
             _ => self.parse_expr_stmt(),
         }
     }
 
-    pub(super) fn parse_expr_stmt(&mut self) -> Option<Statement<'a>> {
-        let expr = self.parse_expr(Precedence::Lowest);
-        if expect_tok!(self.peek_tok()?, Token::Semicolon, |tok| {
-            parser_error!(
-                "Expected semicolon after expression statement, received token: {tok:?} instead"
-            );
-        }) {
+    /// Cur token is `_Static_assert` or `static_assert`. Ends on the `;`
+    pub(super) fn parse_static_assert(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        if self.cur_tok() == Some(&Token::Ident("_Static_assert")) {
+            self.require_standard(CStandard::C11, start, "`_Static_assert`");
+        }
+        if !self.expect_peek(Token::LParent, "after `static_assert`") {
+            return None;
+        }
+        let cond = self.parse_next_expr(Precedence::Assign, "as static assertion condition");
+        let message = if self.peek_tok() == Some(&Token::Comma) {
+            self.next_tok();
+            if !matches!(self.peek_tok(), Some(Token::LitString(_))) {
+                self.unexpected_peek(&["string literal"], "as static assertion message");
+                return None;
+            }
             self.next_tok();
+            match self.parse_string_literal()? {
+                Expression::LiteralString(message) => Some(message),
+                _ => None,
+            }
+        } else {
+            self.require_standard(CStandard::C23, start, "static assertion without a message");
+            None
+        };
+        if !self.expect_peek(Token::RParent, "after static assertion") {
+            return None;
+        }
+        if !self.expect_peek(Token::Semicolon, "after static assertion") {
+            return None;
         }
-        expr.map(|expr| Statement::Expression(expr))
+        Some(Statement::StaticAssert(StaticAssertStmt {
+            cond,
+            message,
+            span: start.to(self.cur_span()),
+        }))
     }
 
-    fn parse_while(&mut self) -> Option<Statement<'a>> {
-        expect_tok!(self.peek_tok()?, Token::LParent, |tok| {
-            parser_error!(
-                "Expected Left Parenthesis after `while` keyword, received {tok:#?} instead"
-            )
-        });
-        // Skip While
-        self.next_tok();
-        // Skip Left Parenthesis
-        self.next_tok();
-        let cond = self.parse_expr(Precedence::Lowest)?;
-        expect_tok!(self.peek_tok()?, Token::RParent, |tok| {
-            parser_error!(
-                "Expected Right Parenthesis after while condition, received {tok:#?} instead"
-            )
-        });
-        // Skip Right Parenthesis
-        self.next_tok();
-        expect_tok!(self.peek_tok()?, Token::LCurly, |tok| {
-            parser_error!("Expected Left Curly Brackets after Right Parenthesis of for loop condition, received {tok:#?} instead")
-        });
-        // Skip Left Curly Brackets
+    /// Cur token is the first token of an attribute specifier
+    fn parse_attributed_stmt(&mut self) -> Option<Statement<'a>> {
+        if self.starts_declaration_at(self.skip_attributes(self.tok_index)) {
+            return self.parse_declaration();
+        }
+        let start = self.cur_span();
+        let attributes = self.parse_attributes()?;
+        if self.peek_tok() == Some(&Token::Semicolon) {
+            self.next_tok();
+            return Some(Statement::Attribute(AttributeStmt {
+                attributes,
+                span: start.to(self.cur_span()),
+            }));
+        }
+        parser_warn!(
+            self,
+            ErrorCode::Unsupported,
+            start.to(self.cur_span()),
+            "attributes on statements are ignored"
+        );
         self.next_tok();
-        let block = self.parse_block(Token::RCurly)?;
+        self.parse_stmt()
+    }
+
+    pub(super) fn parse_expr_stmt(&mut self) -> Option<Statement<'a>> {
+        let expr = self.parse_expr(Precedence::Lowest)?;
+        if !self.expect_peek(Token::Semicolon, "after expression statement") {
+            return None;
+        }
+        Some(Statement::Expression(expr))
+    }
+
+    fn parse_while(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        self.expect_peek(Token::LParent, "after `while` keyword");
+        let cond = self.parse_next_expr(Precedence::Lowest, "as while condition");
+        self.expect_peek(Token::RParent, "after while condition");
+        let body = self.parse_loop_body("as while body")?;
         Some(Statement::While(WhileStmt {
             cond,
-            block,
+            body,
+            span: start.to(self.cur_span()),
         }))
     }
 
-    fn parse_for(&mut self) -> Option<Statement<'a>> {
-        expect_tok!(self.peek_tok()?, Token::LParent, |tok| {
+    fn parse_do_while(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        let body = self.parse_loop_body("as do-while body")?;
+        self.expect_peek(Token::While, "after do-while body");
+        self.expect_peek(Token::LParent, "after `while` keyword");
+        let cond = self.parse_next_expr(Precedence::Lowest, "as do-while condition");
+        self.expect_peek(Token::RParent, "after do-while condition");
+        if !self.expect_peek(Token::Semicolon, "after do-while statement") {
+            return None;
+        }
+        Some(Statement::DoWhile(DoWhileStmt {
+            cond,
+            body,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the token before a loop body. Ends on the last token of
+    /// the body
+    fn parse_loop_body(&mut self, context: &str) -> Option<&'a Statement<'a>> {
+        self.loop_depth += 1;
+        let body = self.parse_sub_stmt(context);
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_return(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        let val = match self.peek_tok() {
+            Some(Token::Semicolon) => None,
+            _ => Some(self.parse_next_expr(Precedence::Lowest, "as return value")),
+        };
+        if !self.expect_peek(Token::Semicolon, "after return statement") {
+            return None;
+        }
+        Some(Statement::Return(ReturnStmt {
+            val,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    fn parse_break(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        if self.loop_depth == 0 && self.switch_depth == 0 {
             parser_error!(
-                "Expected Left Parenthesis after `for` keyword, received {tok:#?} instead"
-            )
-        });
-        // Skip For
-        self.next_tok();
-        // Skip Left Parenthesis
+                self,
+                ErrorCode::MisplacedStatement,
+                start,
+                "`break` statement not within a loop or switch"
+            );
+        }
+        if !self.expect_peek(Token::Semicolon, "after `break`") {
+            return None;
+        }
+        Some(Statement::Break(BreakStmt {
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    fn parse_continue(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        if self.loop_depth == 0 {
+            parser_error!(
+                self,
+                ErrorCode::MisplacedStatement,
+                start,
+                "`continue` statement not within a loop"
+            );
+        }
+        if !self.expect_peek(Token::Semicolon, "after `continue`") {
+            return None;
+        }
+        Some(Statement::Continue(ContinueStmt {
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    fn parse_goto(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        if self.peek_tok() == Some(&Token::Asterisk) {
+            self.next_tok();
+            self.require_gnu(start.to(self.cur_span()), "computed `goto`");
+            let target = self.parse_next_expr(Precedence::Lowest, "as goto target");
+            if !self.expect_peek(Token::Semicolon, "after goto statement") {
+                return None;
+            }
+            return Some(Statement::ComputedGoto(ComputedGotoStmt {
+                target,
+                span: start.to(self.cur_span()),
+            }));
+        }
         self.next_tok();
-        let init_stmt = self.parse_stmt()?;
-        expect_tok!(self.cur_tok()?, Token::Semicolon, |tok| {
-            parser_error!("Expected semicolon after the init statement, received {tok:#?} instead")
-        });
-        // Skip semicolon
+        let label = self.expect_cur_ident("as label after `goto`")?;
+        if !self.expect_peek(Token::Semicolon, "after goto statement") {
+            return None;
+        }
+        Some(Statement::Goto(GotoStmt {
+            label,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is `asm` or `__asm__`. Ends on the `;`
+    fn parse_asm(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        self.require_gnu(start, "`asm`");
+        let mut is_volatile = false;
+        let mut is_inline = false;
+        let mut is_goto = false;
+        while matches!(self.peek_tok(), Some(Token::Volatile | Token::Inline | Token::Goto)) {
+            self.next_tok();
+            match self.cur_tok() {
+                Some(Token::Volatile) => encounter_modifier!(self, is_volatile, "Encountered second `volatile` in asm"),
+                Some(Token::Inline) => encounter_modifier!(self, is_inline, "Encountered second `inline` in asm"),
+                _ => encounter_modifier!(self, is_goto, "Encountered second `goto` in asm"),
+            }
+        }
+        if !self.expect_peek(Token::LParent, "after `asm`") {
+            return None;
+        }
+        let template = self.parse_asm_string("as asm template")?;
+        // Sections are separated by `:`, and trailing ones may be omitted
+        let mut outputs = Vec::new();
+        let mut inputs = Vec::new();
+        let mut clobbers = Vec::new();
+        let mut labels = Vec::new();
+        if self.peek_tok() == Some(&Token::Colon) {
+            self.next_tok();
+            outputs = self.parse_asm_operands()?;
+        }
+        if self.peek_tok() == Some(&Token::Colon) {
+            self.next_tok();
+            inputs = self.parse_asm_operands()?;
+        }
+        if self.peek_tok() == Some(&Token::Colon) {
+            self.next_tok();
+            while matches!(self.peek_tok(), Some(Token::LitString(_))) {
+                clobbers.push(self.parse_asm_string("as clobber")?);
+                if self.peek_tok() != Some(&Token::Comma) {
+                    break;
+                }
+                self.next_tok();
+            }
+        }
+        if self.peek_tok() == Some(&Token::Colon) {
+            self.next_tok();
+            while let Some(Token::Ident(label)) = self.peek_tok() {
+                labels.push(*label);
+                self.next_tok();
+                if self.peek_tok() != Some(&Token::Comma) {
+                    break;
+                }
+                self.next_tok();
+            }
+        }
+        if !self.expect_peek(Token::RParent, "after asm operands") {
+            return None;
+        }
+        if !self.expect_peek(Token::Semicolon, "after asm statement") {
+            return None;
+        }
+        Some(Statement::Asm(AsmStmt {
+            is_volatile,
+            is_inline,
+            is_goto,
+            template,
+            outputs,
+            inputs,
+            clobbers,
+            labels,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// Cur token is the token before the string literal. Ends on its last
+    /// token
+    fn parse_asm_string(&mut self, context: &str) -> Option<StringLiteral<'a>> {
+        if !matches!(self.peek_tok(), Some(Token::LitString(_))) {
+            self.unexpected_peek(&["string literal"], context);
+            return None;
+        }
         self.next_tok();
-        let comp_expr = self.parse_expr(Precedence::Lowest)?;
-        expect_tok!(self.cur_tok()?, Token::Semicolon, |tok| {
-            parser_error!(
-                "Expected semicolon after the comparison expression, received {tok:#?} instead"
-            )
+        match self.parse_string_literal()? {
+            Expression::LiteralString(literal) => Some(literal),
+            _ => None,
+        }
+    }
+
+    /// Cur token is the `:` before the operands. Ends on the last operand
+    fn parse_asm_operands(&mut self) -> Option<Vec<AsmOperand<'a>>> {
+        let mut operands = Vec::new();
+        while matches!(self.peek_tok(), Some(Token::LitString(_) | Token::LSquare)) {
+            let start = self.peek_span();
+            let name = if self.peek_tok() == Some(&Token::LSquare) {
+                self.next_tok();
+                self.next_tok();
+                let name = self.expect_cur_ident("as asm operand name")?;
+                if !self.expect_peek(Token::RSquare, "after asm operand name") {
+                    return None;
+                }
+                Some(name)
+            } else {
+                None
+            };
+            let constraint = self.parse_asm_string("as asm operand constraint")?;
+            if !self.expect_peek(Token::LParent, "after asm operand constraint") {
+                return None;
+            }
+            let value = self.parse_next_expr(Precedence::Lowest, "as asm operand");
+            if !self.expect_peek(Token::RParent, "after asm operand") {
+                return None;
+            }
+            operands.push(AsmOperand {
+                name,
+                constraint,
+                value,
+                span: start.to(self.cur_span()),
+            });
+            if self.peek_tok() != Some(&Token::Comma) {
+                break;
+            }
+            self.next_tok();
+        }
+        Some(operands)
+    }
+
+    /// Cur token is the first of a run of labels like `case 1: case 2: x:`.
+    /// Ends on the last token of the statement they are attached to. The
+    /// labels are parsed in a loop, so long runs do not nest
+    pub(super) fn parse_labeled_stmt(&mut self) -> Option<Statement<'a>> {
+        let mut labels = Vec::new();
+        loop {
+            let start = self.cur_span();
+            labels.push((self.parse_label()?, start));
+            if !self.peek_starts_label() {
+                break;
+            }
+            self.next_tok();
+        }
+        let body = self.parse_sub_stmt("after label")?;
+        let end = self.cur_span();
+        let arena = self.arena;
+        let stmt = labels.into_iter().rev().fold(body, |body, (label, start)| {
+            &*arena.alloc(label.into_stmt(body, start.to(end)))
         });
-        self.next_tok();
-        let update_stmt = self.parse_stmt()?;
-        expect_tok!(self.cur_tok()?, Token::Semicolon, |tok| {
+        Some(stmt.clone())
+    }
+
+    /// Whether the next token starts a label
+    fn peek_starts_label(&self) -> bool {
+        match self.peek_tok() {
+            Some(Token::Case | Token::Default) => true,
+            Some(Token::Ident(_)) => self.tokens.get(self.tok_index + 2).is_some_and(|tok| tok.tok == Token::Colon),
+            _ => false,
+        }
+    }
+
+    /// Cur token is `case`, `default` or the label name. Ends on the colon
+    fn parse_label(&mut self) -> Option<Label<'a>> {
+        match self.cur_tok() {
+            Some(Token::Case) => {
+                self.check_in_switch("case");
+                let value = self.parse_next_expr(Precedence::Lowest, "as case value");
+                let range_end = if self.peek_tok() == Some(&Token::Ellipsis) {
+                    self.next_tok();
+                    self.require_gnu(self.cur_span(), "case ranges");
+                    Some(self.parse_next_expr(Precedence::Lowest, "as end of case range"))
+                } else {
+                    None
+                };
+                if !self.expect_peek(Token::Colon, "after case value") {
+                    return None;
+                }
+                Some(Label::Case {
+                    value: Box::new(value),
+                    range_end: range_end.map(Box::new),
+                })
+            }
+            Some(Token::Default) => {
+                self.check_in_switch("default");
+                if !self.expect_peek(Token::Colon, "after `default`") {
+                    return None;
+                }
+                Some(Label::Default)
+            }
+            _ => {
+                let name = self.expect_cur_ident("as label")?;
+                self.next_tok();
+                Some(Label::Named(name))
+            }
+        }
+    }
+
+    fn parse_switch(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        self.expect_peek(Token::LParent, "after `switch` keyword");
+        let cond = self.parse_next_expr(Precedence::Lowest, "as switch condition");
+        self.expect_peek(Token::RParent, "after switch condition");
+        self.switch_depth += 1;
+        let body = self.parse_sub_stmt("as switch body");
+        self.switch_depth -= 1;
+        Some(Statement::Switch(SwitchStmt {
+            cond,
+            body: body?,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    fn check_in_switch(&mut self, label: &str) {
+        if self.switch_depth == 0 {
             parser_error!(
-                "Expected semicolon after the update statement, received {tok:#?} instead"
-            )
-        });
+                self,
+                ErrorCode::MisplacedStatement,
+                self.cur_span(),
+                "`{label}` label not within a switch statement"
+            );
+        }
+    }
+
+    /// Cur token is the token before a statement that is part of another
+    /// one, like the colon of a label or the `)` of a loop condition. Ends on
+    /// the last token of the statement
+    fn parse_sub_stmt(&mut self, context: &str) -> Option<&'a Statement<'a>> {
         self.next_tok();
-        expect_tok!(self.cur_tok()?, Token::RParent, |tok| {
-            parser_error!("Expected Right Parenthesis after the update statement of the for loop, received {tok:#?} instead")
-        });
-        expect_tok!(self.peek_tok()?, Token::LCurly, |tok| {
-            parser_error!("Expected Left Curly Brackets after the for loop's control expression, received {tok:#?} instead")
-        });
+        let stmt = match self.cur_tok() {
+            Some(Token::Semicolon) => Statement::Empty(EmptyStmt {
+                span: self.cur_span(),
+            }),
+            Some(Token::RCurly) | None => {
+                self.unexpected_cur(&["statement"], context);
+                // Leave the closing bracket to the enclosing block
+                self.tok_index -= 1;
+                Statement::Empty(EmptyStmt {
+                    span: self.cur_span(),
+                })
+            }
+            _ => self.parse_stmt()?,
+        };
+        Some(self.arena.alloc(stmt))
+    }
+
+    fn parse_for(&mut self) -> Option<Statement<'a>> {
+        // Declarations in the init clause are scoped to the loop
+        self.push_scope();
+        let stmt = self.parse_for_clauses();
+        self.pop_scope();
+        stmt
+    }
+
+    fn parse_for_clauses(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        if !self.expect_peek(Token::LParent, "after `for` keyword") {
+            return None;
+        }
         self.next_tok();
-        let block = self.parse_block(Token::RCurly)?;
+        let init = match self.cur_tok() {
+            Some(Token::Semicolon) => None,
+            _ if self.starts_declaration_at(self.tok_index) => self.parse_for_declaration()?,
+            _ => {
+                let init = self.parse_expr(Precedence::Lowest)?;
+                if !self.expect_peek(Token::Semicolon, "after for loop init clause") {
+                    return None;
+                }
+                Some(ForInit::Expr(init))
+            }
+        };
+        let cond = match self.peek_tok() {
+            Some(Token::Semicolon) => None,
+            _ => Some(self.parse_next_expr(Precedence::Lowest, "as for loop condition")),
+        };
+        if !self.expect_peek(Token::Semicolon, "after for loop condition") {
+            return None;
+        }
+        let increment = match self.peek_tok() {
+            Some(Token::RParent) => None,
+            _ => Some(self.parse_next_expr(Precedence::Lowest, "as for loop increment")),
+        };
+        self.expect_peek(Token::RParent, "after for loop clauses");
+        let body = self.parse_loop_body("as for loop body")?;
         Some(Statement::For(ForStmt {
-            init_stmt: self.arena.alloc(init_stmt),
-            comp_expr,
-            update_stmt: self.arena.alloc(update_stmt),
-            block,
+            init,
+            cond,
+            increment,
+            body,
+            span: start.to(self.cur_span()),
         }))
     }
 
+    /// Cur token is the first declaration specifier of the init clause. Ends
+    /// on the `;`
+    fn parse_for_declaration(&mut self) -> Option<Option<ForInit<'a>>> {
+        match self.parse_declaration()? {
+            Statement::Variable(declaration)
+                if matches!(
+                    declaration.specifiers.data_storage_class,
+                    DataStorageClass::Auto | DataStorageClass::Register | DataStorageClass::None
+                ) =>
+            {
+                Some(Some(ForInit::Declaration(declaration)))
+            }
+            stmt => {
+                parser_error!(
+                    self,
+                    ErrorCode::MisplacedStatement,
+                    stmt.span(),
+                    "for loop init clause may only declare `auto` or `register` variables"
+                );
+                Some(None)
+            }
+        }
+    }
+
     fn parse_if(&mut self, if_type: IfType) -> Option<Statement<'a>> {
+        let start = self.cur_span();
         let cond = if if_type != IfType::Else {
             if if_type == IfType::ElseIf {
                 self.next_tok();
             }
 
-            expect_tok!(self.peek_tok()?, Token::LParent, |tok| {
-                parser_error!(
-                    "Expected Left Parenthesis after `if` keyword, received {tok:#?} instead"
-                )
-            });
-            // Skip If keyword
-            self.next_tok();
-            // Skip Left Parenthesis
-            self.next_tok();
-            let cond = self.parse_expr(Precedence::Lowest);
-            expect_tok!(self.peek_tok()?, Token::RParent, |tok| {
-                parser_error!("Expected Right Parenthesis after condition of if statement, received {tok:#?} instead")
-            });
-            // Skip Right Parenthesis
-            self.next_tok();
-            cond
+            self.expect_peek(Token::LParent, "after `if` keyword");
+            let cond = self.parse_next_expr(Precedence::Lowest, "as if condition");
+            self.expect_peek(Token::RParent, "after condition of if statement");
+            Some(cond)
         } else {
             None
         };
 
-        let block = if expect_tok!(self.peek_tok()?, Token::LCurly) {
-            self.next_tok();
-            self.parse_block(Token::RCurly)?
-        } else {
-            // Single line if statement
-            // FIXME: This does not work yet
-            self.next_tok();
-            BlockStmt {
-                block: vec![Statement::Expression(self.parse_expr(Precedence::Lowest)?)],
-            }
-        };
+        let body = self.parse_sub_stmt("as if body")?;
 
-        let alt = if expect_tok!(self.peek_tok()?, Token::Else) {
+        // An `else` belongs to the innermost `if`, which is parsing it first
+        let alt = if expect_tok!(self.peek_tok(), Some(Token::Else)) {
             self.next_tok();
-            let if_stmt = self.parse_if(if expect_tok!(self.peek_tok()?, Token::If) {
+            let if_stmt = self.parse_if(if expect_tok!(self.peek_tok(), Some(Token::If)) {
                 IfType::ElseIf
             } else {
                 IfType::Else
             });
             match if_stmt {
-                Some(Statement::If(if_stmt @ IfStmt { .. })) => Some(self.arena.alloc(if_stmt)),
-                None => None,
-                _ => unreachable!(),
+                Some(Statement::If(if_stmt @ IfStmt { .. })) => Some(&*self.arena.alloc(if_stmt)),
+                _ => None,
             }
         } else {
             None
@@ -191,328 +617,364 @@ impl<'a, 's: 'a> Parser<'a, 's> {
         Some(Statement::If(IfStmt {
             if_type,
             cond,
-            block,
-            alt: match alt {
-                Some(alt) => Some(alt),
-                None => None,
-            },
+            body,
+            alt,
+            span: start.to(self.cur_span()),
         }))
     }
 
-    pub(super) fn parse_variable(&mut self) -> Option<Statement<'a>> {
-        let mut var_type = if let Token::Ident(ident) = self.cur_tok()? {
-            Some(Type::Ident(&ident))
-        } else {
-            None
-        };
-        let mut is_const = false;
-        let mut is_volatile = false;
-        let mut data_storage_class = DataStorageClass::None;
-        while self.peek_tok()? != &Token::Assign
-            && self.peek_tok()? != &Token::Semicolon
-            && self.peek_tok()? != &Token::LSquare
-        {
-            match *self.cur_tok()? {
-                // TODO: Allow multiple const/volatile
-                Token::Volatile => {
-                    encounter_modifier!(
-                        is_volatile,
-                        "Encountered second `volatile` variable modifier"
-                    )
-                }
-                Token::Const => {
-                    encounter_modifier!(
-                        is_const,
-                        "Encountered second `const` variable specification"
-                    )
-                }
-                Token::Auto => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Auto)
+    /// Whether `params` is the identifier list of an old-style definition
+    /// and is followed by its parameter declarations or the body. Cur token
+    /// is the `)` of the list
+    fn starts_param_declarations(&self, params: &[Param<'a>]) -> bool {
+        let is_identifier_list = !params.is_empty()
+            && params.iter().all(|param| match param.data_type {
+                Type::Ident { name, qualifiers, .. } => {
+                    param.name.is_none()
+                        && qualifiers.is_empty()
+                        && param.attributes.is_empty()
+                        && !self.is_typedef_name(name)
                 }
-                Token::Static => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Static)
-                }
-                Token::Register => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Register)
-                }
-                Token::Extern => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Extern)
+                _ => false,
+            });
+        is_identifier_list
+            && (self.starts_declaration_at(self.tok_index + 1)
+                || (self.peek_tok() == Some(&Token::LCurly) && self.options.standard < CStandard::C23))
+    }
+
+    /// Cur token is the `)` of an identifier list. Ends before the `{` of the
+    /// body
+    fn parse_param_declarations(&mut self, params: &mut [Param<'a>]) -> Option<()> {
+        for param in params.iter_mut() {
+            if let Type::Ident { name, .. } = param.data_type {
+                param.name = Some(name);
+            }
+        }
+        if self.options.standard >= CStandard::C23 {
+            parser_error!(
+                self,
+                ErrorCode::UnavailableInStandard,
+                self.peek_span(),
+                "old-style function definitions were removed in C23"
+            );
+        }
+        let mut declared = vec![false; params.len()];
+        while !matches!(self.peek_tok(), Some(Token::LCurly) | None) {
+            self.next_tok();
+            let specifiers = self.parse_specifiers()?;
+            let base = self.specified_type(specifiers)?;
+            loop {
+                let declarator = self.parse_declarator(base.clone(), DeclaratorKind::Named)?;
+                let Some(name) = declarator.name else {
+                    self.unexpected_peek(&["identifier"], "in parameter declaration");
+                    return None;
+                };
+                match params.iter().position(|param| param.name == Some(name)) {
+                    Some(index) if declared[index] => {
+                        parser_error!(
+                            self,
+                            ErrorCode::InvalidParameters,
+                            declarator.data_type.span(),
+                            "redeclaration of parameter `{name}`"
+                        );
+                    }
+                    Some(index) => {
+                        declared[index] = true;
+                        let param = &mut params[index];
+                        param.adjusted_type = self.adjust_param_type(&declarator.data_type);
+                        param.data_type = declarator.data_type;
+                        param.attributes = declarator.attributes;
+                    }
+                    None => {
+                        parser_error!(
+                            self,
+                            ErrorCode::InvalidParameters,
+                            declarator.data_type.span(),
+                            "declaration for parameter `{name}` but no such parameter"
+                        );
+                    }
                 }
-                Token::Asterisk => {
-                    let type_ref = self.arena.alloc(var_type.unwrap());
-                    let mut is_restricted = false;
-                    let mut is_const = false;
-                    while let Token::Restrict | Token::Const = self.peek_tok()? {
-                        match self.peek_tok()? {
-                            Token::Restrict => is_restricted = true,
-                            Token::Const => is_const = true,
-                            _ => unreachable!(),
-                        }
+                match self.peek_tok() {
+                    Some(Token::Comma) => self.next_tok(),
+                    Some(Token::Semicolon) => {
                         self.next_tok();
+                        break;
+                    }
+                    _ => {
+                        self.unexpected_peek(&["`,`", "`;`"], "after parameter declarator");
+                        return None;
                     }
-                    var_type = Some(Type::Pointer {
-                        data_type: type_ref,
-                        is_const,
-                        is_restricted,
-                    });
                 }
-                //Token::Signed => todo!(),
-                //Token::Unsigned => todo!(),
-                Token::Enum => var_type = self.encounter_cdt_pointer(CompositeDataType::Enum),
-                Token::Struct => var_type = self.encounter_cdt_pointer(CompositeDataType::Struct),
-                Token::Union => var_type = self.encounter_cdt_pointer(CompositeDataType::Union),
-                Token::Ident(ident) => match var_type {
-                    Some(_) => (),
-                    None => var_type = Some(Type::Ident(ident)),
-                },
-                tok => todo!("{tok:?}"),
             }
-            self.next_tok();
         }
-        let name = match self.cur_tok()? {
-            Token::Ident(ident) => *ident,
-            _ => unreachable!(),
-        };
-        if let Token::LSquare = self.peek_tok()? {
-            self.next_tok();
-            let size: Option<usize> = match *self.peek_tok()? {
-                Token::LitInt(int) => {
-                    self.next_tok();
-                    Some(int.parse().unwrap())
-                }
-                _ => None,
-            };
-            let alloc = self.arena.alloc(var_type.unwrap());
-            var_type = Some(Type::Array {
-                data_type: alloc,
-                size,
-            });
-            self.next_tok();
+        if self.peek_tok() != Some(&Token::LCurly) {
+            self.unexpected_peek(&["`{`"], "after parameter declarations");
+            return None;
         }
-        self.next_tok();
-        let expr = match self.cur_tok()? {
-            Token::Assign => {
-                self.next_tok();
-                self.parse_expr(Precedence::Lowest)
-            }
-            Token::Semicolon => None,
-            _ => todo!(),
-        };
-        if expect_tok!(self.peek_tok()?, Token::Semicolon, |tok| {
-            parser_error!(
-                "Expected semicolon after variable definition, received: {tok:?} instead"
+        for (param, _) in params.iter_mut().zip(declared).filter(|(_, declared)| !declared) {
+            let name = param.name.unwrap_or_default();
+            parser_warn!(
+                self,
+                ErrorCode::InvalidParameters,
+                param.span,
+                "type of parameter `{name}` defaults to `int`"
             );
-        }) {
-            // go to semicolon
-            self.next_tok();
-        };
-        Some(Statement::Variable(VariableStmt {
-            name,
-            is_volatile,
-            is_const,
-            data_storage_class,
-            data_type: var_type?,
-            val: expr,
-        }))
+            param.data_type = Type::Primitive {
+                kind: PrimitiveKind::Int,
+                qualifiers: Qualifiers::NONE,
+                span: param.span,
+            };
+            param.adjusted_type = param.data_type.clone();
+        }
+        Some(())
     }
 
-    pub(super) fn parse_function(&mut self) -> Option<Statement<'a>> {
-        let mut ret_type = if let Token::Ident(ident) = self.cur_tok()? {
-            Some(Type::Ident(&ident))
-        } else {
-            None
-        };
-        let mut should_inline = false;
-        let mut is_volatile = false;
-        let mut data_storage_class = DataStorageClass::None;
-        while *self.peek_tok()? != Token::LParent {
-            match *self.cur_tok()? {
-                Token::Volatile => {
-                    encounter_modifier!(is_volatile, "Encountered second `volatile` specification")
-                }
-                Token::Inline => {
-                    encounter_modifier!(should_inline, "Encountered second `inline` specification")
-                }
-                Token::Static => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Static)
-                }
-                Token::Extern => {
-                    encounter_dsc_modifier!(data_storage_class, DataStorageClass::Extern)
-                }
-                Token::Asterisk => {
-                    let type_ref = self.arena.alloc(ret_type.unwrap());
-                    let is_restricted = if let Token::Restrict = self.peek_tok()? {
-                        self.next_tok();
-                        true
-                    } else {
-                        false
-                    };
-                    let is_const = if let Token::Const = self.peek_tok()? {
-                        self.next_tok();
-                        true
-                    } else {
-                        false
-                    };
-                    ret_type = Some(Type::Pointer {
-                        data_type: type_ref,
-                        is_const,
-                        is_restricted,
-                    });
-                }
-                //Token::Signed => todo!(),
-                //Token::Unsigned => todo!(),
-                Token::Enum => ret_type = self.encounter_cdt_pointer(CompositeDataType::Enum),
-                Token::Struct => ret_type = self.encounter_cdt_pointer(CompositeDataType::Struct),
-                Token::Union => ret_type = self.encounter_cdt_pointer(CompositeDataType::Union),
-                Token::Ident(ident) => match ret_type {
-                    Some(_) => (),
-                    None => ret_type = Some(Type::Ident(ident)),
-                },
-                tok => todo!("{tok:?}"),
+    /// Cur token is the first declaration specifier. Ends on the `;` or the
+    /// closing bracket of a function body
+    pub(super) fn parse_declaration(&mut self) -> Option<Statement<'a>> {
+        let start = self.cur_span();
+        let specifiers = self.parse_specifiers()?;
+        let mut decl_specifiers = specifiers.decl.clone();
+        let base = self.specified_type(specifiers)?;
+        if decl_specifiers.data_storage_class == DataStorageClass::Typedef {
+            return self.parse_typedef(start, decl_specifiers, base);
+        }
+        if self.peek_tok() == Some(&Token::Semicolon) {
+            if let Some(stmt) = self.tag_declaration(&base, start.to(self.peek_span())) {
+                self.next_tok();
+                return Some(stmt);
             }
-            self.next_tok();
         }
-        let name = match self.cur_tok()? {
-            Token::Ident(ident) => *ident,
-            _ => unreachable!(),
+        let declarator = self.parse_declarator(base.clone(), DeclaratorKind::Named)?;
+        let Some(name) = declarator.name else {
+            self.unexpected_peek(&["identifier"], "in declaration");
+            return None;
         };
-        expect_tok!(self.peek_tok()?, Token::LParent, |tok| {
-            parser_error!(
-                "Expected left parenthesis after function name, recevied {tok:?} instead"
-            );
-        });
-        let args = self.parse_field_list(Token::Comma, Token::RParent)?;
-        expect_tok!(self.peek_tok()?, Token::RParent, |tok| {
-            parser_error!("Expected right parenthesis after arguments, received {tok:?} instead");
-        });
-        self.next_tok();
-        match self.peek_tok()? {
-            Token::Semicolon => {
-                self.next_tok();
+        self.declare(name, false);
+        match declarator.data_type {
+            // Function declarators followed by `,` or `=` are part of a
+            // declaration list
+            Type::Function {
+                return_type,
+                mut params,
+                is_variadic,
+                mut style,
+                ..
+            } if matches!(self.peek_tok(), Some(Token::Semicolon | Token::LCurly))
+                || self.starts_param_declarations(&params) =>
+            {
+                if self.starts_param_declarations(&params) {
+                    self.parse_param_declarations(&mut params)?;
+                    style = ParamStyle::Identifiers;
+                }
                 self.next_tok();
+                let body = match self.cur_tok() {
+                    Some(Token::LCurly) => {
+                        // Parameters are visible in the body
+                        self.push_scope();
+                        for param in &params {
+                            if let Some(name) = param.name {
+                                self.declare(name, false);
+                            }
+                        }
+                        let body = self.parse_block(Token::RCurly);
+                        self.pop_scope();
+                        body
+                    }
+                    _ => None,
+                };
+                // The function has no declarator of its own to attach them to
+                decl_specifiers.attributes.extend(declarator.attributes);
                 Some(Statement::Function(FunctionStmt {
                     name,
-                    is_volatile,
-                    should_inline,
-                    data_storage_class,
-                    args,
-                    ret_data_type: ret_type?,
-                    body: None,
+                    specifiers: decl_specifiers,
+                    params,
+                    is_variadic,
+                    style,
+                    ret_data_type: return_type.clone(),
+                    body,
+                    span: start.to(self.cur_span()),
                 }))
             }
-            Token::LCurly => {
-                self.next_tok();
-                let func = Some(Statement::Function(FunctionStmt {
-                    name,
-                    is_volatile,
-                    should_inline,
-                    data_storage_class,
-                    args,
-                    ret_data_type: ret_type?,
-                    body: self.parse_block(Token::RCurly),
-                }));
-                self.next_tok();
-                self.next_tok();
-                func
-            }
-            tok => {
-                parser_error!("Expected semicolon or left curly brackets after function argument parenthesis, received {tok:?} instead");
-                panic!()
+            data_type => {
+                let mut declarators = Vec::new();
+                let mut name = name;
+                let mut data_type = data_type;
+                let mut attributes = declarator.attributes;
+                loop {
+                    let decl_start = data_type.span();
+                    let init = match self.peek_tok() {
+                        Some(Token::Assign) => {
+                            self.next_tok();
+                            Some(self.parse_initializer("as variable value")?)
+                        }
+                        _ => None,
+                    };
+                    declarators.push(InitDeclarator {
+                        name,
+                        data_type,
+                        attributes,
+                        init,
+                        span: decl_start.to(self.cur_span()),
+                    });
+                    match self.peek_tok() {
+                        Some(Token::Comma) => self.next_tok(),
+                        Some(Token::Semicolon) => {
+                            self.next_tok();
+                            break;
+                        }
+                        _ => {
+                            self.unexpected_peek(&["`,`", "`=`", "`;`"], "after variable declarator");
+                            break;
+                        }
+                    }
+                    let declarator = self.parse_declarator(base.clone(), DeclaratorKind::Named)?;
+                    let Some(next_name) = declarator.name else {
+                        self.unexpected_peek(&["identifier"], "in declaration");
+                        return None;
+                    };
+                    self.declare(next_name, false);
+                    name = next_name;
+                    data_type = declarator.data_type;
+                    attributes = declarator.attributes;
+                }
+                Some(Statement::Variable(VariableStmt {
+                    specifiers: decl_specifiers,
+                    declarators,
+                    span: start.to(self.cur_span()),
+                }))
             }
         }
     }
 
-    /// First token needs to be the token before the first type
-    fn parse_field_list(&mut self, seperator: Token<'a>, end: Token<'a>) -> Option<Vec<Field<'a>>> {
-        let mut fields: Vec<Field<'a>> = Vec::new();
-        self.next_tok();
-        // manually parse first field
-        let field = if self.peek_tok()? != &end {
-            self.next_tok();
-            let mut field_type = self.parse_type()?;
-            self.next_tok();
-            let name = match *self.cur_tok()? {
-                Token::Ident(ident) => ident,
-                _ => todo!(),
+    /// Cur token is the last declaration specifier. Ends on the `;`
+    fn parse_typedef(&mut self, start: Span, specifiers: DeclSpecifiers<'a>, base: Type<'a>) -> Option<Statement<'a>> {
+        let mut declarators = Vec::new();
+        loop {
+            let declarator = self.parse_declarator(base.clone(), DeclaratorKind::Named)?;
+            let Some(name) = declarator.name else {
+                self.unexpected_peek(&["identifier"], "as typedef name");
+                return None;
             };
-            if let Token::LSquare = self.peek_tok()? {
-                self.next_tok();
-                let size: Option<usize> = match *self.peek_tok()? {
-                    Token::LitInt(int) => {
-                        self.next_tok();
-                        Some(int.parse().unwrap())
-                    }
-                    _ => None,
-                };
-                let type_ = self.arena.alloc(field_type);
-                field_type = Type::Array {
-                    data_type: type_,
-                    size,
-                };
-                self.next_tok();
+            self.declare(name, true);
+            declarators.push(TypedefDeclarator {
+                name,
+                span: declarator.data_type.span().to(self.cur_span()),
+                data_type: declarator.data_type,
+                attributes: declarator.attributes,
+            });
+            match self.peek_tok() {
+                Some(Token::Comma) => self.next_tok(),
+                Some(Token::Semicolon) => {
+                    self.next_tok();
+                    break;
+                }
+                _ => {
+                    self.unexpected_peek(&["`,`", "`;`"], "after typedef name");
+                    break;
+                }
             }
-            Field { name, field_type }
-        } else {
-            return Some(vec![]);
-        };
-        fields.push(field);
-        while self.peek_tok()? == &seperator && self.peek_tok()? != &end {
-            self.next_tok();
-            self.next_tok();
-            let mut type_ = self.parse_type();
-            self.next_tok();
-            let name = *match self.cur_tok()? {
-                Token::Ident(ident) => ident,
-                _ => todo!(),
-            };
-            if let Token::LSquare = self.peek_tok()? {
-                self.next_tok();
-                let size: Option<usize> = match *self.peek_tok()? {
-                    Token::LitInt(int) => {
-                        self.next_tok();
-                        Some(int.parse().unwrap())
-                    }
-                    _ => None,
-                };
-                let alloc = self.arena.alloc(type_.unwrap());
-                type_ = Some(Type::Array {
-                    data_type: alloc,
-                    size,
-                });
-                self.next_tok();
+        }
+        Some(Statement::Typedef(TypedefStmt {
+            specifiers,
+            declarators,
+            span: start.to(self.cur_span()),
+        }))
+    }
+
+    /// The definition or forward declaration of a declaration without
+    /// declarators like `struct P { int x; };`
+    fn tag_declaration(&self, data_type: &Type<'a>, span: Span) -> Option<Statement<'a>> {
+        Some(match *data_type {
+            Type::Struct { definition: Some(definition), .. } => {
+                Statement::Struct(StructStmt { span, ..definition.clone() })
             }
-            fields.push(Field {
+            Type::Struct { name, .. } => Statement::Struct(StructStmt {
                 name,
-                field_type: type_?,
-            })
-        }
-        Some(fields)
+                attributes: Vec::new(),
+                fields: None,
+                span,
+            }),
+            Type::Union { definition: Some(definition), .. } => {
+                Statement::Union(UnionStmt { span, ..definition.clone() })
+            }
+            Type::Union { name, .. } => Statement::Union(UnionStmt {
+                name,
+                attributes: Vec::new(),
+                fields: None,
+                span,
+            }),
+            Type::Enum { definition: Some(definition), .. } => {
+                Statement::Enum(EnumStmt { span, ..definition.clone() })
+            }
+            Type::Enum { name, .. } => Statement::Enum(EnumStmt {
+                name,
+                attributes: Vec::new(),
+                underlying_type: None,
+                variants: None,
+                span,
+            }),
+            _ => return None,
+        })
     }
 
-    fn parse_block(&mut self, end: Token) -> Option<BlockStmt<'a>> {
+    /// Cur token is the left bracket. Ends on `end`
+    pub(super) fn parse_block(&mut self, end: Token) -> Option<BlockStmt<'a>> {
+        let start = self.cur_span();
         let mut block = Vec::new();
+        self.push_scope();
         self.next_tok();
         while self.cur_tok() != Some(&end) {
-            block.push(match self.parse_stmt() {
-                Some(stmt) => stmt,
-                None => break,
-            });
-            self.next_tok();
+            if self.cur_tok().is_none() {
+                self.unexpected_eof(&[&format!("`{end}`")], "at the end of block");
+                break;
+            }
+            if let Some(stmt) = self.parse_stmt_recovering() {
+                block.push(stmt);
+                self.next_tok();
+            }
         }
+        self.pop_scope();
+
+        Some(BlockStmt {
+            block,
+            span: start.to(self.cur_span()),
+        })
+    }
 
-        Some(BlockStmt { block: block })
+    pub(super) fn expect_cur_ident(&mut self, context: &str) -> Option<&'a str> {
+        match self.cur_tok() {
+            Some(Token::Ident(ident)) => Some(*ident),
+            _ => {
+                self.unexpected_cur(&["identifier"], context);
+                None
+            }
+        }
     }
+}
 
-    fn encounter_cdt_pointer(&mut self, _type: CompositeDataType) -> Option<Type<'a>> {
-        let name = *match self.peek_tok()? {
-            Token::Ident(ident) => ident,
-            _ => unreachable!(),
-        };
-        self.next_tok();
-        match _type {
-            CompositeDataType::Struct => Some(Type::Struct(name)),
-            CompositeDataType::Enum => Some(Type::Enum(name)),
-            CompositeDataType::Union => Some(Type::Union(name)),
+/// Label of a statement that is not parsed yet
+enum Label<'a> {
+    Named(Ident<'a>),
+    /// Boxed because expressions are much larger than the other variants
+    Case {
+        value: Box<Expression<'a>>,
+        range_end: Option<Box<Expression<'a>>>,
+    },
+    Default,
+}
+
+impl<'a> Label<'a> {
+    fn into_stmt(self, body: &'a Statement<'a>, span: Span) -> Statement<'a> {
+        match self {
+            Label::Named(name) => Statement::Label(LabelStmt { name, body, span }),
+            Label::Case { value, range_end } => Statement::Case(CaseStmt {
+                value: *value,
+                range_end: range_end.map(|range_end| *range_end),
+                body,
+                span,
+            }),
+            Label::Default => Statement::Default(DefaultStmt { body, span }),
         }
     }
 }
@@ -1,69 +1,1401 @@
-use crate::{ast::types::Type, lexer::tokens::Token, parser_error};
+use crate::{
+    ast::{
+        expr::Expression,
+        stmt::{
+            Alignment, Attribute, DataStorageClass, DeclSpecifiers, EnumStmt, Enumerator, Field, StructStmt,
+            UnionStmt,
+        },
+        types::{ArraySize, Param, ParamStyle, PrimitiveKind, Qualifiers, Type, TypeOfOperand},
+        Ident,
+    },
+    diagnostic::ErrorCode,
+    encounter_dsc_modifier, encounter_modifier,
+    lexer::tokens::Token,
+    parser_error, parser_warn,
+    span::Span,
+};
 
-use super::{CompositeDataType, Parser};
+use super::{expr::Precedence, CStandard, CompositeDataType, Parser};
+
+/// Type specifiers of primitive types that the lexer does not treat as
+/// keywords
+const PRIMITIVE_KEYWORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "float", "double", "_Bool", "bool", "_Complex", "_BitInt", "__int128",
+];
+
+/// Other declaration specifiers that the lexer does not treat as keywords
+const SPECIFIER_KEYWORDS: &[&str] = &[
+    "_Atomic", "_Noreturn", "_Thread_local", "thread_local", "constexpr", "_Alignas", "alignas", "typeof",
+    "typeof_unqual", "__typeof__",
+];
+
+/// How often each primitive type specifier was given
+#[derive(Debug, Default)]
+struct PrimitiveSpecifiers {
+    void: u8,
+    char: u8,
+    short: u8,
+    int: u8,
+    long: u8,
+    float: u8,
+    double: u8,
+    bool: u8,
+    complex: u8,
+    signed: u8,
+    unsigned: u8,
+    bit_int: u8,
+    width: u16,
+    int128: u8,
+    /// Covers all primitive specifiers, `None` if there are none
+    span: Option<Span>,
+}
+
+impl PrimitiveSpecifiers {
+    /// The type named by the specifiers, `None` for invalid combinations
+    fn resolve(&self) -> Option<PrimitiveKind> {
+        let counts = [
+            self.void,
+            self.char,
+            self.short,
+            self.int,
+            self.float,
+            self.double,
+            self.bool,
+            self.complex,
+            self.bit_int,
+            self.int128,
+            self.signed + self.unsigned,
+        ];
+        // Only `long` may be repeated
+        if counts.iter().any(|count| *count > 1) || self.long > 2 {
+            return None;
+        }
+        let total = counts.iter().sum::<u8>() + self.long;
+        let sign = self.signed + self.unsigned;
+        let is_unsigned = self.unsigned == 1;
+        let integer = |signed, unsigned| if is_unsigned { unsigned } else { signed };
+        let kind = if self.void == 1 {
+            (total == 1).then_some(PrimitiveKind::Void)?
+        } else if self.bool == 1 {
+            (total == 1).then_some(PrimitiveKind::Bool)?
+        } else if self.bit_int == 1 {
+            (total == 1 + sign).then_some(PrimitiveKind::BitInt {
+                width: self.width,
+                is_unsigned,
+            })?
+        } else if self.int128 == 1 {
+            (total == 1 + sign).then_some(integer(PrimitiveKind::Int128, PrimitiveKind::UnsignedInt128))?
+        } else if self.char == 1 {
+            (total == 1 + sign).then_some(match (self.signed, self.unsigned) {
+                (1, _) => PrimitiveKind::SignedChar,
+                (_, 1) => PrimitiveKind::UnsignedChar,
+                _ => PrimitiveKind::Char,
+            })?
+        } else if self.float == 1 {
+            (total == 1 + self.complex).then_some(match self.complex {
+                1 => PrimitiveKind::FloatComplex,
+                _ => PrimitiveKind::Float,
+            })?
+        } else if self.double == 1 {
+            (total == 1 + self.complex + self.long && self.long < 2).then_some(match (self.long, self.complex) {
+                (1, 1) => PrimitiveKind::LongDoubleComplex,
+                (1, _) => PrimitiveKind::LongDouble,
+                (_, 1) => PrimitiveKind::DoubleComplex,
+                _ => PrimitiveKind::Double,
+            })?
+        } else if self.complex == 1 || (self.short == 1 && self.long > 0) {
+            return None;
+        } else if self.short == 1 {
+            integer(PrimitiveKind::Short, PrimitiveKind::UnsignedShort)
+        } else if self.long == 2 {
+            integer(PrimitiveKind::LongLong, PrimitiveKind::UnsignedLongLong)
+        } else if self.long == 1 {
+            integer(PrimitiveKind::Long, PrimitiveKind::UnsignedLong)
+        } else {
+            integer(PrimitiveKind::Int, PrimitiveKind::UnsignedInt)
+        };
+        Some(kind)
+    }
+}
+
+/// Declaration specifiers in front of the declarators of a declaration
+pub(super) struct Specifiers<'a> {
+    /// The specified type including its qualifiers
+    pub data_type: Option<Type<'a>>,
+    pub decl: DeclSpecifiers<'a>,
+    pub span: Span,
+}
+
+/// Whether a declarator must, may or must not declare a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DeclaratorKind {
+    Named,
+    /// Parameters, which may be unnamed
+    Optional,
+    /// Type names of casts and `sizeof`
+    Abstract,
+}
+
+/// The name and type declared by a declarator
+pub(super) struct Declarator<'a> {
+    pub name: Option<Ident<'a>>,
+    pub data_type: Type<'a>,
+    /// Attributes after the declarator
+    pub attributes: Vec<Attribute<'a>>,
+}
+
+/// Array or function declarator suffix, applied to the type after all
+/// suffixes to its right
+enum Suffix<'a> {
+    Array {
+        size: ArraySize<'a>,
+        is_static: bool,
+        qualifiers: Qualifiers,
+        span: Span,
+    },
+    Function {
+        params: Vec<Param<'a>>,
+        is_variadic: bool,
+        style: ParamStyle,
+        span: Span,
+    },
+}
 
 impl<'a, 's: 'a> Parser<'a, 's> {
-    /// First token needs to be the first token of the type
+    /// Parses a type name as used in casts. First token needs to be the first
+    /// token of the type. Ends on the last token of the type
     pub(super) fn parse_type(&mut self) -> Option<Type<'a>> {
-        match self.cur_tok()? {
-            Token::Signed => todo!(),
-            Token::Unsigned => todo!(),
-            Token::Enum => {
-                let id = self.id_for_cdt_ptr(CompositeDataType::Enum)?;
-                self.next_tok();
-                Some(self.parse_ptr(Type::Enum(id))?)
+        let specifiers = self.parse_specifiers()?;
+        let base = self.specified_type(specifiers)?;
+        Some(self.parse_declarator(base, DeclaratorKind::Abstract)?.data_type)
+    }
+
+    /// Whether the identifier names a type
+    pub(super) fn is_type_name(&self, name: &str) -> bool {
+        self.is_primitive_keyword(name) || self.is_typedef_name(name)
+    }
+
+    fn is_primitive_keyword(&self, name: &str) -> bool {
+        PRIMITIVE_KEYWORDS.contains(&name) && self.is_keyword(name)
+    }
+
+    /// Whether the identifier is a declaration specifier other than a
+    /// primitive type
+    fn is_specifier_ident(&self, name: &str) -> bool {
+        SPECIFIER_KEYWORDS.contains(&name) && self.is_keyword(name)
+    }
+
+    /// Whether the token at `index` starts a declaration
+    pub(super) fn starts_declaration_at(&self, index: usize) -> bool {
+        self.tokens.get(index).is_some_and(|tok| is_specifier_keyword(&tok.tok)) || self.starts_type_name(index)
+    }
+
+    /// Whether the token at `index` starts a type name
+    pub(super) fn starts_type_name(&self, index: usize) -> bool {
+        match self.tokens.get(index).map(|tok| &tok.tok) {
+            Some(
+                Token::Const
+                | Token::Volatile
+                | Token::Restrict
+                | Token::Signed
+                | Token::Unsigned
+                | Token::Struct
+                | Token::Union
+                | Token::Enum,
+            ) => true,
+            Some(Token::Ident(name)) => self.is_type_name(name) || self.is_specifier_ident(name),
+            _ => false,
+        }
+    }
+
+    /// Cur token is the first specifier. Ends on the last specifier
+    pub(super) fn parse_specifiers(&mut self) -> Option<Specifiers<'a>> {
+        let start = self.cur_span();
+        let mut data_type = None;
+        let mut qualifiers = Qualifiers::NONE;
+        let mut decl = DeclSpecifiers {
+            attributes: Vec::new(),
+            is_extension: false,
+            data_storage_class: DataStorageClass::None,
+            is_thread_local: false,
+            is_constexpr: false,
+            is_inline: false,
+            is_noreturn: false,
+            alignment: Vec::new(),
+            span: start,
+        };
+        let mut primitive = PrimitiveSpecifiers::default();
+        // `auto` without a type infers it in C23
+        let mut auto_span = None;
+        loop {
+            let span = self.cur_span();
+            let specified = match self.cur_tok() {
+                _ if self.starts_attributes_at(self.tok_index) => {
+                    let attributes = self.parse_attributes()?;
+                    decl.attributes.extend(attributes);
+                    None
+                }
+                Some(Token::Ident("__extension__")) => {
+                    self.require_gnu(span, "`__extension__`");
+                    decl.is_extension = true;
+                    None
+                }
+                Some(Token::Ident("_Atomic")) if self.peek_tok() == Some(&Token::LParent) => {
+                    self.require_standard(CStandard::C11, span, "`_Atomic`");
+                    Some(self.parse_atomic_type()?)
+                }
+                Some(Token::Const | Token::Volatile | Token::Restrict | Token::Ident("_Atomic")) => {
+                    self.add_qualifier(&mut qualifiers);
+                    None
+                }
+                Some(Token::Inline) => {
+                    self.require_standard(CStandard::C99, span, "`inline`");
+                    encounter_modifier!(self, decl.is_inline, "Encountered second `inline` specifier");
+                    None
+                }
+                Some(Token::Ident("_Noreturn")) => {
+                    self.require_standard(CStandard::C11, span, "`_Noreturn`");
+                    encounter_modifier!(self, decl.is_noreturn, "Encountered second `_Noreturn` specifier");
+                    None
+                }
+                Some(Token::Ident(name @ ("_Thread_local" | "thread_local"))) if self.is_keyword(name) => {
+                    self.require_standard(CStandard::C11, span, "`_Thread_local`");
+                    encounter_modifier!(self, decl.is_thread_local, "Encountered second `_Thread_local` specifier");
+                    None
+                }
+                Some(Token::Ident("constexpr")) if self.is_keyword("constexpr") => {
+                    encounter_modifier!(self, decl.is_constexpr, "Encountered second `constexpr` specifier");
+                    None
+                }
+                Some(Token::Ident(name @ ("_Alignas" | "alignas"))) if self.is_keyword(name) => {
+                    self.require_standard(CStandard::C11, span, "`_Alignas`");
+                    let alignment = self.parse_alignment()?;
+                    decl.alignment.push(alignment);
+                    None
+                }
+                Some(Token::Ident(name @ ("typeof" | "typeof_unqual" | "__typeof__"))) if self.is_keyword(name) => {
+                    Some(self.parse_typeof()?)
+                }
+                Some(Token::Auto) => {
+                    encounter_dsc_modifier!(self, decl.data_storage_class, DataStorageClass::Auto);
+                    auto_span = Some(span);
+                    None
+                }
+                Some(Token::Static) => {
+                    encounter_dsc_modifier!(self, decl.data_storage_class, DataStorageClass::Static);
+                    None
+                }
+                Some(Token::Register) => {
+                    encounter_dsc_modifier!(self, decl.data_storage_class, DataStorageClass::Register);
+                    None
+                }
+                Some(Token::Extern) => {
+                    encounter_dsc_modifier!(self, decl.data_storage_class, DataStorageClass::Extern);
+                    None
+                }
+                Some(Token::Typedef) => {
+                    encounter_dsc_modifier!(self, decl.data_storage_class, DataStorageClass::Typedef);
+                    None
+                }
+                Some(Token::Signed) => {
+                    primitive.signed += 1;
+                    self.extend_primitive(&mut primitive, span);
+                    None
+                }
+                Some(Token::Unsigned) => {
+                    primitive.unsigned += 1;
+                    self.extend_primitive(&mut primitive, span);
+                    None
+                }
+                Some(Token::Ident(name)) if self.is_primitive_keyword(name) => {
+                    let name = *name;
+                    self.parse_primitive_keyword(name, &mut primitive)?;
+                    self.extend_primitive(&mut primitive, span);
+                    None
+                }
+                Some(Token::Enum) => Some(self.parse_tagged_type(CompositeDataType::Enum)?),
+                Some(Token::Struct) => Some(self.parse_tagged_type(CompositeDataType::Struct)?),
+                Some(Token::Union) => Some(self.parse_tagged_type(CompositeDataType::Union)?),
+                Some(Token::Ident(name)) => Some(Type::Ident {
+                    name,
+                    qualifiers: Qualifiers::NONE,
+                    span,
+                }),
+                Some(tok) => {
+                    let tok = *tok;
+                    parser_error!(self, ErrorCode::ExpectedType, span, "expected type, found `{tok}`");
+                    return None;
+                }
+                None => {
+                    self.unexpected_eof(&["type"], "");
+                    return None;
+                }
+            };
+            if let Some(specified) = specified {
+                if data_type.is_some() {
+                    parser_error!(
+                        self,
+                        ErrorCode::DuplicateSpecifier,
+                        specified.span(),
+                        "two or more data types in declaration specifiers"
+                    );
+                }
+                data_type = Some(specified);
             }
-            Token::Struct => {
-                let id = self.id_for_cdt_ptr(CompositeDataType::Struct)?;
-                self.next_tok();
-                Some(self.parse_ptr(Type::Struct(id))?)
+            let more = match self.peek_tok() {
+                Some(Token::Ident("__attribute__" | "__extension__")) => true,
+                Some(Token::Ident(name)) if self.is_primitive_keyword(name) || self.is_specifier_ident(name) => true,
+                // An identifier after `auto` is the declared name of an
+                // inferred type, unless it names a type
+                Some(Token::Ident(name)) if auto_span.is_some() && self.options.standard >= CStandard::C23 => {
+                    data_type.is_none() && primitive.span.is_none() && self.is_typedef_name(name)
+                }
+                // An identifier after the type is the declared name
+                Some(Token::Ident(_)) => data_type.is_none() && primitive.span.is_none(),
+                Some(Token::LSquare) => self.tokens.get(self.tok_index + 2).is_some_and(|tok| tok.tok == Token::LSquare),
+                Some(tok) => is_specifier_keyword(tok),
+                None => false,
+            };
+            if !more {
+                break;
             }
-            Token::Union => {
-                let id = self.id_for_cdt_ptr(CompositeDataType::Union)?;
+            self.next_tok();
+        }
+        if let Some(span) = primitive.span {
+            if data_type.is_some() {
+                parser_error!(
+                    self,
+                    ErrorCode::DuplicateSpecifier,
+                    span,
+                    "two or more data types in declaration specifiers"
+                );
+            } else {
+                if primitive.long == 2 {
+                    self.require_standard(CStandard::C99, span, "`long long`");
+                }
+                let kind = primitive.resolve().unwrap_or_else(|| {
+                    parser_error!(
+                        self,
+                        ErrorCode::InvalidTypeSpecifiers,
+                        span,
+                        "invalid combination of type specifiers"
+                    );
+                    PrimitiveKind::Int
+                });
+                data_type = Some(Type::Primitive {
+                    kind,
+                    qualifiers: Qualifiers::NONE,
+                    span,
+                });
+            }
+        }
+        if let Some(auto_span) = auto_span.filter(|_| data_type.is_none() && self.options.standard >= CStandard::C23) {
+            decl.data_storage_class = DataStorageClass::None;
+            data_type = Some(Type::Auto {
+                qualifiers: Qualifiers::NONE,
+                span: auto_span,
+            });
+        }
+        let span = start.to(self.cur_span());
+        decl.span = span;
+        Some(Specifiers {
+            data_type: data_type.map(|data_type| qualify(data_type, qualifiers)),
+            decl,
+            span,
+        })
+    }
+
+    /// Cur token is a type qualifier, which is added to `qualifiers`
+    fn add_qualifier(&mut self, qualifiers: &mut Qualifiers) {
+        let (qualifier, keyword) = match self.cur_tok() {
+            Some(Token::Const) => (Qualifiers::CONST, "const"),
+            Some(Token::Volatile) => (Qualifiers::VOLATILE, "volatile"),
+            Some(Token::Restrict) => (Qualifiers::RESTRICT, "restrict"),
+            _ => (Qualifiers::ATOMIC, "_Atomic"),
+        };
+        let span = self.cur_span();
+        match qualifier {
+            Qualifiers::RESTRICT => self.require_standard(CStandard::C99, span, "`restrict`"),
+            Qualifiers::ATOMIC => self.require_standard(CStandard::C11, span, "`_Atomic`"),
+            _ => (),
+        }
+        if !qualifiers.insert(qualifier) {
+            parser_warn!(
+                self,
+                ErrorCode::DuplicateSpecifier,
+                span,
+                "Encountered second `{keyword}` qualifier"
+            );
+        }
+    }
+
+    /// Whether the token after the cur token is a type qualifier
+    fn peek_is_qualifier(&self) -> bool {
+        match self.peek_tok() {
+            Some(Token::Const | Token::Volatile | Token::Restrict) => true,
+            // `_Atomic(` is the atomic type specifier
+            Some(Token::Ident("_Atomic")) => {
+                self.tokens.get(self.tok_index + 2).is_none_or(|tok| tok.tok != Token::LParent)
+            }
+            _ => false,
+        }
+    }
+
+    /// Cur token is `_Atomic`. Ends on the right parenthesis after the type
+    fn parse_atomic_type(&mut self) -> Option<Type<'a>> {
+        let start = self.cur_span();
+        self.next_tok();
+        self.next_tok();
+        let data_type = self.parse_type()?;
+        if !self.expect_peek(Token::RParent, "after atomic type") {
+            return None;
+        }
+        if let Type::Array { .. } | Type::Function { .. } = data_type {
+            parser_error!(
+                self,
+                ErrorCode::InvalidTypeSpecifiers,
+                start.to(self.cur_span()),
+                "`_Atomic` cannot be applied to an array or function type"
+            );
+        }
+        Some(qualify(data_type, Qualifiers::ATOMIC))
+    }
+
+    /// Cur token is `typeof` or `typeof_unqual`. Ends on the right
+    /// parenthesis
+    fn parse_typeof(&mut self) -> Option<Type<'a>> {
+        let start = self.cur_span();
+        let keyword = *self.cur_tok()?;
+        if keyword == Token::Ident("__typeof__") {
+            self.require_gnu(start, "`__typeof__`");
+        }
+        if !self.expect_peek(Token::LParent, &format!("after `{keyword}`")) {
+            return None;
+        }
+        let operand = if self.starts_type_name(self.tok_index + 1) {
+            self.next_tok();
+            TypeOfOperand::Type(self.arena.alloc(self.parse_type()?))
+        } else {
+            TypeOfOperand::Expr(self.arena.alloc(self.parse_next_expr(Precedence::Lowest, &format!("in `{keyword}`"))))
+        };
+        if !self.expect_peek(Token::RParent, &format!("to close `{keyword}`")) {
+            return None;
+        }
+        Some(Type::TypeOf {
+            operand,
+            is_unqual: keyword == Token::Ident("typeof_unqual"),
+            qualifiers: Qualifiers::NONE,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Whether the token at `index` starts a `[[...]]` or `__attribute__`
+    /// specifier
+    pub(super) fn starts_attributes_at(&self, index: usize) -> bool {
+        match self.tokens.get(index).map(|tok| &tok.tok) {
+            Some(Token::LSquare) => self.tokens.get(index + 1).is_some_and(|tok| tok.tok == Token::LSquare),
+            Some(Token::Ident("__attribute__")) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the tokens after the cur token start an attribute specifier
+    pub(super) fn peek_starts_attributes(&self) -> bool {
+        self.starts_attributes_at(self.tok_index + 1)
+    }
+
+    /// Index of the token after the attribute specifiers at `index`
+    pub(super) fn skip_attributes(&self, mut index: usize) -> usize {
+        while self.starts_attributes_at(index) {
+            // Skip the keyword to the parentheses of GNU attributes
+            if self.tokens[index].tok == Token::Ident("__attribute__") {
+                index += 1;
+            }
+            let mut depth = 0;
+            loop {
+                match self.tokens.get(index).map(|tok| &tok.tok) {
+                    Some(Token::LSquare | Token::LParent) => depth += 1,
+                    Some(Token::RSquare | Token::RParent) => depth -= 1,
+                    Some(_) => (),
+                    None => return index,
+                }
+                index += 1;
+                if depth <= 0 {
+                    break;
+                }
+            }
+        }
+        index
+    }
+
+    /// Cur token starts an attribute specifier. Ends on the last token of the
+    /// specifiers that follow each other
+    pub(super) fn parse_attributes(&mut self) -> Option<Vec<Attribute<'a>>> {
+        let mut attributes = Vec::new();
+        loop {
+            match self.cur_tok() {
+                Some(Token::Ident("__attribute__")) => attributes.extend(self.parse_gnu_attribute_specifier()?),
+                _ => attributes.extend(self.parse_attribute_specifier()?),
+            }
+            if !self.peek_starts_attributes() {
+                return Some(attributes);
+            }
+            self.next_tok();
+        }
+    }
+
+    /// Parses the attribute specifiers after the cur token, if there are any.
+    /// Ends on the last token of the specifiers
+    pub(super) fn parse_trailing_attributes(&mut self) -> Option<Vec<Attribute<'a>>> {
+        if !self.peek_starts_attributes() {
+            return Some(Vec::new());
+        }
+        self.next_tok();
+        self.parse_attributes()
+    }
+
+    /// Cur token is the first `[` of `[[...]]`. Ends on the last `]`
+    fn parse_attribute_specifier(&mut self) -> Option<Vec<Attribute<'a>>> {
+        self.require_standard(CStandard::C23, self.cur_span(), "`[[...]]` attributes");
+        self.next_tok();
+        let attributes = self.parse_attribute_list(false, Token::RSquare)?;
+        self.next_tok();
+        if !self.expect_peek(Token::RSquare, "to close attribute specifier") {
+            return None;
+        }
+        Some(attributes)
+    }
+
+    /// Cur token is `__attribute__`. Ends on the last `)`
+    fn parse_gnu_attribute_specifier(&mut self) -> Option<Vec<Attribute<'a>>> {
+        self.require_gnu(self.cur_span(), "`__attribute__`");
+        if !self.expect_peek(Token::LParent, "after `__attribute__`")
+            || !self.expect_peek(Token::LParent, "after `__attribute__(`")
+        {
+            return None;
+        }
+        let attributes = self.parse_attribute_list(true, Token::RParent)?;
+        self.next_tok();
+        if !self.expect_peek(Token::RParent, "to close `__attribute__`") {
+            return None;
+        }
+        Some(attributes)
+    }
+
+    /// Cur token is the token before the list. Ends before `close`
+    fn parse_attribute_list(&mut self, is_gnu: bool, close: Token<'a>) -> Option<Vec<Attribute<'a>>> {
+        let mut attributes = Vec::new();
+        // Attributes may be empty, like `[[]]` or `[[a,,b]]`
+        while self.peek_tok() != Some(&close) {
+            if self.peek_tok() != Some(&Token::Comma) {
+                attributes.push(self.parse_attribute(is_gnu)?);
+            }
+            match self.peek_tok() {
+                Some(Token::Comma) => self.next_tok(),
+                Some(tok) if *tok == close => break,
+                _ => {
+                    self.unexpected_peek(&["`,`", &format!("`{close}`")], "after attribute");
+                    return None;
+                }
+            }
+        }
+        Some(attributes)
+    }
+
+    /// Name of an attribute, which may be spelled like a keyword, e.g.
+    /// `__attribute__((const))`
+    fn attribute_name(&self, tok: &Token<'a>) -> Option<&'a str> {
+        match tok {
+            Token::Ident(name) => Some(*name),
+            tok => {
+                let spelling = tok.to_string();
+                let is_word = spelling.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && spelling.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                is_word.then(|| &*self.arena.alloc_str(&spelling))
+            }
+        }
+    }
+
+    /// Cur token is the token before the attribute. Ends on its last token
+    fn parse_attribute(&mut self, is_gnu: bool) -> Option<Attribute<'a>> {
+        let Some(mut name) = self.peek_tok().and_then(|tok| self.attribute_name(tok)) else {
+            self.unexpected_peek(&["attribute name"], "in attribute specifier");
+            return None;
+        };
+        self.next_tok();
+        let start = self.cur_span();
+        let mut prefix = None;
+        // The lexer has no `::` token
+        if self.peek_tok() == Some(&Token::Colon)
+            && self.tokens.get(self.tok_index + 2).is_some_and(|tok| tok.tok == Token::Colon)
+        {
+            self.next_tok();
+            self.next_tok();
+            let Some(suffix) = self.peek_tok().and_then(|tok| self.attribute_name(tok)) else {
+                self.unexpected_peek(&["attribute name"], "after `::`");
+                return None;
+            };
+            prefix = Some(name);
+            name = suffix;
+            self.next_tok();
+        }
+        let args = if self.peek_tok() == Some(&Token::LParent) {
+            self.next_tok();
+            let mut args = Vec::new();
+            let mut depth = 0;
+            loop {
                 self.next_tok();
-                Some(self.parse_ptr(Type::Union(id))?)
+                match self.cur_tok() {
+                    Some(Token::RParent) if depth == 0 => break,
+                    Some(tok) => {
+                        match tok {
+                            Token::LParent | Token::LSquare | Token::LCurly => depth += 1,
+                            Token::RParent | Token::RSquare | Token::RCurly => depth -= 1,
+                            _ => (),
+                        }
+                        args.push(*tok);
+                    }
+                    None => {
+                        self.unexpected_eof(&["`)`"], "to close attribute arguments");
+                        return None;
+                    }
+                }
             }
-            Token::Ident(ident) => Some(self.parse_ptr(Type::Ident(ident))?),
-            tok => panic!("Cannot parse type from token: {tok:?}"),
+            Some(args)
+        } else {
+            None
+        };
+        Some(Attribute {
+            is_gnu,
+            prefix,
+            name,
+            args,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is `_Alignas`. Ends on the right parenthesis
+    fn parse_alignment(&mut self) -> Option<Alignment<'a>> {
+        let start = self.cur_span();
+        if !self.expect_peek(Token::LParent, "after `_Alignas`") {
+            return None;
         }
+        let alignment = if self.starts_type_name(self.tok_index + 1) {
+            self.next_tok();
+            let data_type = self.parse_type()?;
+            if !self.expect_peek(Token::RParent, "after alignment type") {
+                return None;
+            }
+            Alignment::Type {
+                data_type,
+                span: start.to(self.cur_span()),
+            }
+        } else {
+            let value = self.parse_next_expr(Precedence::Assign, "as alignment");
+            if !self.expect_peek(Token::RParent, "after alignment") {
+                return None;
+            }
+            Alignment::Expr {
+                value,
+                span: start.to(self.cur_span()),
+            }
+        };
+        Some(alignment)
     }
 
-    fn id_for_cdt_ptr(&mut self, cdt: CompositeDataType) -> Option<&'a str> {
-        Some(match self.peek_tok()? {
-            Token::Ident(id) => id,
+    fn extend_primitive(&self, primitive: &mut PrimitiveSpecifiers, start: Span) {
+        let span = start.to(self.cur_span());
+        primitive.span = Some(primitive.span.map_or(span, |prev| prev.to(span)));
+    }
+
+    /// Cur token is a primitive type specifier identifier. Ends on its last
+    /// token
+    fn parse_primitive_keyword(&mut self, name: &str, primitive: &mut PrimitiveSpecifiers) -> Option<()> {
+        match name {
+            "void" => primitive.void += 1,
+            "char" => primitive.char += 1,
+            "short" => primitive.short += 1,
+            "int" => primitive.int += 1,
+            "long" => primitive.long += 1,
+            "float" => primitive.float += 1,
+            "double" => primitive.double += 1,
+            "_Bool" | "bool" => {
+                self.require_standard(CStandard::C99, self.cur_span(), "`_Bool`");
+                primitive.bool += 1;
+            }
+            "_Complex" => {
+                self.require_standard(CStandard::C99, self.cur_span(), "`_Complex`");
+                primitive.complex += 1;
+            }
+            "__int128" => {
+                self.require_gnu(self.cur_span(), "`__int128`");
+                primitive.int128 += 1;
+            }
+            _ => {
+                self.require_standard(CStandard::C23, self.cur_span(), "`_BitInt`");
+                primitive.bit_int += 1;
+                primitive.width = self.parse_bit_int_width()?;
+            }
+        }
+        Some(())
+    }
+
+    /// Cur token is `_BitInt`. Ends on the right parenthesis after the width
+    fn parse_bit_int_width(&mut self) -> Option<u16> {
+        if !self.expect_peek(Token::LParent, "after `_BitInt`") {
+            return None;
+        }
+        let Some(Token::LitInt(spelling)) = self.peek_tok() else {
+            self.unexpected_peek(&["integer constant"], "as `_BitInt` width");
+            return None;
+        };
+        let spelling = *spelling;
+        self.next_tok();
+        let literal = self.parse_int_value(spelling, self.cur_span())?;
+        let width = match u16::try_from(literal.value) {
+            Ok(width) if width > 0 => width,
             _ => {
                 parser_error!(
-                    "Expected identifier for {cdt} pointer, received {:?} instead",
-                    self.peek_tok().unwrap()
+                    self,
+                    ErrorCode::InvalidLiteral,
+                    self.cur_span(),
+                    "`_BitInt` width `{spelling}` is out of range"
                 );
-                ""
+                return None;
+            }
+        };
+        if !self.expect_peek(Token::RParent, "after `_BitInt` width") {
+            return None;
+        }
+        Some(width)
+    }
+
+    /// Reports specifiers without a type
+    pub(super) fn specified_type(&mut self, specifiers: Specifiers<'a>) -> Option<Type<'a>> {
+        if specifiers.data_type.is_none() {
+            parser_error!(
+                self,
+                ErrorCode::ExpectedType,
+                specifiers.span,
+                "expected type in declaration specifiers"
+            );
+        }
+        specifiers.data_type
+    }
+
+    /// Cur token is the `struct`, `union` or `enum` keyword. Ends on the tag
+    /// or the closing bracket of the definition
+    fn parse_tagged_type(&mut self, cdt: CompositeDataType) -> Option<Type<'a>> {
+        let start = self.cur_span();
+        let mut attributes = self.parse_trailing_attributes()?;
+        let name = match self.peek_tok() {
+            Some(Token::Ident(id)) => {
+                let id = *id;
+                self.next_tok();
+                Some(id)
+            }
+            Some(Token::LCurly) => None,
+            _ => {
+                self.unexpected_peek(&["identifier", "`{`"], &format!("after `{cdt}`"));
+                return None;
             }
+        };
+        if cdt == CompositeDataType::Enum {
+            return self.parse_enum_type(start, name, attributes);
+        }
+        let fields = match self.peek_tok() {
+            Some(Token::LCurly) => {
+                self.next_tok();
+                let fields = self.parse_field_list()?;
+                attributes.extend(self.parse_trailing_attributes()?);
+                Some(fields)
+            }
+            _ => None,
+        };
+        let span = start.to(self.cur_span());
+        if fields.is_none() {
+            self.ignore_tag_attributes(&attributes, span);
+        }
+        let arena = self.arena;
+        Some(match cdt {
+            CompositeDataType::Struct => Type::Struct {
+                name,
+                definition: fields.map(|fields| &*arena.alloc(StructStmt {
+                    name,
+                    attributes,
+                    fields: Some(fields),
+                    span,
+                })),
+                qualifiers: Qualifiers::NONE,
+                span,
+            },
+            _ => Type::Union {
+                name,
+                definition: fields.map(|fields| &*arena.alloc(UnionStmt {
+                    name,
+                    attributes,
+                    fields: Some(fields),
+                    span,
+                })),
+                qualifiers: Qualifiers::NONE,
+                span,
+            },
         })
     }
 
-    fn parse_ptr(&mut self, mut type_: Type<'a>) -> Option<Type<'a>> {
-        let mut ptr_const = false;
-        let mut ptr_restrict = false;
-        Some(loop {
-            match self.peek_tok()? {
-                Token::Const => match &mut type_ {
-                    Type::Pointer { is_const, .. } => *is_const = true,
-                    _ => ptr_const = true,
-                },
-                Token::Restrict => match &mut type_ {
-                    Type::Pointer { is_restricted, .. } => *is_restricted = true,
-                    _ => ptr_restrict = true,
-                },
-                Token::Asterisk => {
-                    type_ = Type::Pointer {
-                        data_type: self.arena.alloc(type_),
-                        is_const: ptr_const,
-                        is_restricted: ptr_restrict,
+    /// Cur token is the `enum` keyword or the tag. Ends on the last token of
+    /// the type
+    fn parse_enum_type(
+        &mut self,
+        start: Span,
+        name: Option<Ident<'a>>,
+        mut attributes: Vec<Attribute<'a>>,
+    ) -> Option<Type<'a>> {
+        let underlying_type = if self.peek_tok() == Some(&Token::Colon) && self.starts_enum_base() {
+            self.require_standard(CStandard::C23, self.peek_span(), "fixed underlying types of enums");
+            self.next_tok();
+            self.next_tok();
+            let specifiers = self.parse_specifiers()?;
+            Some(self.specified_type(specifiers)?)
+        } else {
+            None
+        };
+        let variants = match self.peek_tok() {
+            Some(Token::LCurly) => {
+                self.next_tok();
+                let variants = self.parse_enumerators()?;
+                attributes.extend(self.parse_trailing_attributes()?);
+                Some(variants)
+            }
+            _ => None,
+        };
+        let span = start.to(self.cur_span());
+        let is_definition = underlying_type.is_some() || variants.is_some();
+        if !is_definition {
+            self.ignore_tag_attributes(&attributes, span);
+        }
+        let definition = is_definition.then(|| {
+            &*self.arena.alloc(EnumStmt {
+                name,
+                attributes,
+                underlying_type,
+                variants,
+                span,
+            })
+        });
+        Some(Type::Enum {
+            name,
+            definition,
+            qualifiers: Qualifiers::NONE,
+            span,
+        })
+    }
+
+    /// Warns about attributes of a struct, union or enum type that is not
+    /// defined in place, which have no AST node to be attached to
+    fn ignore_tag_attributes(&mut self, attributes: &[Attribute<'a>], span: Span) {
+        if !attributes.is_empty() {
+            parser_warn!(
+                self,
+                ErrorCode::Unsupported,
+                span,
+                "attributes of a type without its definition are ignored"
+            );
+        }
+    }
+
+    /// Whether the `:` after the cur token starts a fixed underlying type
+    /// rather than a bit-field width of an enum type
+    fn starts_enum_base(&self) -> bool {
+        matches!(self.tokens.get(self.tok_index + 2).map(|tok| &tok.tok), Some(Token::Ident(_)))
+            || self.starts_type_name(self.tok_index + 2)
+    }
+
+    /// Cur token is the left bracket of a struct or union definition. Ends on
+    /// the right bracket
+    fn parse_field_list(&mut self) -> Option<Vec<Field<'a>>> {
+        if !self.enter_nested() {
+            return None;
+        }
+        let fields = self.parse_field_list_inner();
+        self.leave_nested();
+        fields
+    }
+
+    fn parse_field_list_inner(&mut self) -> Option<Vec<Field<'a>>> {
+        let mut fields = Vec::new();
+        while self.peek_tok() != Some(&Token::RCurly) {
+            if self.peek_tok().is_none() {
+                self.unexpected_eof(&["`}`"], "at the end of member list");
+                return None;
+            }
+            self.next_tok();
+            // Static assertions may appear among the members, but do not declare one
+            if matches!(
+                self.cur_tok(),
+                Some(Token::Ident(name @ ("_Static_assert" | "static_assert"))) if self.is_keyword(name)
+            ) {
+                self.parse_static_assert()?;
+                continue;
+            }
+            let start = self.cur_span();
+            let specifiers = self.parse_specifiers()?;
+            let base = self.specified_type(specifiers)?;
+            // Anonymous struct or union
+            if self.peek_tok() == Some(&Token::Semicolon)
+                && matches!(base, Type::Struct { name: None, .. } | Type::Union { name: None, .. })
+            {
+                self.next_tok();
+                fields.push(Field {
+                    name: None,
+                    field_type: base,
+                    bit_width: None,
+                    attributes: Vec::new(),
+                    span: start.to(self.cur_span()),
+                });
+                continue;
+            }
+            loop {
+                let field_start = self.peek_span();
+                // Unnamed bit-fields only have a width
+                let (name, field_type, mut attributes) = match self.peek_tok() {
+                    Some(Token::Colon) => (None, base.clone(), Vec::new()),
+                    _ => {
+                        let declarator = self.parse_declarator(base.clone(), DeclaratorKind::Named)?;
+                        if declarator.name.is_none() {
+                            self.unexpected_peek(&["identifier"], "as member name");
+                            return None;
+                        }
+                        (declarator.name, declarator.data_type, declarator.attributes)
+                    }
+                };
+                let bit_width = match self.peek_tok() {
+                    Some(Token::Colon) => {
+                        self.next_tok();
+                        let width = self.parse_next_expr(Precedence::Assign, "as bit-field width");
+                        attributes.extend(self.parse_trailing_attributes()?);
+                        Some(width)
+                    }
+                    _ => None,
+                };
+                fields.push(Field {
+                    name,
+                    field_type,
+                    bit_width,
+                    attributes,
+                    span: field_start.to(self.cur_span()),
+                });
+                match self.peek_tok() {
+                    Some(Token::Comma) => self.next_tok(),
+                    Some(Token::Semicolon) => {
+                        self.next_tok();
+                        break;
+                    }
+                    _ => {
+                        self.unexpected_peek(&["`,`", "`;`"], "after member declaration");
+                        return None;
+                    }
+                }
+            }
+        }
+        self.next_tok();
+        Some(fields)
+    }
+
+    /// Cur token is the left bracket of an enum definition. Ends on the right
+    /// bracket
+    fn parse_enumerators(&mut self) -> Option<Vec<Enumerator<'a>>> {
+        let mut variants = Vec::new();
+        loop {
+            let Some(Token::Ident(name)) = self.peek_tok() else {
+                self.unexpected_peek(&["identifier"], "as enumerator");
+                return None;
+            };
+            let name = *name;
+            self.next_tok();
+            // Enumeration constants hide typedef names
+            self.declare(name, false);
+            let start = self.cur_span();
+            let value = match self.peek_tok() {
+                Some(Token::Assign) => {
+                    self.next_tok();
+                    Some(self.parse_next_expr(Precedence::Assign, "as enumerator value"))
+                }
+                _ => None,
+            };
+            variants.push(Enumerator {
+                name,
+                value,
+                span: start.to(self.cur_span()),
+            });
+            match self.peek_tok() {
+                Some(Token::Comma) => {
+                    self.next_tok();
+                    // Trailing comma
+                    if self.peek_tok() == Some(&Token::RCurly) {
+                        self.next_tok();
+                        return Some(variants);
+                    }
+                }
+                Some(Token::RCurly) => {
+                    self.next_tok();
+                    return Some(variants);
+                }
+                _ => {
+                    self.unexpected_peek(&["`,`", "`}`"], "after enumerator");
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Parses the declarator declaring an object of type `base`. Cur token is
+    /// the token before the declarator. Ends on the last token of the
+    /// declarator
+    pub(super) fn parse_declarator(&mut self, base: Type<'a>, kind: DeclaratorKind) -> Option<Declarator<'a>> {
+        if !self.enter_nested() {
+            return None;
+        }
+        let declarator = self.parse_declarator_inner(base, kind);
+        self.leave_nested();
+        declarator
+    }
+
+    fn parse_declarator_inner(&mut self, mut data_type: Type<'a>, kind: DeclaratorKind) -> Option<Declarator<'a>> {
+        while self.peek_tok() == Some(&Token::Asterisk) {
+            self.next_tok();
+            let qualifiers = self.parse_ptr_qualifiers();
+            data_type = Type::Pointer {
+                span: data_type.span().to(self.cur_span()),
+                data_type: self.arena.alloc(data_type),
+                qualifiers,
+            };
+        }
+
+        let mut name = None;
+        // Index of the `(` of a nested declarator, which applies to the type
+        // after the suffixes following it
+        let mut nested = None;
+        match self.peek_tok() {
+            Some(Token::Ident(ident)) if kind != DeclaratorKind::Abstract => {
+                name = Some(*ident);
+                self.next_tok();
+            }
+            Some(Token::LParent) if self.is_nested_declarator(kind) => {
+                self.next_tok();
+                nested = Some(self.tok_index);
+                self.skip_parenthesized()?;
+            }
+            _ => (),
+        }
+
+        // Attributes after the name and after each suffix, which may only
+        // come last with GNU `__attribute__`
+        let mut attributes = Vec::new();
+        let mut suffixes = Vec::new();
+        loop {
+            match self.peek_tok() {
+                _ if self.peek_starts_attributes() => attributes.extend(self.parse_trailing_attributes()?),
+                Some(Token::LSquare) => {
+                    self.next_tok();
+                    suffixes.push(self.parse_array_suffix()?);
+                }
+                Some(Token::LParent) => {
+                    self.next_tok();
+                    suffixes.push(self.parse_function_suffix()?);
+                }
+                _ => break,
+            }
+        }
+        // `a[2][3]` is an array of 2 arrays of 3
+        for suffix in suffixes.into_iter().rev() {
+            data_type = self.apply_suffix(data_type, suffix);
+        }
+
+        let Some(open) = nested else {
+            return Some(Declarator {
+                name,
+                data_type,
+                attributes,
+            });
+        };
+        let end = self.tok_index;
+        self.tok_index = open;
+        let mut declarator = self.parse_declarator(data_type, kind);
+        let closed = declarator.is_some() && self.expect_peek(Token::RParent, "after nested declarator");
+        self.tok_index = end;
+        if let Some(declarator) = &mut declarator {
+            declarator.attributes.extend(attributes);
+        }
+        declarator.filter(|_| closed)
+    }
+
+    /// Cur token is the asterisk of a pointer. Ends on the last qualifier
+    fn parse_ptr_qualifiers(&mut self) -> Qualifiers {
+        let mut qualifiers = Qualifiers::NONE;
+        while self.peek_is_qualifier() {
+            self.next_tok();
+            self.add_qualifier(&mut qualifiers);
+        }
+        qualifiers
+    }
+
+    /// Whether the `(` after the cur token starts a nested declarator rather
+    /// than a parameter list
+    fn is_nested_declarator(&self, kind: DeclaratorKind) -> bool {
+        match self.tokens.get(self.tok_index + 2).map(|tok| &tok.tok) {
+            Some(Token::Asterisk | Token::LParent | Token::LSquare) => true,
+            Some(Token::Ident(name)) => kind != DeclaratorKind::Abstract && !self.is_type_name(name),
+            _ => false,
+        }
+    }
+
+    /// Cur token is a left parenthesis. Ends on the matching right
+    /// parenthesis
+    fn skip_parenthesized(&mut self) -> Option<()> {
+        let mut depth = 0;
+        loop {
+            match self.cur_tok() {
+                Some(Token::LParent) => depth += 1,
+                Some(Token::RParent) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(());
                     }
                 }
-                _ => break type_,
+                Some(Token::Semicolon | Token::LCurly | Token::RCurly) => {
+                    self.unexpected_cur(&["`)`"], "to close nested declarator");
+                    return None;
+                }
+                Some(_) => (),
+                None => {
+                    self.unexpected_eof(&["`)`"], "to close nested declarator");
+                    return None;
+                }
+            }
+            self.next_tok();
+        }
+    }
+
+    /// Cur token is the left square bracket. Ends on the right square bracket
+    fn parse_array_suffix(&mut self) -> Option<Suffix<'a>> {
+        let start = self.cur_span();
+        let mut is_static = false;
+        let mut qualifiers = Qualifiers::NONE;
+        while self.peek_tok() == Some(&Token::Static) || self.peek_is_qualifier() {
+            self.next_tok();
+            match self.cur_tok() {
+                Some(Token::Static) => encounter_modifier!(self, is_static, "Encountered second `static` in array size"),
+                _ => self.add_qualifier(&mut qualifiers),
             }
+        }
+        let size = match self.peek_tok() {
+            Some(Token::RSquare) => ArraySize::Unspecified,
+            Some(Token::Asterisk) if self.tokens.get(self.tok_index + 2).is_some_and(|tok| tok.tok == Token::RSquare) => {
+                self.next_tok();
+                ArraySize::Star
+            }
+            _ => {
+                let size = self.parse_next_expr(Precedence::Comma, "as array size");
+                if let Expression::LiteralInt { value: 0, span } = size {
+                    self.require_gnu(span, "zero-length arrays");
+                }
+                ArraySize::Expr(self.arena.alloc(size))
+            }
+        };
+        if !self.expect_peek(Token::RSquare, "after array size") {
+            return None;
+        }
+        Some(Suffix::Array {
+            size,
+            is_static,
+            qualifiers,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// Cur token is the left parenthesis. Ends on the right parenthesis
+    fn parse_function_suffix(&mut self) -> Option<Suffix<'a>> {
+        let start = self.cur_span();
+        let mut params = Vec::new();
+        let mut is_variadic = false;
+        let mut style = ParamStyle::Prototype;
+        if self.peek_tok() == Some(&Token::RParent) {
             self.next_tok();
+            if self.options.standard < CStandard::C23 {
+                style = ParamStyle::Unspecified;
+            }
+        } else {
+            loop {
+                self.next_tok();
+                if self.cur_tok() == Some(&Token::Ellipsis) {
+                    is_variadic = true;
+                    if params.is_empty() {
+                        self.require_standard(CStandard::C23, self.cur_span(), "`...` without a named parameter");
+                    }
+                    if !self.expect_peek(Token::RParent, "after `...`") {
+                        return None;
+                    }
+                    break;
+                }
+                params.push(self.parse_param()?);
+                match self.peek_tok() {
+                    Some(Token::Comma) => self.next_tok(),
+                    Some(Token::RParent) => {
+                        self.next_tok();
+                        break;
+                    }
+                    _ => {
+                        self.unexpected_peek(&["`,`", "`)`"], "after parameter");
+                        return None;
+                    }
+                }
+            }
+            self.check_void_params(&mut params, is_variadic);
+        }
+        Some(Suffix::Function {
+            params,
+            is_variadic,
+            style,
+            span: start.to(self.cur_span()),
         })
     }
+
+    /// Removes the `void` of `(void)`, which declares that there are no
+    /// parameters, and reports `void` anywhere else
+    fn check_void_params(&mut self, params: &mut Vec<Param<'a>>, is_variadic: bool) {
+        let is_void = |param: &Param| {
+            matches!(
+                param.data_type,
+                Type::Primitive {
+                    kind: PrimitiveKind::Void,
+                    ..
+                }
+            )
+        };
+        match params.as_slice() {
+            [param]
+                if is_void(param) && param.name.is_none() && param.data_type.qualifiers().is_empty() && !is_variadic =>
+            {
+                params.clear();
+            }
+            _ => {
+                for param in params.iter().filter(|param| is_void(param)) {
+                    parser_error!(
+                        self,
+                        ErrorCode::InvalidParameters,
+                        param.span,
+                        "`void` must be the only parameter and unnamed"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Cur token is the first token of the parameter. Ends on its last token
+    fn parse_param(&mut self) -> Option<Param<'a>> {
+        let start = self.cur_span();
+        let specifiers = self.parse_specifiers()?;
+        let base = self.specified_type(specifiers)?;
+        let declarator = self.parse_declarator(base, DeclaratorKind::Optional)?;
+        Some(Param {
+            name: declarator.name,
+            adjusted_type: self.adjust_param_type(&declarator.data_type),
+            data_type: declarator.data_type,
+            attributes: declarator.attributes,
+            span: start.to(self.cur_span()),
+        })
+    }
+
+    /// The type a parameter of type `data_type` has inside the function
+    pub(super) fn adjust_param_type(&self, data_type: &Type<'a>) -> Type<'a> {
+        match data_type {
+            Type::Array {
+                data_type,
+                qualifiers,
+                span,
+                ..
+            } => Type::Pointer {
+                data_type,
+                qualifiers: *qualifiers,
+                span: *span,
+            },
+            Type::Function { span, .. } => Type::Pointer {
+                data_type: self.arena.alloc(data_type.clone()),
+                qualifiers: Qualifiers::NONE,
+                span: *span,
+            },
+            data_type => data_type.clone(),
+        }
+    }
+
+    fn apply_suffix(&mut self, data_type: Type<'a>, suffix: Suffix<'a>) -> Type<'a> {
+        let start = data_type.span();
+        match suffix {
+            Suffix::Array {
+                size,
+                is_static,
+                qualifiers,
+                span,
+            } => Type::Array {
+                data_type: self.arena.alloc(data_type),
+                size,
+                is_static,
+                qualifiers,
+                span: start.to(span),
+            },
+            Suffix::Function {
+                params,
+                is_variadic,
+                style,
+                span,
+            } => Type::Function {
+                return_type: self.arena.alloc(data_type),
+                params,
+                is_variadic,
+                style,
+                span: start.to(span),
+            },
+        }
+    }
+}
+
+/// Adds qualifiers given in the declaration specifiers to the specified type
+fn qualify(mut data_type: Type<'_>, added: Qualifiers) -> Type<'_> {
+    match &mut data_type {
+        Type::Primitive { qualifiers, .. }
+        | Type::Ident { qualifiers, .. }
+        | Type::Pointer { qualifiers, .. }
+        | Type::Struct { qualifiers, .. }
+        | Type::Union { qualifiers, .. }
+        | Type::Enum { qualifiers, .. }
+        | Type::TypeOf { qualifiers, .. }
+        | Type::Auto { qualifiers, .. } => {
+            qualifiers.insert(added);
+        }
+        // Only reachable through `_Atomic(type-name)`, which is reported
+        Type::Array { .. } | Type::Function { .. } => (),
+    }
+    data_type
+}
+
+/// Whether the keyword can continue declaration specifiers
+fn is_specifier_keyword(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::Const
+            | Token::Volatile
+            | Token::Restrict
+            | Token::Inline
+            | Token::Auto
+            | Token::Static
+            | Token::Register
+            | Token::Extern
+            | Token::Typedef
+            | Token::Signed
+            | Token::Unsigned
+            | Token::Struct
+            | Token::Union
+            | Token::Enum
+    )
 }
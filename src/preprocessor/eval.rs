@@ -0,0 +1,296 @@
+use crate::{
+    diagnostic::ErrorCode,
+    lexer::tokens::{SpannedToken, Token},
+    parser::literal::{char_literal, int_literal},
+    parser_error,
+    span::{FileId, Span},
+};
+
+use super::{expand::PpToken, pp_ident, Preprocessor};
+
+/// Value of a preprocessor expression, which has type `intmax_t` or
+/// `uintmax_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PpValue {
+    /// Two's complement bits of the value
+    value: u64,
+    is_unsigned: bool,
+}
+
+impl PpValue {
+    const ZERO: PpValue = PpValue::int(0);
+
+    const fn int(value: i64) -> Self {
+        Self {
+            value: value as u64,
+            is_unsigned: false,
+        }
+    }
+
+    fn is_true(self) -> bool {
+        self.value != 0
+    }
+}
+
+impl<'s> Preprocessor<'s> {
+    /// Evaluates the controlling expression of `#if` or `#elif`. Invalid
+    /// expressions are reported and evaluate to false
+    pub(super) fn evaluate_condition(&mut self, args: &[SpannedToken<'s>], file: FileId, span: Span) -> bool {
+        // `defined` is evaluated before macro expansion
+        let mut tokens = Vec::with_capacity(args.len());
+        let mut index = 0;
+        while index < args.len() {
+            let tok = args[index];
+            if pp_ident(&tok.tok) != Some("defined") {
+                tokens.push(PpToken::new(tok));
+                index += 1;
+                continue;
+            }
+            let parenthesized = args.get(index + 1).is_some_and(|open| open.tok == Token::LParent);
+            let name_index = index + 1 + parenthesized as usize;
+            let name = args.get(name_index).and_then(|name| pp_ident(&name.tok));
+            let closed = !parenthesized || args.get(name_index + 1).is_some_and(|close| close.tok == Token::RParent);
+            let Some(name) = name.filter(|_| closed) else {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidConditionExpression,
+                    tok.span,
+                    "`defined` requires a macro name, optionally in parentheses"
+                );
+                return false;
+            };
+            let value = if self.is_defined(name) { "1" } else { "0" };
+            tokens.push(PpToken::new(SpannedToken {
+                tok: Token::LitInt(value),
+                ..tok
+            }));
+            index = name_index + 1 + parenthesized as usize;
+        }
+
+        let tokens = self.expand_all(tokens, file);
+        let mut evaluator = Evaluator {
+            tokens: &tokens,
+            index: 0,
+            end: Span::in_file(span.end, span.end, span.file),
+            error: None,
+        };
+        let value = evaluator.expr(0, true);
+        if evaluator.error.is_none() {
+            if let Some(tok) = tokens.get(evaluator.index) {
+                evaluator.error = Some((tok.tok.span, format!("unexpected `{}` in preprocessor expression", tok.tok.tok)));
+            }
+        }
+        match evaluator.error {
+            Some((span, message)) => {
+                parser_error!(self, ErrorCode::InvalidConditionExpression, span, "{message}");
+                false
+            }
+            None => value.is_true(),
+        }
+    }
+}
+
+/// Evaluates a fully macro expanded `#if` expression by precedence climbing
+struct Evaluator<'t, 's> {
+    tokens: &'t [PpToken<'s>],
+    index: usize,
+    /// Span reported for a missing token at the end
+    end: Span,
+    /// The first error
+    error: Option<(Span, String)>,
+}
+
+impl<'s> Evaluator<'_, 's> {
+    fn peek(&self) -> Option<SpannedToken<'s>> {
+        self.tokens.get(self.index).map(|tok| tok.tok)
+    }
+
+    fn fail(&mut self, span: Span, message: impl Into<String>) -> PpValue {
+        if self.error.is_none() {
+            self.error = Some((span, message.into()));
+        }
+        PpValue::ZERO
+    }
+
+    fn expect(&mut self, tok: Token, context: &str) {
+        match self.peek() {
+            Some(found) if found.tok == tok => self.index += 1,
+            found => {
+                let span = found.map_or(self.end, |found| found.span);
+                self.fail(span, format!("expected `{tok}` {context}"));
+            }
+        }
+    }
+
+    /// Parses an expression of operators binding at least as tight as
+    /// `min_prec`. `eval` is false in unevaluated operands of `&&`, `||`
+    /// and `?:`, where division by zero is not an error
+    fn expr(&mut self, min_prec: u8, eval: bool) -> PpValue {
+        let mut left = self.unary(eval);
+        while let Some(op) = self.peek() {
+            let Some(prec) = precedence(&op.tok) else {
+                break;
+            };
+            if prec < min_prec {
+                break;
+            }
+            self.index += 1;
+            left = match op.tok {
+                Token::QuestionMark => {
+                    let then = self.expr(0, eval && left.is_true());
+                    self.expect(Token::Colon, "in conditional expression");
+                    // Right associative
+                    let otherwise = self.expr(prec, eval && !left.is_true());
+                    let is_unsigned = then.is_unsigned || otherwise.is_unsigned;
+                    let value = if left.is_true() { then } else { otherwise };
+                    PpValue { is_unsigned, ..value }
+                }
+                Token::And => {
+                    let right = self.expr(prec + 1, eval && left.is_true());
+                    PpValue::int((left.is_true() && right.is_true()) as i64)
+                }
+                Token::Or => {
+                    let right = self.expr(prec + 1, eval && !left.is_true());
+                    PpValue::int((left.is_true() || right.is_true()) as i64)
+                }
+                tok => {
+                    let right = self.expr(prec + 1, eval);
+                    self.binary(&tok, left, right, op.span, eval)
+                }
+            };
+        }
+        left
+    }
+
+    fn binary(&mut self, op: &Token, left: PpValue, right: PpValue, span: Span, eval: bool) -> PpValue {
+        let is_unsigned = left.is_unsigned || right.is_unsigned;
+        let (l, r) = (left.value, right.value);
+        let (sl, sr) = (l as i64, r as i64);
+        let value = match op {
+            Token::Asterisk => l.wrapping_mul(r),
+            Token::Divide | Token::Mod if r == 0 => {
+                return match eval {
+                    true => self.fail(span, "division by zero in preprocessor expression"),
+                    false => PpValue::ZERO,
+                };
+            }
+            Token::Divide if is_unsigned => l / r,
+            Token::Divide => sl.wrapping_div(sr) as u64,
+            Token::Mod if is_unsigned => l % r,
+            Token::Mod => sl.wrapping_rem(sr) as u64,
+            Token::Plus => l.wrapping_add(r),
+            Token::Minus => l.wrapping_sub(r),
+            // Shifts have the type of the left operand
+            Token::LeftShift => {
+                return PpValue {
+                    value: l.wrapping_shl(r as u32),
+                    ..left
+                }
+            }
+            Token::RightShift if left.is_unsigned => {
+                return PpValue {
+                    value: l.wrapping_shr(r as u32),
+                    ..left
+                }
+            }
+            Token::RightShift => return PpValue::int(sl.wrapping_shr(r as u32)),
+            Token::LessThan | Token::GreaterThan | Token::LTEquals | Token::GTEquals => {
+                let ordering = match is_unsigned {
+                    true => l.cmp(&r),
+                    false => sl.cmp(&sr),
+                };
+                let result = match op {
+                    Token::LessThan => ordering.is_lt(),
+                    Token::GreaterThan => ordering.is_gt(),
+                    Token::LTEquals => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                };
+                return PpValue::int(result as i64);
+            }
+            Token::Equals => return PpValue::int((l == r) as i64),
+            Token::NEquals => return PpValue::int((l != r) as i64),
+            Token::Ampersand => l & r,
+            Token::XOr => l ^ r,
+            Token::BOr => l | r,
+            // Comma
+            _ => return right,
+        };
+        PpValue { value, is_unsigned }
+    }
+
+    fn unary(&mut self, eval: bool) -> PpValue {
+        let Some(tok) = self.peek() else {
+            return self.fail(self.end, "expected value in preprocessor expression");
+        };
+        self.index += 1;
+        match tok.tok {
+            Token::LitInt(spelling) => match int_literal(spelling) {
+                Ok(int) => match u64::try_from(int.value) {
+                    Ok(value) => PpValue {
+                        value,
+                        is_unsigned: int.is_unsigned || value > i64::MAX as u64,
+                    },
+                    Err(_) => self.fail(tok.span, format!("integer constant `{spelling}` is too large")),
+                },
+                Err(message) => self.fail(tok.span, message),
+            },
+            Token::LitChar(spelling) => match char_literal(spelling, &mut Vec::new()) {
+                // Plain characters are signed chars, multi-character
+                // constants are ints
+                Ok((_, value)) if spelling.starts_with('\'') && value <= 0xFF => PpValue::int(value as u8 as i8 as i64),
+                Ok((_, value)) if spelling.starts_with('\'') => PpValue::int(value as i32 as i64),
+                Ok((_, value)) => PpValue::int(value as i64),
+                Err(message) => self.fail(tok.span, message),
+            },
+            Token::LitFloat(_) => self.fail(tok.span, "floating constant in preprocessor expression"),
+            Token::LitString(_) => self.fail(tok.span, "string literal in preprocessor expression"),
+            Token::LParent => {
+                let value = self.expr(0, eval);
+                self.expect(Token::RParent, "to close `(`");
+                value
+            }
+            Token::Plus => self.unary(eval),
+            Token::Minus => {
+                let value = self.unary(eval);
+                PpValue {
+                    value: value.value.wrapping_neg(),
+                    ..value
+                }
+            }
+            Token::BNot => {
+                let value = self.unary(eval);
+                PpValue {
+                    value: !value.value,
+                    ..value
+                }
+            }
+            Token::ExclamMark => PpValue::int(!self.unary(eval).is_true() as i64),
+            // Identifiers that are not macros evaluate to 0
+            _ if pp_ident(&tok.tok) == Some("true") => PpValue::int(1),
+            _ if pp_ident(&tok.tok).is_some() => PpValue::ZERO,
+            tok => {
+                let span = self.tokens[self.index - 1].tok.span;
+                self.fail(span, format!("expected value in preprocessor expression, found `{tok}`"))
+            }
+        }
+    }
+}
+
+/// Binding power of a binary operator in preprocessor expressions
+fn precedence(tok: &Token) -> Option<u8> {
+    Some(match tok {
+        Token::Comma => 1,
+        Token::QuestionMark => 2,
+        Token::Or => 3,
+        Token::And => 4,
+        Token::BOr => 5,
+        Token::XOr => 6,
+        Token::Ampersand => 7,
+        Token::Equals | Token::NEquals => 8,
+        Token::LessThan | Token::GreaterThan | Token::LTEquals | Token::GTEquals => 9,
+        Token::LeftShift | Token::RightShift => 10,
+        Token::Plus | Token::Minus => 11,
+        Token::Asterisk | Token::Divide | Token::Mod => 12,
+        _ => return None,
+    })
+}
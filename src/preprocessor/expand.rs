@@ -0,0 +1,458 @@
+use logos::Logos;
+
+use crate::{
+    diagnostic::ErrorCode,
+    lexer::{
+        tokens::{SpannedToken, Token},
+        unsplice,
+    },
+    parser_error,
+    span::{Expansion, ExpansionId, FileId},
+};
+
+use super::{pp_ident, Macro, Preprocessor};
+
+/// A token during macro expansion
+#[derive(Debug, Clone)]
+pub(super) struct PpToken<'s> {
+    pub tok: SpannedToken<'s>,
+    /// Macros whose expansion produced this token, which must not be
+    /// expanded from it again (its hide set)
+    pub hide: Vec<&'s str>,
+}
+
+impl<'s> PpToken<'s> {
+    pub fn new(tok: SpannedToken<'s>) -> Self {
+        Self { tok, hide: Vec::new() }
+    }
+}
+
+/// Element of a replacement list after argument substitution
+#[derive(Debug, Clone)]
+enum Item<'s> {
+    Tok(PpToken<'s>),
+    /// Stands in for an empty argument that is an operand of `##`
+    Placemarker,
+}
+
+impl<'s> Preprocessor<'s> {
+    /// Expands all macros in `tokens`, rescanning each expansion together
+    /// with the tokens following it
+    pub(super) fn expand_all(&mut self, tokens: Vec<PpToken<'s>>, file: FileId) -> Vec<PpToken<'s>> {
+        // Stack of the remaining input, the next token is at the end
+        let mut input: Vec<_> = tokens.into_iter().rev().collect();
+        let mut out = Vec::new();
+        while let Some(tok) = input.pop() {
+            if let Some(tok) = self.expand_one(tok, &mut input, file) {
+                out.push(tok);
+            }
+        }
+        out
+    }
+
+    /// Pushes the expansion of `tok` onto `input`. Returns the token if it
+    /// does not name a macro that can be expanded
+    fn expand_one(&mut self, tok: PpToken<'s>, input: &mut Vec<PpToken<'s>>, file: FileId) -> Option<PpToken<'s>> {
+        let name = match pp_ident(&tok.tok.tok) {
+            Some(name) if !tok.hide.contains(&name) => name,
+            _ => return Some(tok),
+        };
+        let Some(mac) = self.macros.get(name).cloned() else {
+            return self.expand_builtin(name, tok, input, file);
+        };
+
+        let (args, call_site, mut hide) = match mac.params {
+            None => (Vec::new(), tok.tok.span, tok.hide.clone()),
+            Some(_) => {
+                // A function-like macro name without arguments is no invocation
+                if !input.last().is_some_and(|next| next.tok.tok == Token::LParent) {
+                    return Some(tok);
+                }
+                input.pop();
+                let (args, close) = self.collect_args(&mac, &tok, input)?;
+                let hide = tok
+                    .hide
+                    .iter()
+                    .filter(|name| close.hide.contains(name))
+                    .copied()
+                    .collect();
+                (args, tok.tok.span.to(close.tok.span), hide)
+            }
+        };
+        hide.push(name);
+
+        let id = self.source_map.add_expansion(Expansion {
+            name: name.to_string(),
+            definition: mac.span,
+            call_site,
+        });
+        let mut expansion = self.substitute(&mac, &args, id, file);
+        for (index, expanded) in expansion.iter_mut().enumerate() {
+            for name in &hide {
+                if !expanded.hide.contains(name) {
+                    expanded.hide.push(name);
+                }
+            }
+            expanded.tok.first_on_line = false;
+            if index == 0 {
+                expanded.tok.leading_space = tok.tok.leading_space;
+                // A `#` starting a line would be taken for a directive
+                expanded.tok.first_on_line = tok.tok.first_on_line && expanded.tok.tok != Token::Hash;
+            }
+        }
+        input.extend(expansion.into_iter().rev());
+        None
+    }
+
+    /// `__FILE__`, `__LINE__` and the `_Pragma` operator
+    fn expand_builtin(
+        &mut self,
+        name: &str,
+        tok: PpToken<'s>,
+        input: &mut Vec<PpToken<'s>>,
+        file: FileId,
+    ) -> Option<PpToken<'s>> {
+        let expanded = match name {
+            "__FILE__" => {
+                let name = self.presumed_file(tok.tok.span);
+                let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+                Token::LitString(self.arena.alloc_str(&format!("\"{escaped}\"")))
+            }
+            "__LINE__" => {
+                let line = self.presumed_line(tok.tok.span);
+                Token::LitInt(self.arena.alloc_str(&line.to_string()))
+            }
+            "_Pragma" => {
+                self.pragma_operator(tok, input, file);
+                return None;
+            }
+            _ => return Some(tok),
+        };
+        Some(PpToken {
+            tok: SpannedToken {
+                tok: expanded,
+                ..tok.tok
+            },
+            hide: tok.hide,
+        })
+    }
+
+    /// `_Pragma("...")`, which is executed like `#pragma ...`
+    fn pragma_operator(&mut self, tok: PpToken<'s>, input: &mut Vec<PpToken<'s>>, file: FileId) {
+        let operands: Vec<_> = input.iter().rev().take(3).map(|tok| tok.tok.tok).collect();
+        let [Token::LParent, Token::LitString(str), Token::RParent] = operands[..] else {
+            return parser_error!(
+                self,
+                ErrorCode::InvalidDirective,
+                tok.tok.span,
+                "`_Pragma` takes a parenthesized string literal"
+            );
+        };
+        input.truncate(input.len() - 2);
+        let close = input.pop().unwrap();
+        let span = tok.tok.span.to(close.tok.span);
+
+        let str = unsplice(str);
+        let quoted = &str[str.find('"').unwrap_or(0)..];
+        let text = quoted[1..quoted.len() - 1]
+            .replace("\\\\", "\\")
+            .replace("\\\"", "\"");
+        let text: &'s str = self.arena.alloc_str(&text);
+        let tokens = Token::lexer(text)
+            .spanned()
+            .filter_map(|(tok, range)| Some((tok.ok()?, range)))
+            .enumerate()
+            .map(|(index, (tok, range))| SpannedToken {
+                tok,
+                span,
+                first_on_line: false,
+                leading_space: index > 0 && text[..range.start].ends_with(char::is_whitespace),
+            })
+            .collect();
+        self.pragma(tokens, file, span);
+    }
+
+    /// Collects the arguments of an invocation of `mac`, with the input
+    /// positioned after the opening parenthesis. Returns the arguments and
+    /// the closing parenthesis
+    fn collect_args(
+        &mut self,
+        mac: &Macro<'s>,
+        name: &PpToken<'s>,
+        input: &mut Vec<PpToken<'s>>,
+    ) -> Option<(Vec<Vec<PpToken<'s>>>, PpToken<'s>)> {
+        let named = mac.params.as_ref().map_or(0, Vec::len);
+        let mut args = vec![Vec::new()];
+        let mut depth = 0;
+        let close = loop {
+            let Some(tok) = input.pop() else {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidMacroArguments,
+                    name.tok.span,
+                    "unterminated argument list invoking macro `{}`",
+                    mac.name
+                );
+                return None;
+            };
+            match tok.tok.tok {
+                Token::LParent => depth += 1,
+                Token::RParent if depth == 0 => break tok,
+                Token::RParent => depth -= 1,
+                // Commas belong to the variable arguments
+                Token::Comma if depth == 0 && !(mac.is_variadic && args.len() > named) => {
+                    args.push(Vec::new());
+                    continue;
+                }
+                _ => (),
+            }
+            args.last_mut().unwrap().push(tok);
+        };
+
+        if named == 0 && !mac.is_variadic && args.len() == 1 && args[0].is_empty() {
+            args.clear();
+        }
+        if mac.is_variadic && args.len() == named {
+            args.push(Vec::new());
+        }
+        let expected = named + mac.is_variadic as usize;
+        if args.len() != expected {
+            let span = name.tok.span.to(close.tok.span);
+            let at_least = if mac.is_variadic { "at least " } else { "" };
+            parser_error!(
+                self,
+                ErrorCode::InvalidMacroArguments,
+                span,
+                "macro `{}` requires {at_least}{named} argument(s), but {} were given",
+                mac.name,
+                args.len()
+            );
+            return None;
+        }
+        Some((args, close))
+    }
+
+    /// Replaces the parameters in the replacement list of `mac` and performs
+    /// the `#` and `##` operators. Tokens of the replacement list keep their
+    /// spelling location in the definition and are attributed to `expansion`
+    fn substitute(
+        &mut self,
+        mac: &Macro<'s>,
+        args: &[Vec<PpToken<'s>>],
+        expansion: ExpansionId,
+        file: FileId,
+    ) -> Vec<PpToken<'s>> {
+        let body: Vec<_> = mac
+            .body
+            .iter()
+            .map(|tok| SpannedToken {
+                span: tok.span.in_expansion(expansion),
+                ..*tok
+            })
+            .collect();
+        let mut expanded = vec![None; args.len()];
+        let mut subst = Substitution {
+            mac,
+            args,
+            expanded: &mut expanded,
+            file,
+        };
+        self.replace(&mut subst, &body)
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Tok(tok) => Some(tok),
+                Item::Placemarker => None,
+            })
+            .collect()
+    }
+
+    fn replace(&mut self, subst: &mut Substitution<'_, 's>, body: &[SpannedToken<'s>]) -> Vec<Item<'s>> {
+        let mut items: Vec<Item<'s>> = Vec::new();
+        let mut index = 0;
+        while index < body.len() {
+            let tok = body[index];
+            if tok.tok == Token::HashHash && index + 1 < body.len() {
+                let right = body[index + 1];
+                // GNU extension: `, ## __VA_ARGS__` drops the comma if there
+                // are no variable arguments
+                let after_comma = matches!(items.last(), Some(Item::Tok(tok)) if tok.tok.tok == Token::Comma);
+                if after_comma && subst.mac.is_variadic && pp_ident(&right.tok) == Some("__VA_ARGS__") {
+                    let va_args = &subst.args[subst.args.len() - 1];
+                    match va_args.is_empty() {
+                        true => drop(items.pop()),
+                        false => items.extend(va_args.iter().cloned().map(Item::Tok)),
+                    }
+                    index += 2;
+                    continue;
+                }
+                let (operand, used) = self.operand(subst, body, index + 1);
+                index += 1 + used;
+                let left = items.pop().unwrap_or(Item::Placemarker);
+                let mut operand = operand.into_iter();
+                let right = operand.next().unwrap_or(Item::Placemarker);
+                items.extend(self.paste(left, right));
+                items.extend(operand);
+                continue;
+            }
+
+            let param = pp_ident(&tok.tok).and_then(|name| subst.param_index(name));
+            let pasted = body.get(index + 1).is_some_and(|next| next.tok == Token::HashHash);
+            match param {
+                // Arguments are macro expanded unless they are operands of `##`
+                Some(param) if !pasted => {
+                    let arg = match &subst.expanded[param] {
+                        Some(expanded) => expanded.clone(),
+                        None => {
+                            let expanded = self.expand_all(subst.args[param].clone(), subst.file);
+                            subst.expanded[param] = Some(expanded.clone());
+                            expanded
+                        }
+                    };
+                    let first = items.len();
+                    items.extend(arg.into_iter().map(Item::Tok));
+                    if let Some(Item::Tok(first)) = items.get_mut(first) {
+                        first.tok.leading_space = tok.leading_space;
+                    }
+                    index += 1;
+                }
+                _ => {
+                    let (operand, used) = self.operand(subst, body, index);
+                    items.extend(operand);
+                    index += used;
+                }
+            }
+        }
+        items
+    }
+
+    /// The unexpanded replacement of the element of `body` at `index`, as
+    /// used for operands of `#` and `##`. Returns the number of tokens used
+    fn operand(&mut self, subst: &mut Substitution<'_, 's>, body: &[SpannedToken<'s>], index: usize) -> (Vec<Item<'s>>, usize) {
+        let tok = body[index];
+        let name = pp_ident(&tok.tok);
+        if tok.tok == Token::Hash && subst.mac.is_function_like() && index + 1 < body.len() {
+            let (operand, used) = self.operand(subst, body, index + 1);
+            let tokens: Vec<_> = operand
+                .into_iter()
+                .filter_map(|item| match item {
+                    Item::Tok(tok) => Some(tok),
+                    Item::Placemarker => None,
+                })
+                .collect();
+            return (vec![Item::Tok(self.stringify(&tokens, tok))], used + 1);
+        }
+        if name == Some("__VA_OPT__") && subst.mac.is_variadic {
+            if let Some(close) = matching_paren(body, index + 1) {
+                let has_args = !subst.args[subst.args.len() - 1].is_empty();
+                let items = match has_args {
+                    true => self.replace(subst, &body[index + 2..close]),
+                    false => vec![Item::Placemarker],
+                };
+                return (items, close - index + 1);
+            }
+        }
+        if let Some(param) = name.and_then(|name| subst.param_index(name)) {
+            let arg = &subst.args[param];
+            if arg.is_empty() {
+                return (vec![Item::Placemarker], 1);
+            }
+            let mut arg = arg.clone();
+            arg[0].tok.leading_space = tok.leading_space;
+            return (arg.into_iter().map(Item::Tok).collect(), 1);
+        }
+        (vec![Item::Tok(PpToken::new(tok))], 1)
+    }
+
+    /// The `#` operator, which spells `tokens` as a string literal
+    fn stringify(&mut self, tokens: &[PpToken<'s>], hash: SpannedToken<'s>) -> PpToken<'s> {
+        let mut str = String::from("\"");
+        for (index, tok) in tokens.iter().enumerate() {
+            if index > 0 && tok.tok.leading_space {
+                str.push(' ');
+            }
+            let spelling = unsplice(tok.tok.tok.as_str());
+            match tok.tok.tok {
+                Token::LitString(_) | Token::LitChar(_) => {
+                    for char in spelling.chars() {
+                        if matches!(char, '"' | '\\') {
+                            str.push('\\');
+                        }
+                        str.push(char);
+                    }
+                }
+                _ => str.push_str(&spelling),
+            }
+        }
+        str.push('"');
+        PpToken::new(SpannedToken {
+            tok: Token::LitString(self.arena.alloc_str(&str)),
+            ..hash
+        })
+    }
+
+    /// The `##` operator, which joins the spellings of two tokens into one
+    fn paste(&mut self, left: Item<'s>, right: Item<'s>) -> Vec<Item<'s>> {
+        let (left, right) = match (left, right) {
+            (Item::Placemarker, right) => return vec![right],
+            (left, Item::Placemarker) => return vec![left],
+            (Item::Tok(left), Item::Tok(right)) => (left, right),
+        };
+        let text = format!("{}{}", unsplice(left.tok.tok.as_str()), unsplice(right.tok.tok.as_str()));
+        let text: &'s str = self.arena.alloc_str(&text);
+        let mut lexer = Token::lexer(text);
+        match (lexer.next(), lexer.span(), lexer.next()) {
+            (Some(Ok(tok)), range, None) if range == (0..text.len()) => {
+                vec![Item::Tok(PpToken {
+                    tok: SpannedToken { tok, ..left.tok },
+                    hide: left.hide,
+                })]
+            }
+            _ => {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidPaste,
+                    left.tok.span,
+                    "pasting `{}` and `{}` does not give a valid preprocessing token",
+                    left.tok.tok,
+                    right.tok.tok
+                );
+                vec![Item::Tok(left), Item::Tok(right)]
+            }
+        }
+    }
+}
+
+/// Arguments of the macro invocation being substituted
+struct Substitution<'m, 's> {
+    mac: &'m Macro<'s>,
+    args: &'m [Vec<PpToken<'s>>],
+    /// Fully macro expanded arguments, computed on first use
+    expanded: &'m mut [Option<Vec<PpToken<'s>>>],
+    file: FileId,
+}
+
+impl Substitution<'_, '_> {
+    fn param_index(&self, name: &str) -> Option<usize> {
+        match name {
+            "__VA_ARGS__" if self.mac.is_variadic => Some(self.args.len() - 1),
+            name => self.mac.params.as_ref()?.iter().position(|param| *param == name),
+        }
+    }
+}
+
+/// Index of the `)` matching the `(` at `open`
+pub(super) fn matching_paren(tokens: &[SpannedToken], open: usize) -> Option<usize> {
+    if tokens.get(open)?.tok != Token::LParent {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, tok) in tokens.iter().enumerate().skip(open) {
+        match tok.tok {
+            Token::LParent => depth += 1,
+            Token::RParent if depth == 1 => return Some(index),
+            Token::RParent => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
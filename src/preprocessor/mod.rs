@@ -0,0 +1,832 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bumpalo::Bump;
+
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode},
+    lexer::{
+        tokens::{SpannedToken, Token},
+        unsplice, Lexer,
+    },
+    parser::CStandard,
+    parser_error, parser_warn,
+    span::{FileId, SourceMap, Span},
+};
+
+use self::expand::PpToken;
+
+mod eval;
+mod expand;
+
+/// How deep `#include` may nest, which also stops files including themselves
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessorOptions {
+    /// Path of the main file. Used for `__FILE__` and as the starting point
+    /// of `#include "..."`
+    pub file_path: Option<PathBuf>,
+    /// Directories searched by `#include "..."` after the directory of the
+    /// including file
+    pub quote_paths: Vec<PathBuf>,
+    /// Directories searched by `#include <...>`, and by `#include "..."` after
+    /// the quote paths
+    pub include_paths: Vec<PathBuf>,
+    /// Macros defined before preprocessing, spelled `NAME` or `NAME=VALUE`
+    pub defines: Vec<String>,
+    /// Revision of the C standard, which decides `__STDC_VERSION__`
+    pub standard: CStandard,
+}
+
+/// A macro defined by `#define` or predefined by the preprocessor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro<'s> {
+    pub name: &'s str,
+    /// Parameters of a function-like macro. The variadic parameter is not
+    /// included, its arguments are referred to by `__VA_ARGS__`
+    pub params: Option<Vec<&'s str>>,
+    pub is_variadic: bool,
+    /// Replacement list
+    pub body: Vec<SpannedToken<'s>>,
+    /// Span of the name in the definition
+    pub span: Span,
+}
+
+impl Macro<'_> {
+    pub fn is_function_like(&self) -> bool {
+        self.params.is_some()
+    }
+
+    /// Whether a redefinition is allowed to replace `self` silently
+    fn same_definition(&self, other: &Macro) -> bool {
+        self.params == other.params
+            && self.is_variadic == other.is_variadic
+            && self.body.len() == other.body.len()
+            && self.body.iter().zip(&other.body).enumerate().all(|(index, (a, b))| {
+                a.tok == b.tok && (index == 0 || a.leading_space == b.leading_space)
+            })
+    }
+}
+
+/// A `#pragma` directive or `_Pragma` operator the preprocessor does not
+/// handle itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pragma<'s> {
+    /// Tokens after `pragma`
+    pub tokens: Vec<SpannedToken<'s>>,
+    pub span: Span,
+}
+
+/// Runs translation phase 4 on the tokens of a [Lexer]: executes directives,
+/// includes files and expands macros. The result is again a [Lexer], which
+/// can be passed to the parser
+pub struct Preprocessor<'s> {
+    pub options: PreprocessorOptions,
+    /// The main file and every included file
+    pub source_map: SourceMap<'s>,
+    pub macros: HashMap<&'s str, Rc<Macro<'s>>>,
+    pub pragmas: Vec<Pragma<'s>>,
+    pub diagnostics: Vec<Diagnostic>,
+    arena: &'s Bump,
+    main: Option<Lexer<'s>>,
+    output: Vec<SpannedToken<'s>>,
+    /// Diagnostics of the lexers, which are dropped in skipped groups
+    lexer_diagnostics: Vec<Diagnostic>,
+    /// Groups skipped by conditional inclusion
+    skipped: Vec<Span>,
+    /// Files containing `#pragma once`
+    once: HashSet<PathBuf>,
+    include_depth: usize,
+    /// Line offset and file name set by `#line`, per file
+    presumed: HashMap<FileId, (isize, Option<String>)>,
+}
+
+/// State of an `#if` group
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+    span: Span,
+    /// Whether the enclosing group is included
+    parent_active: bool,
+    /// Whether the current branch is included
+    active: bool,
+    /// Whether any branch was included so far
+    taken: bool,
+    seen_else: bool,
+}
+
+impl<'s> Preprocessor<'s> {
+    pub fn new(lexer: Lexer<'s>, arena: &'s Bump) -> Self {
+        Self::with_options(lexer, arena, PreprocessorOptions::default())
+    }
+
+    pub fn with_options(lexer: Lexer<'s>, arena: &'s Bump, options: PreprocessorOptions) -> Self {
+        Self {
+            options,
+            source_map: SourceMap::new(),
+            macros: HashMap::new(),
+            pragmas: Vec::new(),
+            diagnostics: Vec::new(),
+            arena,
+            main: Some(lexer),
+            output: Vec::new(),
+            lexer_diagnostics: Vec::new(),
+            skipped: Vec::new(),
+            once: HashSet::new(),
+            include_depth: 0,
+            presumed: HashMap::new(),
+        }
+    }
+
+    /// Preprocesses the main file. The returned lexer holds the resulting
+    /// tokens together with all lexer and preprocessor diagnostics
+    pub fn preprocess(&mut self) -> Lexer<'s> {
+        let Some(main) = self.main.take() else {
+            return Lexer::new("");
+        };
+        let name = match &self.options.file_path {
+            Some(path) => path.display().to_string(),
+            None => "<input>".to_string(),
+        };
+        self.source_map
+            .add_file(name, self.options.file_path.clone(), main.input, None);
+
+        let predefined = self.arena.alloc_str(&self.predefined_macros());
+        let builtin = self.source_map.add_file("<built-in>", None, predefined, None);
+        let lexer = Lexer::with_file(predefined, builtin);
+        self.lexer_diagnostics.extend(lexer.diagnostics);
+        self.process_file(lexer.tokens, builtin);
+
+        self.lexer_diagnostics.extend(main.diagnostics);
+        self.process_file(main.tokens, FileId::MAIN);
+
+        let skipped = std::mem::take(&mut self.skipped);
+        let mut diagnostics: Vec<_> = std::mem::take(&mut self.lexer_diagnostics)
+            .into_iter()
+            .filter(|diagnostic| {
+                !skipped.iter().any(|span| {
+                    span.file == diagnostic.span.file
+                        && span.start <= diagnostic.span.start
+                        && diagnostic.span.end <= span.end
+                })
+            })
+            .collect();
+        diagnostics.append(&mut self.diagnostics);
+        Lexer {
+            input: main.input,
+            tokens: std::mem::take(&mut self.output),
+            diagnostics,
+            source_map: Some(self.source_map.clone()),
+        }
+    }
+
+    /// Source of the `<built-in>` file defining the predefined macros and
+    /// the macros given in the options
+    fn predefined_macros(&self) -> String {
+        let (date, time) = date_and_time();
+        let version = self.options.standard.stdc_version();
+        let mut src = format!(
+            "#define __STDC__ 1\n\
+             #define __STDC_HOSTED__ 1\n\
+             #define __STDC_VERSION__ {version}\n\
+             #define __DATE__ \"{date}\"\n\
+             #define __TIME__ \"{time}\"\n"
+        );
+        for define in &self.options.defines {
+            let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+            src.push_str(&format!("#define {name} {value}\n"));
+        }
+        src
+    }
+
+    fn process_file(&mut self, tokens: Vec<SpannedToken<'s>>, file: FileId) {
+        let tokens: Vec<_> = tokens.into_iter().map(|tok| self.unspliced(tok)).collect();
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let mut text = Vec::new();
+        let mut skip_start = None;
+        let mut index = 0;
+        while index < tokens.len() {
+            let active = conditionals.last().is_none_or(|cond| cond.active);
+            if tokens[index].tok != Token::Hash || !tokens[index].first_on_line {
+                if active {
+                    text.push(PpToken::new(tokens[index]));
+                }
+                index += 1;
+                continue;
+            }
+
+            let end = index
+                + 1
+                + tokens[index + 1..]
+                    .iter()
+                    .position(|tok| tok.first_on_line)
+                    .unwrap_or(tokens.len() - index - 1);
+            let line = &tokens[index..end];
+            if active {
+                self.flush_text(&mut text, file);
+            }
+            self.directive(line, file, &mut conditionals);
+
+            let now_active = conditionals.last().is_none_or(|cond| cond.active);
+            if active && !now_active {
+                skip_start = Some(line[line.len() - 1].span.end);
+            } else if !active && now_active {
+                if let Some(start) = skip_start.take() {
+                    self.skipped
+                        .push(Span::in_file(start, line[0].span.start, file));
+                }
+            }
+            index = end;
+        }
+        self.flush_text(&mut text, file);
+
+        if let Some(start) = skip_start {
+            let end = self.source_map.file(file).map_or(start, |file| file.src.len());
+            self.skipped.push(Span::in_file(start, end, file));
+        }
+        for cond in conditionals {
+            parser_error!(
+                self,
+                ErrorCode::UnbalancedConditional,
+                cond.span,
+                "unterminated conditional directive"
+            );
+        }
+    }
+
+    /// Removes line splices from identifiers and numbers
+    fn unspliced(&self, tok: SpannedToken<'s>) -> SpannedToken<'s> {
+        let alloc = |str: &'s str| -> &'s str {
+            match unsplice(str) {
+                std::borrow::Cow::Borrowed(str) => str,
+                std::borrow::Cow::Owned(str) => self.arena.alloc_str(&str),
+            }
+        };
+        let cleaned = match tok.tok {
+            Token::Ident(name) => Token::Ident(alloc(name)),
+            Token::LitInt(int) => Token::LitInt(alloc(int)),
+            Token::LitFloat(float) => Token::LitFloat(alloc(float)),
+            tok => tok,
+        };
+        SpannedToken { tok: cleaned, ..tok }
+    }
+
+    /// Expands the text lines collected so far into the output
+    fn flush_text(&mut self, text: &mut Vec<PpToken<'s>>, file: FileId) {
+        if text.is_empty() {
+            return;
+        }
+        let expanded = self.expand_all(std::mem::take(text), file);
+        self.output.extend(expanded.into_iter().map(|tok| tok.tok));
+    }
+
+    /// Executes the directive on `line`, which starts with the `#`
+    fn directive(&mut self, line: &[SpannedToken<'s>], file: FileId, conditionals: &mut Vec<Conditional>) {
+        let span = line[0].span.to(line[line.len() - 1].span);
+        let active = conditionals.last().is_none_or(|cond| cond.active);
+        // Null directive
+        let Some(name_tok) = line.get(1) else {
+            return;
+        };
+        let directive = name_tok.tok;
+        let args = &line[2..];
+        let name = match name_tok.tok {
+            // GNU line marker, `# 12 "file.c"`
+            Token::LitInt(_) if active => return self.line_directive(&line[1..], file, span, false),
+            tok => pp_ident(&tok),
+        };
+        match name.unwrap_or_default() {
+            "if" | "ifdef" | "ifndef" => {
+                let taken = active && self.condition(name_tok, args, file, span);
+                conditionals.push(Conditional {
+                    span,
+                    parent_active: active,
+                    active: taken,
+                    taken: taken || !active,
+                    seen_else: false,
+                });
+            }
+            "elif" | "elifdef" | "elifndef" | "else" => {
+                let Some(cond) = conditionals.last_mut() else {
+                    return parser_error!(
+                        self,
+                        ErrorCode::UnbalancedConditional,
+                        span,
+                        "`#{directive}` without `#if`"
+                    );
+                };
+                if cond.seen_else {
+                    let cond_span = cond.span;
+                    cond.active = false;
+                    let diagnostic = Diagnostic::error(
+                        ErrorCode::UnbalancedConditional,
+                        span,
+                        format!("`#{directive}` after `#else`"),
+                    )
+                    .with_note(format!("the conditional starts at byte {}", cond_span.start));
+                    return self.diagnostics.push(diagnostic);
+                }
+                let cond = *cond;
+                let taken = if name_tok.tok == Token::Else {
+                    self.extra_tokens(name_tok, args);
+                    cond.parent_active && !cond.taken
+                } else {
+                    cond.parent_active && !cond.taken && self.condition(name_tok, args, file, span)
+                };
+                let cond = conditionals.last_mut().unwrap();
+                cond.active = taken;
+                cond.taken |= taken;
+                cond.seen_else = name_tok.tok == Token::Else;
+            }
+            "endif" => {
+                if conditionals.pop().is_none() {
+                    parser_error!(
+                        self,
+                        ErrorCode::UnbalancedConditional,
+                        span,
+                        "`#endif` without `#if`"
+                    );
+                }
+                self.extra_tokens(name_tok, args);
+            }
+            _ if !active => (),
+            "define" => self.define(args, span),
+            "undef" => {
+                match args.first().and_then(|tok| pp_ident(&tok.tok)) {
+                    Some(name) => {
+                        self.macros.remove(name);
+                    }
+                    None => self.expected_macro_name(args.first().map_or(span, |tok| tok.span)),
+                }
+                self.extra_tokens(name_tok, args.get(1..).unwrap_or_default());
+            }
+            "include" => self.include(args, file, span),
+            "line" => self.line_directive(args, file, span, true),
+            "error" | "warning" => {
+                let message = match (args.first(), args.last()) {
+                    (Some(first), Some(last)) => self
+                        .source_map
+                        .source_text(first.span.to(last.span))
+                        .unwrap_or_default(),
+                    _ => "",
+                };
+                let (code, severity) = match name_tok.tok.as_str() {
+                    "error" => (ErrorCode::ErrorDirective, crate::diagnostic::Severity::Error),
+                    _ => (ErrorCode::WarningDirective, crate::diagnostic::Severity::Warning),
+                };
+                self.diagnostics
+                    .push(Diagnostic::new(code, severity, span, format!("#{directive} {message}")));
+            }
+            "pragma" => self.pragma(args.to_vec(), file, span),
+            // Ignored like in GCC
+            "ident" | "sccs" => (),
+            _ => parser_error!(
+                self,
+                ErrorCode::InvalidDirective,
+                name_tok.span,
+                "invalid preprocessing directive `#{directive}`"
+            ),
+        }
+    }
+
+    /// Evaluates the condition of an `#if`, `#ifdef`, `#elif` and so on
+    fn condition(&mut self, name_tok: &SpannedToken<'s>, args: &[SpannedToken<'s>], file: FileId, span: Span) -> bool {
+        let directive = name_tok.tok;
+        let name = pp_ident(&directive).unwrap_or_default();
+        if let Some(negated) = name.strip_suffix("def") {
+            let Some(macro_name) = args.first().and_then(|tok| pp_ident(&tok.tok)) else {
+                self.expected_macro_name(args.first().map_or(span, |tok| tok.span));
+                return false;
+            };
+            self.extra_tokens(name_tok, &args[1..]);
+            return self.is_defined(macro_name) != negated.ends_with('n');
+        }
+        if args.is_empty() {
+            parser_error!(
+                self,
+                ErrorCode::InvalidConditionExpression,
+                span,
+                "`#{directive}` with no expression"
+            );
+            return false;
+        }
+        self.evaluate_condition(args, file, span)
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name) || matches!(name, "__FILE__" | "__LINE__")
+    }
+
+    fn expected_macro_name(&mut self, span: Span) {
+        parser_error!(self, ErrorCode::InvalidDirective, span, "expected macro name");
+    }
+
+    fn extra_tokens(&mut self, name_tok: &SpannedToken<'s>, extra: &[SpannedToken<'s>]) {
+        if let Some(tok) = extra.first() {
+            let directive = name_tok.tok;
+            parser_warn!(
+                self,
+                ErrorCode::InvalidDirective,
+                tok.span,
+                "extra tokens at end of `#{directive}` directive"
+            );
+        }
+    }
+
+    fn define(&mut self, args: &[SpannedToken<'s>], span: Span) {
+        let Some(name_tok) = args.first() else {
+            return self.expected_macro_name(span);
+        };
+        let name = match pp_ident(&name_tok.tok) {
+            Some("defined") => {
+                return parser_error!(
+                    self,
+                    ErrorCode::InvalidDirective,
+                    name_tok.span,
+                    "`defined` cannot be used as a macro name"
+                );
+            }
+            Some(name) => name,
+            None => return self.expected_macro_name(name_tok.span),
+        };
+
+        let mut body = &args[1..];
+        let mut params = None;
+        let mut is_variadic = false;
+        if body.first().is_some_and(|tok| tok.tok == Token::LParent && !tok.leading_space) {
+            let mut names = Vec::new();
+            let mut index = 1;
+            loop {
+                let Some(tok) = body.get(index) else {
+                    return parser_error!(
+                        self,
+                        ErrorCode::InvalidDirective,
+                        span,
+                        "missing `)` in macro parameter list"
+                    );
+                };
+                match (tok.tok, pp_ident(&tok.tok)) {
+                    (Token::RParent, _) if names.is_empty() && !is_variadic => break,
+                    (Token::Ellipsis, _) => is_variadic = true,
+                    (_, Some("__VA_ARGS__" | "__VA_OPT__")) | (_, None) => {
+                        return parser_error!(
+                            self,
+                            ErrorCode::InvalidDirective,
+                            tok.span,
+                            "expected parameter name, found `{}`",
+                            tok.tok
+                        );
+                    }
+                    (_, Some(param)) if names.contains(&param) => {
+                        return parser_error!(
+                            self,
+                            ErrorCode::InvalidDirective,
+                            tok.span,
+                            "duplicate macro parameter `{param}`"
+                        );
+                    }
+                    (_, Some(param)) => names.push(param),
+                }
+                index += 1;
+                match body.get(index).map(|tok| tok.tok) {
+                    Some(Token::RParent) => break,
+                    Some(Token::Comma) if !is_variadic => index += 1,
+                    _ => {
+                        return parser_error!(
+                            self,
+                            ErrorCode::InvalidDirective,
+                            body.get(index).map_or(span, |tok| tok.span),
+                            "expected `,` or `)` in macro parameter list"
+                        );
+                    }
+                }
+            }
+            body = &body[index + 1..];
+            params = Some(names);
+        }
+
+        let mut body = body.to_vec();
+        if let Some(first) = body.first_mut() {
+            first.leading_space = false;
+        }
+        let mac = Macro {
+            name,
+            params,
+            is_variadic,
+            body,
+            span: name_tok.span,
+        };
+        if !self.valid_body(&mac) {
+            return;
+        }
+        if let Some(previous) = self.macros.get(name) {
+            if !previous.same_definition(&mac) {
+                let diagnostic = Diagnostic::warning(
+                    ErrorCode::MacroRedefined,
+                    name_tok.span,
+                    format!("macro `{name}` redefined"),
+                );
+                let diagnostic = match self.source_map.line_col(previous.span) {
+                    Some(pos) => diagnostic.with_note(format!(
+                        "previous definition at {}:{}:{}",
+                        self.source_map.file(previous.span.file).map_or("", |file| &file.name),
+                        pos.line,
+                        pos.col
+                    )),
+                    None => diagnostic,
+                };
+                self.diagnostics.push(diagnostic);
+            }
+        }
+        self.macros.insert(name, Rc::new(mac));
+    }
+
+    /// Checks the use of `#`, `##` and `__VA_ARGS__` in a replacement list
+    fn valid_body(&mut self, mac: &Macro<'s>) -> bool {
+        let body = &mac.body;
+        for edge in [body.first(), body.last()].into_iter().flatten() {
+            if edge.tok == Token::HashHash {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidDirective,
+                    edge.span,
+                    "`##` cannot appear at either end of a macro expansion"
+                );
+                return false;
+            }
+        }
+        for (index, tok) in body.iter().enumerate() {
+            let name = pp_ident(&tok.tok);
+            if matches!(name, Some("__VA_ARGS__" | "__VA_OPT__")) && !mac.is_variadic {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidDirective,
+                    tok.span,
+                    "`{}` can only appear in the expansion of a variadic macro",
+                    tok.tok
+                );
+                return false;
+            }
+            if name == Some("__VA_OPT__") && expand::matching_paren(body, index + 1).is_none() {
+                parser_error!(
+                    self,
+                    ErrorCode::InvalidDirective,
+                    tok.span,
+                    "`__VA_OPT__` must be followed by a parenthesized replacement"
+                );
+                return false;
+            }
+            if tok.tok == Token::Hash && mac.is_function_like() {
+                let next = body.get(index + 1).and_then(|tok| pp_ident(&tok.tok));
+                let is_param = next.is_some_and(|next| {
+                    mac.params.iter().flatten().any(|param| *param == next)
+                        || (mac.is_variadic && matches!(next, "__VA_ARGS__" | "__VA_OPT__"))
+                });
+                if !is_param {
+                    parser_error!(
+                        self,
+                        ErrorCode::InvalidDirective,
+                        tok.span,
+                        "`#` is not followed by a macro parameter"
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn include(&mut self, args: &[SpannedToken<'s>], file: FileId, span: Span) {
+        let Some((name, is_angled)) = self.header_name(args, file) else {
+            return parser_error!(
+                self,
+                ErrorCode::InvalidDirective,
+                span,
+                "expected \"FILENAME\" or <FILENAME> after `#include`"
+            );
+        };
+        let Some(path) = self.find_include(&name, is_angled, file) else {
+            return parser_error!(
+                self,
+                ErrorCode::IncludeNotFound,
+                span,
+                "`{name}` file not found"
+            );
+        };
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.once.contains(&canonical) {
+            return;
+        }
+        if self.include_depth >= MAX_INCLUDE_DEPTH {
+            return parser_error!(
+                self,
+                ErrorCode::IncludeNotFound,
+                span,
+                "`#include` nested deeper than {MAX_INCLUDE_DEPTH} files"
+            );
+        }
+        let src = match fs::read_to_string(&path) {
+            Ok(src) => self.arena.alloc_str(&src),
+            Err(err) => {
+                return parser_error!(
+                    self,
+                    ErrorCode::IncludeNotFound,
+                    span,
+                    "cannot read `{}`: {err}",
+                    path.display()
+                );
+            }
+        };
+        let id = self
+            .source_map
+            .add_file(path.display().to_string(), Some(path), src, Some(span));
+        let lexer = Lexer::with_file(src, id);
+        self.lexer_diagnostics.extend(lexer.diagnostics);
+        self.include_depth += 1;
+        self.process_file(lexer.tokens, id);
+        self.include_depth -= 1;
+    }
+
+    /// The file name of an `#include` and whether it is spelled `<...>`
+    fn header_name(&mut self, args: &[SpannedToken<'s>], file: FileId) -> Option<(String, bool)> {
+        match args.first()?.tok {
+            Token::LitString(str) if str.starts_with('"') => {
+                return Some((str[1..str.len() - 1].to_string(), false));
+            }
+            // The name is taken from the source, as it is not made of tokens
+            Token::LessThan => {
+                let close = args.iter().position(|tok| tok.tok == Token::GreaterThan)?;
+                let name = self
+                    .source_map
+                    .source_text(Span::in_file(args[0].span.end, args[close].span.start, file))?;
+                return Some((name.to_string(), true));
+            }
+            _ => (),
+        }
+        // Computed include, the expansion has to form one of the above
+        let tokens = args.iter().copied().map(PpToken::new).collect();
+        let expanded = self.expand_all(tokens, file);
+        match expanded.first()?.tok.tok {
+            Token::LitString(str) if str.starts_with('"') => Some((str[1..str.len() - 1].to_string(), false)),
+            Token::LessThan => {
+                let mut name = String::new();
+                for tok in &expanded[1..] {
+                    if tok.tok.tok == Token::GreaterThan {
+                        return Some((name, true));
+                    }
+                    if tok.tok.leading_space && !name.is_empty() {
+                        name.push(' ');
+                    }
+                    name.push_str(tok.tok.tok.as_str());
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn find_include(&self, name: &str, is_angled: bool, file: FileId) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        let current_dir = self
+            .source_map
+            .file(file)
+            .and_then(|file| file.path.as_deref())
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let quote_dirs = std::iter::once(&current_dir).chain(&self.options.quote_paths);
+        let dirs: Vec<&PathBuf> = match is_angled {
+            true => self.options.include_paths.iter().collect(),
+            false => quote_dirs.chain(&self.options.include_paths).collect(),
+        };
+        dirs.into_iter()
+            .map(|dir| dir.join(path))
+            .find(|path| path.is_file())
+    }
+
+    /// `#line` and GNU line markers. `args` start with the line number
+    fn line_directive(&mut self, args: &[SpannedToken<'s>], file: FileId, span: Span, expand: bool) {
+        let tokens: Vec<_> = args.iter().copied().map(PpToken::new).collect();
+        let tokens = match expand {
+            true => self.expand_all(tokens, file),
+            false => tokens,
+        };
+        let line = match tokens.first().map(|tok| tok.tok.tok) {
+            Some(Token::LitInt(digits)) if digits.bytes().all(|byte| byte.is_ascii_digit()) => {
+                digits.parse::<isize>().ok()
+            }
+            _ => None,
+        };
+        let Some(line) = line else {
+            return parser_error!(
+                self,
+                ErrorCode::InvalidDirective,
+                span,
+                "`#line` requires a positive decimal line number"
+            );
+        };
+        let name = match tokens.get(1).map(|tok| tok.tok.tok) {
+            Some(Token::LitString(str)) if str.starts_with('"') => Some(str[1..str.len() - 1].to_string()),
+            None => None,
+            Some(_) if !expand => None,
+            Some(tok) => {
+                return parser_error!(
+                    self,
+                    ErrorCode::InvalidDirective,
+                    span,
+                    "invalid filename `{tok}` in `#line`"
+                );
+            }
+        };
+        // The line after the directive gets the given number
+        let physical = self.physical_line(span) as isize + 1;
+        let presumed = self.presumed.entry(file).or_default();
+        presumed.0 = line - physical;
+        if name.is_some() {
+            presumed.1 = name;
+        }
+    }
+
+    fn physical_line(&self, span: Span) -> usize {
+        self.source_map.line_col(span).map_or(0, |pos| pos.line)
+    }
+
+    /// Line number of the expansion location of `span` as changed by `#line`
+    fn presumed_line(&self, span: Span) -> isize {
+        let span = self.source_map.expansion_span(span);
+        let delta = self.presumed.get(&span.file).map_or(0, |presumed| presumed.0);
+        self.physical_line(span) as isize + delta
+    }
+
+    /// File name of the expansion location of `span` as changed by `#line`
+    fn presumed_file(&self, span: Span) -> String {
+        let span = self.source_map.expansion_span(span);
+        match self.presumed.get(&span.file).and_then(|presumed| presumed.1.clone()) {
+            Some(name) => name,
+            None => self
+                .source_map
+                .file(span.file)
+                .map_or_else(String::new, |file| file.name.clone()),
+        }
+    }
+
+    fn pragma(&mut self, tokens: Vec<SpannedToken<'s>>, file: FileId, span: Span) {
+        if let [once] = &tokens[..] {
+            if once.tok == Token::Ident("once") {
+                if let Some(path) = self.source_map.file(file).and_then(|file| file.path.clone()) {
+                    self.once.insert(fs::canonicalize(&path).unwrap_or(path));
+                }
+                return;
+            }
+        }
+        self.pragmas.push(Pragma { tokens, span });
+    }
+}
+
+/// The identifier a token is spelled as, including keywords, which are
+/// ordinary identifiers to the preprocessor
+fn pp_ident<'s>(tok: &Token<'s>) -> Option<&'s str> {
+    match tok {
+        Token::Ident(name) => Some(name),
+        Token::LitString(_) | Token::LitChar(_) | Token::LitInt(_) | Token::LitFloat(_) => None,
+        tok => {
+            let spelling = tok.as_str();
+            spelling
+                .starts_with(|char: char| char.is_ascii_alphabetic() || char == '_')
+                .then_some(spelling)
+        }
+    }
+}
+
+/// Current date and time in UTC, formatted for `__DATE__` and `__TIME__`
+fn date_and_time() -> (String, String) {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from days since 1970-01-01, by Howard Hinnant
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (
+        format!("{} {day:>2} {year}", MONTHS[month as usize - 1]),
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    )
+}
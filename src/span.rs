@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+
+/// Identifies a file in a [SourceMap]. The file passed to the lexer directly
+/// is [FileId::MAIN]
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct FileId(pub u32);
+
+impl FileId {
+    pub const MAIN: FileId = FileId(0);
+}
+
+/// Identifies a macro expansion in a [SourceMap]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct ExpansionId(pub u32);
+
+/// Byte range `start..end` into the source text of `file`. This is where the
+/// text was spelled, which for tokens produced by a macro is inside the
+/// macro definition. The macro invocation is then found through `expansion`
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub file: FileId,
+    /// The macro expansion that produced the text, if any
+    pub expansion: Option<ExpansionId>,
+}
+
+impl Span {
+    /// A span in the main file
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self::in_file(start, end, FileId::MAIN)
+    }
+
+    pub const fn in_file(start: usize, end: usize, file: FileId) -> Self {
+        Self {
+            start,
+            end,
+            file,
+            expansion: None,
+        }
+    }
+
+    /// The same text, produced by the macro expansion `expansion`
+    pub const fn in_expansion(self, expansion: ExpansionId) -> Self {
+        Self {
+            expansion: Some(expansion),
+            ..self
+        }
+    }
+
+    /// Returns a span covering both `self` and `other`. Spans in different
+    /// files or macro expansions cannot be merged, in which case `self` is
+    /// returned
+    pub fn to(self, other: Span) -> Span {
+        if self.file != other.file || self.expansion != other.expansion {
+            return self;
+        }
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+
+    /// The empty span at the start of this span
+    pub fn start_point(self) -> Span {
+        Span {
+            end: self.start,
+            ..self
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The part of `src` covered by this span
+    pub fn source_text<'s>(&self, src: &'s str) -> &'s str {
+        &src[self.start..self.end]
+    }
+}
+
+/// 1-based line and column. The column is counted in bytes
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Maps byte offsets of a source text to lines and columns
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    /// Byte offset of the first character of every line
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(src: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            src.bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Self {
+            line_starts,
+            len: src.len(),
+        }
+    }
+
+    /// Line and column of a byte offset. Offsets past the end of the text
+    /// are clamped to the end
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        LineCol {
+            line: line + 1,
+            col: offset - self.line_starts[line] + 1,
+        }
+    }
+
+    /// Start and end position of a span
+    pub fn span_to_line_cols(&self, span: Span) -> (LineCol, LineCol) {
+        (self.line_col(span.start), self.line_col(span.end))
+    }
+
+    /// Byte range of a 1-based line, excluding its line break
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map(|next| next - 1)
+            .unwrap_or(self.len);
+        Some(Span::new(start, end))
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+/// A file that tokens were lexed from
+#[derive(Debug, Clone)]
+pub struct SourceFile<'a> {
+    /// Name used in diagnostics and `__FILE__`
+    pub name: String,
+    /// Where the file was read from, if it is on disk
+    pub path: Option<PathBuf>,
+    pub src: &'a str,
+    pub line_index: LineIndex,
+    /// Span of the `#include` directive that included the file
+    pub included_from: Option<Span>,
+}
+
+/// An invocation of a macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub name: String,
+    /// Span of the macro name in its definition
+    pub definition: Span,
+    /// Span of the invocation, from the macro name to the closing
+    /// parenthesis of the arguments. Its own `expansion` is the expansion
+    /// the invocation was produced by, if any
+    pub call_site: Span,
+}
+
+/// All files taking part in a translation unit, and the macro expansions
+/// that produced their tokens
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap<'a> {
+    files: Vec<SourceFile<'a>>,
+    expansions: Vec<Expansion>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(
+        &mut self,
+        name: impl Into<String>,
+        path: Option<PathBuf>,
+        src: &'a str,
+        included_from: Option<Span>,
+    ) -> FileId {
+        self.files.push(SourceFile {
+            name: name.into(),
+            path,
+            src,
+            line_index: LineIndex::new(src),
+            included_from,
+        });
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    pub fn file(&self, id: FileId) -> Option<&SourceFile<'a>> {
+        self.files.get(id.0 as usize)
+    }
+
+    pub fn files(&self) -> &[SourceFile<'a>] {
+        &self.files
+    }
+
+    /// The source text covered by a span
+    pub fn source_text(&self, span: Span) -> Option<&'a str> {
+        self.file(span.file)?.src.get(span.start..span.end)
+    }
+
+    /// Line and column of the start of a span
+    pub fn line_col(&self, span: Span) -> Option<LineCol> {
+        Some(self.file(span.file)?.line_index.line_col(span.start))
+    }
+
+    pub fn add_expansion(&mut self, expansion: Expansion) -> ExpansionId {
+        self.expansions.push(expansion);
+        ExpansionId(self.expansions.len() as u32 - 1)
+    }
+
+    pub fn expansion(&self, id: ExpansionId) -> Option<&Expansion> {
+        self.expansions.get(id.0 as usize)
+    }
+
+    /// The macro expansions that produced `span`, innermost first
+    pub fn expansion_chain(&self, span: Span) -> Vec<&Expansion> {
+        let mut chain = Vec::new();
+        let mut next = span.expansion;
+        while let Some(expansion) = next.and_then(|id| self.expansion(id)) {
+            chain.push(expansion);
+            next = expansion.call_site.expansion;
+        }
+        chain
+    }
+
+    /// Where the text of `span` appears after macro expansion: the outermost
+    /// macro invocation that produced it, or `span` itself
+    pub fn expansion_span(&self, span: Span) -> Span {
+        self.expansion_chain(span)
+            .last()
+            .map_or(span, |expansion| expansion.call_site)
+    }
+
+    /// The `#include` directives that led to `file`, innermost first
+    pub fn include_chain(&self, file: FileId) -> Vec<Span> {
+        let mut chain = Vec::new();
+        let mut next = self.file(file).and_then(|file| file.included_from);
+        while let Some(span) = next {
+            chain.push(span);
+            next = self.file(span.file).and_then(|file| file.included_from);
+        }
+        chain
+    }
+
+    /// `name:line:col` of the start of a span
+    pub fn location(&self, span: Span) -> Option<String> {
+        let pos = self.line_col(span)?;
+        Some(format!("{}:{}:{}", self.file(span.file)?.name, pos.line, pos.col))
+    }
+
+    /// Key that orders spans as they appear in the translation unit: by the
+    /// offset of the outermost `#include` leading to them, then by the
+    /// expanded location
+    pub fn translation_order(&self, span: Span) -> (usize, FileId, usize) {
+        let expanded = self.expansion_span(span);
+        let top = self.include_chain(expanded.file).last().copied().unwrap_or(expanded);
+        (top.start, expanded.file, expanded.start)
+    }
+}
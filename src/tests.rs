@@ -1,37 +1,1590 @@
-#[cfg(test)]
-mod tests {
-    use std::fs::{self};
+use std::fs::{self};
 
-    use bumpalo::Bump;
+use bumpalo::Bump;
 
-    use crate::{lexer::Lexer, parser::Parser};
+use crate::{
+    ast::{
+        expr::{
+            Designator, Encoding, Expression, InOperator, IndexExpr, InfixExpr, Initializer, PostExpr, PostOperator,
+            PreOperator, PrefixExpr,
+        },
+        stmt::{Alignment, DataStorageClass, ForInit, IfType, ReturnStmt, Statement},
+        types::{ParamStyle, Qualifiers, Type},
+    },
+    lexer::{tokens::Token, Lexer},
+    parser::{parse_declaration, parse_expression, parse_statement, parse_type_name, CStandard, Parser, ParserOptions},
+    preprocessor::{Preprocessor, PreprocessorOptions},
+    diagnostic::{Diagnostic, ErrorCode, Severity},
+    span::{LineCol, LineIndex, Span},
+    translation_unit::TranslationUnit,
+};
 
-    const TESTS_PATH: &str = "tests/main.c";
+const TESTS_PATH: &str = "tests/main.c";
 
-    #[test]
-    fn test_lexer() {
-        let file_content = fs::read_to_string(TESTS_PATH).unwrap();
-        let lexer = Lexer::new(&file_content);
-        println!("{:?}", lexer.tokens)
-    }
+#[test]
+fn test_lexer() {
+    let file_content = fs::read_to_string(TESTS_PATH).unwrap();
+    let lexer = Lexer::new(&file_content);
+    println!("{:?}", lexer.tokens)
+}
+
+#[test]
+fn test_parser() {
+    let file_content = fs::read_to_string(TESTS_PATH).unwrap();
+    let lexer = Lexer::new(&file_content);
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let result = parser.parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    dbg!(result.ast);
+}
+
+#[test]
+fn test_src_code_reconstruction() {
+    let file_content = fs::read_to_string(TESTS_PATH).unwrap();
+    let lexer = Lexer::new(&file_content);
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let printed = crate::ast_to_string(parser.parse().ast);
+    let reparsed = Parser::new(Lexer::new(&printed), &parse_arena).parse();
+    assert!(!reparsed.has_errors(), "{:#?}", reparsed.diagnostics);
+    assert_eq!(crate::ast_to_string(reparsed.ast), printed);
+
+    // Printing never panics, also for declarations of every kind and for
+    // what is left after recovering from errors
+    let src = "struct S; union U { int a; }; enum E { A }; typedef struct { int x; } T;
+    int f(int a) { struct S *s; enum E e = A; typedef int I; x = (1 + ; return; }
+    int g( { } enum : int; typedef; struct { int b : ; };";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(result.has_errors());
+    crate::ast_to_string(result.ast);
+}
+
+#[test]
+fn test_line_index() {
+    let src = "int a;\n\nint b;";
+    let index = LineIndex::new(src);
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.line_col(0), LineCol { line: 1, col: 1 });
+    assert_eq!(index.line_col(8), LineCol { line: 3, col: 1 });
+    assert_eq!(index.line_col(12), LineCol { line: 3, col: 5 });
+    assert_eq!(index.line_span(3), Some(Span::new(8, 14)));
+}
 
-    #[test]
-    fn test_parser() {
-        let file_content = fs::read_to_string(TESTS_PATH).unwrap();
-        let lexer = Lexer::new(&file_content);
+#[test]
+fn test_spans() {
+    let src = "int main() {\n    x + y * 10;\n}";
+    let lexer = Lexer::new(src);
+    let index = lexer.line_index();
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let stmts = parser.parse().ast;
+    let Statement::Function(func) = &stmts[0] else {
+        panic!("expected function, got {:?}", stmts[0]);
+    };
+    assert_eq!(func.span, Span::new(0, src.len()));
+    let Statement::Expression(Expression::Infix(add)) = &func.body.as_ref().unwrap().block[0]
+    else {
+        panic!("expected addition");
+    };
+    assert_eq!(add.span.source_text(src), "x + y * 10");
+    assert_eq!(add.right.span().source_text(src), "y * 10");
+    assert_eq!(
+        index.line_col(add.right.span().start),
+        LineCol { line: 2, col: 9 }
+    );
+}
+
+#[test]
+fn test_diagnostics() {
+    let src = "int main() {\n    x + @;\n}";
+    let lexer = Lexer::new(src);
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let result = parser.parse();
+    let error = result.errors().next().unwrap();
+    assert_eq!(error.code, ErrorCode::UnknownCharacter);
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.span.source_text(src), "@");
+
+    let src = "int main() {\n    while (x {}\n}";
+    let lexer = Lexer::new(src);
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let result = parser.parse();
+    let error = result.errors().next().unwrap();
+    assert_eq!(error.code, ErrorCode::UnexpectedToken);
+    assert_eq!(error.expected, vec!["`)`"]);
+    assert_eq!(error.found.as_deref(), Some("`{`"));
+    assert!(error.render(src).contains("2 |     while (x {}"));
+    assert!(result.into_result().is_err());
+}
+
+#[test]
+fn test_malformed_input_does_not_panic() {
+    let deep = "(".repeat(10_000);
+    let inputs = [
+        "int",
+        "int x",
+        "int main(",
+        "int main(int",
+        "int main() {",
+        "struct",
+        "short long x;",
+        "x = ? : ;",
+        "int a[99999999999999999999];",
+        "'';",
+        "sizeof(",
+        "* = 1;",
+        "for (;;",
+        "if (x) else",
+        "}}}",
+        &deep,
+    ];
+    for input in inputs {
+        let lexer = Lexer::new(input);
         let parse_arena = Bump::new();
         let mut parser = Parser::new(lexer, &parse_arena);
-        let stmts = parser.parse();
-        dbg!(stmts);
+        let result = parser.parse();
+        assert!(result.has_errors(), "no error for {input:?}");
     }
+}
 
-    #[test]
-    fn test_src_code_reconstruction() {
-        let file_content = fs::read_to_string(TESTS_PATH).unwrap();
-        let lexer = Lexer::new(&file_content);
-        let parse_arena = Bump::new();
-        let mut parser = Parser::new(lexer, &parse_arena);
-        let stmts = parser.parse();
+#[test]
+fn test_recovery_does_not_repeat_diagnostics() {
+    let src = "case 1: ".repeat(20_000);
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(&src), &parse_arena).parse();
+    // One error per label, and one for the missing statement
+    assert!(result.diagnostics.len() <= 20_001, "{} diagnostics", result.diagnostics.len());
+
+    // Each failed statement keeps its first error when it is skipped, instead
+    // of reporting the errors of its nested statements once per retry
+    let src = "while (x) case 1: ".repeat(300);
+    let result = Parser::new(Lexer::new(&src), &parse_arena).parse();
+    assert!(result.diagnostics.len() <= 600, "{} diagnostics", result.diagnostics.len());
+
+    // A missing operand is reported once, not again by the delimiter or the
+    // end of input that follows it
+    for src in ["a = (1 + ;", "f(1, );", "a = (1 +", "int b[2] = {1, [0] = };"] {
+        let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+        assert_eq!(result.diagnostics.len(), 1, "{src}: {:#?}", result.diagnostics);
+    }
+    let result = parse_expression("x = {1}", &parse_arena, ParserOptions::default(), &[]);
+    assert_eq!(result.diagnostics.len(), 1, "{:#?}", result.diagnostics);
+    assert!(matches!(result.ast, Some(Expression::Infix(_))));
+}
+
+#[test]
+fn test_null_statements_do_not_nest() {
+    let src = format!("void f(void) {{ {} }}", ";".repeat(300));
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(&src), &parse_arena).parse();
+    assert!(result.diagnostics.is_empty(), "{:#?}", result.diagnostics);
+}
+
+#[test]
+fn test_error_recovery() {
+    let src = "int a = 1 +;\nint main() {\n    x + * ;\n    while (x +) { y; }\n    return 0;\n    z;\n}\nint b;";
+    let lexer = Lexer::new(src);
+    let parse_arena = Bump::new();
+    let mut parser = Parser::new(lexer, &parse_arena);
+    let result = parser.parse();
+    assert_eq!(result.errors().count(), 3, "{:#?}", result.diagnostics);
+    assert_eq!(result.ast.len(), 3);
+
+    let Statement::Variable(a) = &result.ast[0] else {
+        panic!("expected variable, got {:?}", result.ast[0]);
+    };
+    let Some(Initializer::Expr(Expression::Infix(value))) = &a.declarators[0].init else {
+        panic!("expected infix value");
+    };
+    assert!(matches!(value.right, Expression::Error { .. }));
+
+    let Statement::Function(main) = &result.ast[1] else {
+        panic!("expected function, got {:?}", result.ast[1]);
+    };
+    let body = &main.body.as_ref().unwrap().block;
+    assert_eq!(body.len(), 4);
+    assert!(matches!(body[0], Statement::Expression(_)));
+    assert!(matches!(body[1], Statement::While(_)));
+    let Statement::Return(ret) = &body[2] else {
+        panic!("expected return statement, got {:?}", body[2]);
+    };
+    assert_eq!(ret.span.source_text(src), "return 0;");
+    assert!(matches!(body[3], Statement::Expression(_)));
+    assert!(matches!(result.ast[2], Statement::Variable(_)));
+}
+
+#[test]
+fn test_comments_and_splices() {
+    let src = "/* multi\n   line */ int a; // spliced \\\n comment\nin\\\nt b = 1\\\n2;\n#define X a ## b \\\n  c\n%: x %:%:";
+    let lexer = Lexer::new(src);
+    assert!(lexer.diagnostics.is_empty(), "{:#?}", lexer.diagnostics);
+    let toks: Vec<_> = lexer.tokens.iter().map(|tok| tok.tok).collect();
+    assert_eq!(
+        &toks[..8],
+        &[
+            Token::Ident("int"),
+            Token::Ident("a"),
+            Token::Semicolon,
+            Token::Ident("in\\\nt"),
+            Token::Ident("b"),
+            Token::Assign,
+            Token::LitInt("1\\\n2"),
+            Token::Semicolon,
+        ]
+    );
+    assert!(lexer.tokens[0].first_on_line && lexer.tokens[0].leading_space);
+    assert!(lexer.tokens[3].first_on_line);
+    let hashes: Vec<_> = lexer
+        .tokens
+        .iter()
+        .filter(|tok| matches!(tok.tok, Token::Hash | Token::HashHash))
+        .map(|tok| (tok.tok, tok.first_on_line))
+        .collect();
+    assert_eq!(
+        hashes,
+        [
+            (Token::Hash, true),
+            (Token::HashHash, false),
+            (Token::Hash, true),
+            (Token::HashHash, false),
+        ]
+    );
+    // The continued `c` still belongs to the `#define` line
+    let c = lexer.tokens.iter().find(|tok| tok.tok == Token::Ident("c")).unwrap();
+    assert!(!c.first_on_line);
+
+    let parse_arena = Bump::new();
+    let result = Parser::new(lexer, &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    assert_eq!(result.ast.len(), 2);
+    // Both directives are skipped, pointing to the preprocessor
+    assert_eq!(result.diagnostics.len(), 2);
+    assert!(result
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == ErrorCode::IgnoredDirective
+            && diagnostic.message.contains("`Preprocessor`")));
+}
+
+#[test]
+fn test_unterminated_comment() {
+    let lexer = Lexer::new("int a; /* never closed\nint b;");
+    assert_eq!(lexer.tokens.len(), 3);
+    assert_eq!(lexer.diagnostics[0].code, ErrorCode::UnterminatedComment);
+    assert_eq!(lexer.diagnostics[0].span, Span::new(7, 9));
+}
+
+fn parse_literals(src: &str) -> (Vec<String>, Vec<Diagnostic>) {
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let literals = result
+        .ast
+        .iter()
+        .map(|stmt| match stmt {
+            Statement::Expression(expr) => format!("{expr:?}"),
+            stmt => format!("{stmt:?}"),
+        })
+        .collect();
+    (literals, result.diagnostics)
+}
+
+#[test]
+fn test_int_literals() {
+    let (literals, diagnostics) = parse_literals(
+        "0; 2147483647; 2147483648; 0x7fffffff; 0x80000000; 0xffffffffffffffff; 017; \
+         0b1010; 1'000'000; 10u; 10ul; 10LU; 10ll; 10ull; 0x8000000000000000ll; \
+         5z; 5uz; 255uwb; 1wb;",
+    );
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    let expected = [
+        "LiteralInt { value: 0",
+        "LiteralInt { value: 2147483647",
+        "LiteralLong { value: 2147483648",
+        "LiteralInt { value: 2147483647",
+        "LiteralUInt { value: 2147483648",
+        "LiteralULong { value: 18446744073709551615",
+        "LiteralInt { value: 15",
+        "LiteralInt { value: 10",
+        "LiteralInt { value: 1000000",
+        "LiteralUInt { value: 10",
+        "LiteralULong { value: 10",
+        "LiteralULong { value: 10",
+        "LiteralLongLong { value: 10",
+        "LiteralULongLong { value: 10",
+        "LiteralULongLong { value: 9223372036854775808",
+        "LiteralSize { value: 5, is_unsigned: false",
+        "LiteralSize { value: 5, is_unsigned: true",
+        "LiteralBitInt { value: 255, width: 8, is_unsigned: true",
+        "LiteralBitInt { value: 1, width: 2, is_unsigned: false",
+    ];
+    assert_eq!(literals.len(), expected.len());
+    for (literal, expected) in literals.iter().zip(expected) {
+        assert!(literal.starts_with(expected), "{literal} != {expected}");
     }
 
+    let (_, diagnostics) =
+        parse_literals("09; 0x; 10lL; 10uu; 1e; 18446744073709551616; 0b12;");
+    assert_eq!(diagnostics.len(), 7, "{diagnostics:#?}");
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.code == ErrorCode::InvalidLiteral));
+
+    // Decimal constants too large for `long long` become unsigned with a
+    // warning
+    let (literals, diagnostics) = parse_literals("9223372036854775808; 18446744073709551615;");
+    assert!(literals[0].starts_with("LiteralULongLong { value: 9223372036854775808"));
+    assert!(literals[1].starts_with("LiteralULongLong { value: 18446744073709551615"));
+    assert_eq!(diagnostics.len(), 2, "{diagnostics:#?}");
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Warning));
+
+    // Binary literals and digit separators are C23
+    let parse_arena = Bump::new();
+    let options = ParserOptions {
+        standard: CStandard::C17,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new("0b1010; 1'000; 1'000.5; 0b1'0;"), &parse_arena, options).parse();
+    let messages: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "use of binary literals requires C23, but C17 is selected",
+            "use of digit separators requires C23, but C17 is selected",
+            "use of digit separators requires C23, but C17 is selected",
+            "use of binary literals requires C23, but C17 is selected",
+            "use of digit separators requires C23, but C17 is selected",
+        ]
+    );
+}
+
+#[test]
+fn test_float_literals() {
+    let (literals, diagnostics) =
+        parse_literals("1.5; .5; 5.; 1e10; 1e-2f; 0x1p-3; 0x1.8p1; 2.5L; 1'000.5;");
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    let expected = [
+        "LiteralDouble { value: 1.5",
+        "LiteralDouble { value: 0.5",
+        "LiteralDouble { value: 5.0",
+        "LiteralDouble { value: 10000000000.0",
+        "LiteralFloat { value: 0.01",
+        "LiteralDouble { value: 0.125",
+        "LiteralDouble { value: 3.0",
+        "LiteralLongDouble { value: 2.5",
+        "LiteralDouble { value: 1000.5",
+    ];
+    assert_eq!(literals.len(), expected.len());
+    for (literal, expected) in literals.iter().zip(expected) {
+        assert!(literal.starts_with(expected), "{literal} != {expected}");
+    }
+
+    let (_, diagnostics) = parse_literals("1.5x; 1.5ff;");
+    assert_eq!(diagnostics.len(), 2, "{diagnostics:#?}");
+}
+
+#[test]
+fn test_char_and_string_literals() {
+    let src = r#"'a'; '\n'; '\x41'; '\0'; '\377'; L'é'; u'x'; U'\U0001F600'; 'ab';
+"a\tb" "\x41" "c"; u8"é"; L"wide" "r"; u"\U0001F600";"#;
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    // Only the multi-character constant is warned about
+    assert_eq!(result.diagnostics.len(), 1);
+    let literals: Vec<_> = result
+        .ast
+        .iter()
+        .map(|stmt| match stmt {
+            Statement::Expression(expr) => expr,
+            stmt => panic!("expected expression, got {stmt:?}"),
+        })
+        .collect();
+
+    let chars: Vec<_> = literals[..9]
+        .iter()
+        .map(|expr| match expr {
+            Expression::LiteralChar(literal) => (literal.encoding, literal.value),
+            expr => panic!("expected char literal, got {expr:?}"),
+        })
+        .collect();
+    assert_eq!(
+        chars,
+        [
+            (Encoding::Plain, 'a' as u32),
+            (Encoding::Plain, 10),
+            (Encoding::Plain, 0x41),
+            (Encoding::Plain, 0),
+            (Encoding::Plain, 0xff),
+            (Encoding::Wide, 0xe9),
+            (Encoding::Utf16, 'x' as u32),
+            (Encoding::Utf32, 0x1f600),
+            (Encoding::Plain, 0x6162),
+        ]
+    );
+
+    let Expression::LiteralString(concat) = literals[9] else {
+        panic!("expected string literal, got {:?}", literals[9]);
+    };
+    assert_eq!(concat.spelling, [r#""a\tb""#, r#""\x41""#, r#""c""#]);
+    assert_eq!(concat.bytes(), b"a\tbAc");
+    assert_eq!(literals[9].to_string(), r#""a\tb" "\x41" "c""#);
+
+    let strings: Vec<_> = literals[10..]
+        .iter()
+        .map(|expr| match expr {
+            Expression::LiteralString(literal) => (literal.encoding, literal.value.to_vec()),
+            expr => panic!("expected string literal, got {expr:?}"),
+        })
+        .collect();
+    assert_eq!(
+        strings,
+        [
+            (Encoding::Utf8, vec![0xc3, 0xa9]),
+            (Encoding::Wide, "wider".chars().map(|char| char as u32).collect()),
+            (Encoding::Utf16, vec![0xd83d, 0xde00]),
+        ]
+    );
+
+    let parse_arena = Bump::new();
+    let result = Parser::new(
+        Lexer::new(r#"''; '\x100'; u8"a" L"b"; "\x"; u'ab';"#),
+        &parse_arena,
+    )
+    .parse();
+    assert_eq!(result.errors().count(), 5, "{:#?}", result.diagnostics);
+}
+
+/// Preprocesses `src` and spells the resulting tokens, separated by a
+/// space where the source had whitespace
+fn preprocess(src: &str, options: PreprocessorOptions) -> (String, Vec<Diagnostic>) {
+    let arena = Bump::new();
+    let mut preprocessor = Preprocessor::with_options(Lexer::new(src), &arena, options);
+    let lexer = preprocessor.preprocess();
+    let mut out = String::new();
+    for tok in &lexer.tokens {
+        if tok.first_on_line && !out.is_empty() {
+            out.push('\n');
+        } else if tok.leading_space && !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(tok.tok.as_str());
+    }
+    (out, lexer.diagnostics)
+}
+
+#[test]
+fn test_macro_expansion() {
+    let src = r#"
+#define N 10
+#define ADD(a, b) ((a) + (b))
+#define STR(x) #x
+#define XSTR(x) STR(x)
+#define CAT(a, b) a ## b
+#define LOG(fmt, ...) printf(fmt, ## __VA_ARGS__)
+#define OPT(x, ...) f(x __VA_OPT__(,) __VA_ARGS__)
+#define SELF SELF + 1
+#define EMPTY()
+int x = ADD(N, ADD(1, 2)) EMPTY();
+char *s = STR("a\n" 'b') XSTR(N);
+int CAT(var, 1) = CAT(0x, 1f);
+LOG("x"); LOG("%d", 1, 2);
+OPT(1); OPT(1, 2, 3);
+SELF;
+ADD
+(N, 1);
+"#;
+    let (out, diagnostics) = preprocess(src, PreprocessorOptions::default());
+    assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    assert_eq!(
+        out,
+        r#"int x = ((10) + (((1) + (2))));
+char *s = "\"a\\n\" 'b'" "10";
+int var1 = 0x1f;
+printf("x"); printf("%d", 1, 2);
+f(1); f(1, 2, 3);
+SELF + 1;
+((10) + (1));"#
+    );
+
+    let (_, diagnostics) = preprocess(
+        "#define F(a) a\n#define G(x) #y\n#define H(x) ## x\n#define N 1\n#define N 2\nF(1, 2) F(",
+        PreprocessorOptions::default(),
+    );
+    let codes: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(
+        codes,
+        [
+            ErrorCode::InvalidDirective,
+            ErrorCode::InvalidDirective,
+            ErrorCode::MacroRedefined,
+            ErrorCode::InvalidMacroArguments,
+            ErrorCode::InvalidMacroArguments,
+        ]
+    );
+}
+
+#[test]
+fn test_conditional_inclusion() {
+    let src = r#"
+#define A 2
+#if A > 1 && defined(A) && !defined B
+a
+#elif 1 / 0
+#else
+#endif
+#ifdef B
+b # garbage ' not a char
+#elifndef C
+c
+#endif
+#if (-1 < 0u) || 'a' != 97 || 0 ? 1 : 0
+wrong
+#elif __STDC_VERSION__ >= 201710L && (1 ? 2 : (1 / 0))
+d __LINE__ __FILE__
+#endif
+#line 100 "renamed.c"
+__LINE__ __FILE__
+#pragma pack(1)
+_Pragma("once") _Pragma("weak sym")
+#warning careful
+#error stop "here"
+"#;
+    let arena = Bump::new();
+    let mut preprocessor = Preprocessor::new(Lexer::new(src), &arena);
+    let lexer = preprocessor.preprocess();
+    let spelled: Vec<_> = lexer.tokens.iter().map(|tok| tok.tok.as_str()).collect();
+    assert_eq!(spelled, ["a", "c", "d", "16", "\"<input>\"", "100", "\"renamed.c\""]);
+    let pragmas: Vec<Vec<_>> = preprocessor
+        .pragmas
+        .iter()
+        .map(|pragma| pragma.tokens.iter().map(|tok| tok.tok.as_str()).collect())
+        .collect();
+    assert_eq!(pragmas, [vec!["pack", "(", "1", ")"], vec!["weak", "sym"]]);
+    let messages: Vec<_> = lexer
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.severity, diagnostic.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (ErrorCode::WarningDirective, Severity::Warning, "#warning careful"),
+            (ErrorCode::ErrorDirective, Severity::Error, "#error stop \"here\""),
+        ]
+    );
+
+    let (_, diagnostics) = preprocess("#if 1 +\n#endif\n#else\n#if 1\n", PreprocessorOptions::default());
+    let codes: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(
+        codes,
+        [
+            ErrorCode::InvalidConditionExpression,
+            ErrorCode::UnbalancedConditional,
+            ErrorCode::UnbalancedConditional,
+        ]
+    );
+
+    // `__STDC_VERSION__` follows the standard
+    let versions: Vec<_> = [CStandard::C89, CStandard::C99, CStandard::C11, CStandard::C17, CStandard::C23]
+        .into_iter()
+        .map(|standard| preprocess("__STDC_VERSION__", PreprocessorOptions { standard, ..Default::default() }).0)
+        .collect();
+    assert_eq!(versions, ["199409L", "199901L", "201112L", "201710L", "202311L"]);
+}
+
+#[test]
+fn test_includes() {
+    let dir = std::env::temp_dir().join(format!("parcer-include-{}", std::process::id()));
+    let system = dir.join("system");
+    fs::create_dir_all(&system).unwrap();
+    fs::write(dir.join("local.h"), "#pragma once\nint local = 1;\n#include <sys.h>\n").unwrap();
+    fs::write(system.join("sys.h"), "#ifndef SYS_H\n#define SYS_H\nint sys = __LINE__;\n#endif\n").unwrap();
+    fs::write(dir.join("main.c"), "").unwrap();
+    let options = PreprocessorOptions {
+        file_path: Some(dir.join("main.c")),
+        include_paths: vec![system],
+        defines: vec!["HEADER=\"local.h\"".to_string()],
+        ..Default::default()
+    };
+
+    let src = "#include \"local.h\"\n#include HEADER\n#include <sys.h>\n#include <local.h>\nint main = 0;\n";
+    let arena = Bump::new();
+    let mut preprocessor = Preprocessor::with_options(Lexer::new(src), &arena, options);
+    let lexer = preprocessor.preprocess();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let codes: Vec<_> = lexer.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::IncludeNotFound]);
+    let files: Vec<_> = preprocessor.source_map.files().iter().map(|file| file.included_from.is_some()).collect();
+    assert_eq!(files, [false, false, true, true, true]);
+
+    let parse_arena = Bump::new();
+    let result = Parser::new(lexer, &parse_arena).parse();
+    assert_eq!(result.errors().count(), 1, "{:#?}", result.diagnostics);
+    assert_eq!(
+        crate::ast_to_string(result.ast),
+        Parser::new(Lexer::new("int local = 1; int sys = 3; int main = 0;"), &parse_arena)
+            .parse()
+            .ast
+            .into_iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<String>()
+    );
+}
+
+#[test]
+fn test_expansion_provenance() {
+    let dir = std::env::temp_dir().join(format!("parcer-provenance-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("x.h"), "#define INNER(x) (x +)\n#define OUTER(x) INNER(x)\n#define ONE 1\n").unwrap();
+    let options = PreprocessorOptions {
+        file_path: Some(dir.join("main.c")),
+        ..Default::default()
+    };
+    let src = "#include \"x.h\"\nint a = ONE;\nint b = OUTER(2);\n";
+    let arena = Bump::new();
+    let lexer = Preprocessor::with_options(Lexer::new(src), &arena, options).preprocess();
+    fs::remove_dir_all(&dir).unwrap();
+    let map = lexer.source_map.clone().unwrap();
+
+    let parse_arena = Bump::new();
+    let result = Parser::new(lexer, &parse_arena).parse();
+    let Statement::Variable(one) = &result.ast[0] else {
+        panic!("expected variable, got {:?}", result.ast[0]);
+    };
+    let span = one.declarators[0].init.as_ref().unwrap().span();
+    assert_eq!(map.location(span).unwrap(), format!("{}:3:13", dir.join("x.h").display()));
+    assert_eq!(map.location(map.expansion_span(span)).unwrap(), format!("{}:2:9", dir.join("main.c").display()));
+
+    let error = result
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.is_error())
+        .unwrap();
+    assert_eq!(map.source_text(error.span), Some(")"));
+    let names: Vec<_> = map.expansion_chain(error.span).iter().map(|expansion| expansion.name.as_str()).collect();
+    assert_eq!(names, ["INNER", "OUTER"]);
+    assert_eq!(
+        error.notes,
+        [
+            format!("in expansion of macro `INNER` defined at {}:1:9", dir.join("x.h").display()),
+            format!("in expansion of macro `OUTER` defined at {}:2:9", dir.join("x.h").display()),
+        ]
+    );
+    assert!(error.render_with(&map).contains("#define INNER(x) (x +)"));
+}
+
+#[test]
+fn test_switch() {
+    let src = "int f(int n) {
+        switch (n % 4) {
+        case 0:
+            while (n > 0) {
+                n = n - 1;
+        case 3: case 2:
+                n = n - 1;
+        case 1:
+                n = n - 1;
+            }
+        default: ;
+        }
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Function(function) = &result.ast[0] else {
+        panic!("expected function, got {:?}", result.ast[0]);
+    };
+    let Statement::Switch(switch) = &function.body.as_ref().unwrap().block[0] else {
+        panic!("expected switch statement");
+    };
+    let Statement::Block(body) = switch.body else {
+        panic!("expected block as switch body, got {:?}", switch.body);
+    };
+    let [Statement::Case(case), Statement::Default(default)] = &body.block[..] else {
+        panic!("expected case and default labels, got {:#?}", body.block);
+    };
+    assert!(matches!(default.body, Statement::Empty(_)));
+    let Statement::While(inner) = case.body else {
+        panic!("expected while loop after `case 0:`, got {:?}", case.body);
+    };
+    let Statement::Block(loop_body) = inner.body else {
+        panic!("expected block as loop body, got {:?}", inner.body);
+    };
+    // `case 3: case 2:` labels the same statement
+    let Statement::Case(three) = &loop_body.block[1] else {
+        panic!("expected nested case, got {:?}", loop_body.block[1]);
+    };
+    assert_eq!(three.value.to_string(), "3");
+    let Statement::Case(two) = three.body else {
+        panic!("expected `case 2:` after `case 3:`, got {:?}", three.body);
+    };
+    assert!(matches!(two.body, Statement::Expression(_)));
+    assert!(matches!(loop_body.block[2], Statement::Case(_)));
+    assert_eq!(
+        result.ast[0].to_string(),
+        "int f(int n) {switch (n % 4) {case 0: while (n > 0) {n = n - 1;case 3: case 2: n = n - 1;case 1: n = n - 1;}default: ;}}"
+    );
+
+    let src = "int f(int n) { switch (n) { case 1 ... 3: n = 0; } case 4: ; }";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::ExtensionDisabled, ErrorCode::MisplacedStatement]);
+    let options = ParserOptions {
+        gnu_extensions: true,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::MisplacedStatement]);
+
+    // Runs of labels do not count against the nesting limit
+    let labels: String = (0..300).map(|n| format!("case {n}: ")).collect();
+    let src = format!("int f(int n) {{ switch (n) {{ {labels} done: default: return n; }} }}");
+    let result = Parser::new(Lexer::new(&src), &parse_arena).parse();
+    assert!(result.diagnostics.is_empty(), "{:#?}", result.diagnostics);
+}
+
+#[test]
+fn test_jump_statements() {
+    let src = "int f(int n) {
+        do {
+            if (n) { continue; }
+            break;
+        } while (n < 10);
+        goto end;
+    end:
+    again: return;
+        return n + 1;
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Function(function) = &result.ast[0] else {
+        panic!("expected function, got {:?}", result.ast[0]);
+    };
+    let body = &function.body.as_ref().unwrap().block;
+    let [Statement::DoWhile(do_while), Statement::Goto(goto), Statement::Label(label), Statement::Return(ret)] = &body[..] else {
+        panic!("unexpected statements {body:#?}");
+    };
+    assert!(matches!(do_while.cond, Expression::Infix(_)));
+    let Statement::Block(do_body) = do_while.body else {
+        panic!("expected block as loop body, got {:?}", do_while.body);
+    };
+    assert!(matches!(do_body.block[1], Statement::Break(_)));
+    assert_eq!(goto.label, "end");
+    assert_eq!(label.name, "end");
+    let Statement::Label(again) = label.body else {
+        panic!("expected second label, got {:?}", label.body);
+    };
+    assert!(matches!(again.body, Statement::Return(ReturnStmt { val: None, .. })));
+    assert_eq!(ret.val.as_ref().unwrap().to_string(), "n + 1");
+
+    let result = Parser::new(Lexer::new("int f() { break; continue; goto; }"), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(
+        codes,
+        [
+            ErrorCode::MisplacedStatement,
+            ErrorCode::MisplacedStatement,
+            ErrorCode::ExpectedIdentifier,
+        ]
+    );
+}
+
+#[test]
+fn test_declarators() {
+    let src = "int (*fp)(int, char);
+    char *(*arr[4])[8];
+    void (*signal(int, void (*)(int)))(int);
+    void f(int n, int a[static n], int b[*], ...);
+    int (*g(int n))[3] {
+        p = (int (*)[3]) q;
+        n = sizeof(char *[2]);
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let [Statement::Variable(fp), Statement::Variable(arr), Statement::Function(signal), Statement::Function(f), Statement::Function(g)] =
+        &result.ast[..]
+    else {
+        panic!("expected 2 variables and 3 functions, got {:#?}", result.ast);
+    };
+    assert_eq!(fp.to_string(), "int (*fp)(int, char);");
+    // Array of 4 pointers to arrays of 8 pointers to char
+    assert_eq!(arr.to_string(), "char *(*arr[4])[8];");
+    assert_eq!(signal.ret_data_type.to_string(), "void (*)(int)");
+    let params: Vec<_> = signal.params.iter().map(|param| param.to_string()).collect();
+    assert_eq!(params, ["int", "void (*)(int)"]);
+    assert!(signal.body.is_none());
+    let params: Vec<_> = f.params.iter().map(|param| param.to_string()).collect();
+    assert_eq!(params, ["int n", "int a[static n]", "int b[*]"]);
+    assert!(f.is_variadic);
+    assert!(matches!(f.params[1].data_type, Type::Array { is_static: true, .. }));
+    assert_eq!(g.ret_data_type.to_string(), "int (*)[3]");
+
+    let body = &g.body.as_ref().unwrap().block;
+    let Statement::Expression(Expression::Infix(cast)) = &body[0] else {
+        panic!("expected assignment, got {:?}", body[0]);
+    };
+    let Expression::Prefix(PrefixExpr { op: PreOperator::Cast(cast_type), .. }) = cast.right else {
+        panic!("expected cast, got {:?}", cast.right);
+    };
+    assert_eq!(cast_type.to_string(), "int (*)[3]");
+    let Statement::Expression(Expression::Infix(size)) = &body[1] else {
+        panic!("expected assignment, got {:?}", body[1]);
+    };
+    assert_eq!(size.right.to_string(), "sizeof(char *[2])");
+}
+
+#[test]
+fn test_initializers() {
+    let src = "int a = 1, *b, c[3] = {1, 2, 3,};
+    struct point p = {.x = 1, .y = {}}, grid[2][2] = {[1] = {0, 1}, [0][1] = 2};";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let [Statement::Variable(ints), Statement::Variable(structs)] = &result.ast[..] else {
+        panic!("expected 2 declarations, got {:#?}", result.ast);
+    };
+    let names: Vec<_> = ints.declarators.iter().map(|declarator| declarator.name).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(ints.to_string(), "int a = 1;int *b;int c[3] = {1, 2, 3};");
+    let Some(Initializer::List { items, .. }) = &structs.declarators[0].init else {
+        panic!("expected initializer list");
+    };
+    assert!(matches!(items[1].value, Initializer::List { ref items, .. } if items.is_empty()));
+    let grid = structs.declarators[1].init.as_ref().unwrap();
+    assert_eq!(grid.to_string(), "{[1] = {0, 1}, [0][1] = 2}");
+
+    let src = "int a[8] = {[1 ... 5] = 1};";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::ExtensionDisabled]);
+    let options = ParserOptions {
+        gnu_extensions: true,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    assert!(result.diagnostics.is_empty(), "{:#?}", result.diagnostics);
+    let Statement::Variable(a) = &result.ast[0] else {
+        panic!("expected variable, got {:?}", result.ast[0]);
+    };
+    let Some(Initializer::List { items, .. }) = &a.declarators[0].init else {
+        panic!("expected initializer list");
+    };
+    assert!(matches!(items[0].designators[..], [Designator::Range { .. }]));
+}
+
+#[test]
+fn test_tagged_types() {
+    let src = "struct P { int x, y; } p;
+    struct list { struct list *next; int data[]; };
+    union U { struct { int a : 3, : 2, b : 1; }; float f; };
+    enum color { RED, GREEN = 2, BLUE, };
+    enum small : uint8_t { A, B };
+    struct outer { struct inner { int z; } in; };
+    struct fwd;";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let [Statement::Variable(p), Statement::Struct(list), Statement::Union(u), Statement::Enum(color), Statement::Enum(small), Statement::Struct(outer), Statement::Struct(fwd)] =
+        &result.ast[..]
+    else {
+        panic!("unexpected statements {:#?}", result.ast);
+    };
+    let Type::Struct { name: Some("P"), definition: Some(point), .. } = p.declarators[0].data_type else {
+        panic!("expected inline struct definition, got {:?}", p.declarators[0].data_type);
+    };
+    let fields: Vec<_> = point.fields.as_ref().unwrap().iter().map(|field| field.to_string()).collect();
+    assert_eq!(fields, ["int x", "int y"]);
+    // Flexible array member
+    let fields: Vec<_> = list.fields.as_ref().unwrap().iter().map(|field| field.to_string()).collect();
+    assert_eq!(fields, ["struct list *next", "int data[]"]);
+
+    let members = u.fields.as_ref().unwrap();
+    assert_eq!(members[0].name, None);
+    let Type::Struct { definition: Some(anonymous), .. } = members[0].field_type else {
+        panic!("expected anonymous struct, got {:?}", members[0].field_type);
+    };
+    let fields: Vec<_> = anonymous.fields.as_ref().unwrap().iter().map(|field| field.to_string()).collect();
+    assert_eq!(fields, ["int a : 3", "int : 2", "int b : 1"]);
+
+    let variants = color.variants.as_ref().unwrap();
+    let names: Vec<_> = variants.iter().map(|variant| variant.name).collect();
+    assert_eq!(names, ["RED", "GREEN", "BLUE"]);
+    assert_eq!(variants[1].value.as_ref().unwrap().to_string(), "2");
+    assert_eq!(small.underlying_type.as_ref().unwrap().to_string(), "uint8_t");
+    let inner = &outer.fields.as_ref().unwrap()[0];
+    assert!(matches!(inner.field_type, Type::Struct { name: Some("inner"), definition: Some(_), .. }));
+    assert_eq!((fwd.name, &fwd.fields), (Some("fwd"), &None));
+    assert_eq!(
+        crate::ast_to_string(result.ast),
+        "struct P {int x;int y;} p;struct list {struct list *next;int data[];};\
+         union U {struct {int a : 3;int : 2;int b : 1;};float f;};enum color {RED, GREEN = 2, BLUE};\
+         enum small : uint8_t {A, B};struct outer {struct inner {int z;} in;};struct fwd;"
+    );
+
+    // Definitions are printed with their members, where they appear
+    let src = "struct S { int a : 3; }; enum E : unsigned char { A = 1 << 2, B }; union U;
+    struct { int a; } x; struct Q { int x; } q, *pq; struct [[deprecated]] D { int d [[maybe_unused]]; };
+    enum [[nodiscard]] { C } c; void f(union V { long l; } *v);";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let printed = crate::ast_to_string(result.ast);
+    assert_eq!(
+        printed,
+        "struct S {int a : 3;};enum E : unsigned char {A = 1 << 2, B};union U;struct {int a;} x;\
+         struct Q {int x;} q, *pq;struct [[deprecated]] D {int d [[maybe_unused]];};enum [[nodiscard]] {C} c;\
+         void f(union V {long l;} *v);"
+    );
+    let reparsed = Parser::new(Lexer::new(&printed), &parse_arena).parse();
+    assert!(!reparsed.has_errors(), "{:#?}", reparsed.diagnostics);
+    assert_eq!(crate::ast_to_string(reparsed.ast), printed);
+}
+
+#[test]
+fn test_typedef_names() {
+    let src = "typedef int T, *PT;
+    typedef struct node { int v; } node;
+    T * a;
+    int b, c;
+    void f(void) {
+        b * c;
+        T x = (T)b + (b);
+        {
+            int T = 2;
+            T * c;
+        }
+        node * n;
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Typedef(typedef) = &result.ast[0] else {
+        panic!("expected typedef, got {:?}", result.ast[0]);
+    };
+    let names: Vec<_> = typedef.declarators.iter().map(|declarator| declarator.name).collect();
+    assert_eq!(names, ["T", "PT"]);
+    assert_eq!(typedef.declarators[1].data_type.to_string(), "int *");
+    let Statement::Typedef(node) = &result.ast[1] else {
+        panic!("expected typedef, got {:?}", result.ast[1]);
+    };
+    assert!(matches!(node.declarators[0].data_type, Type::Struct { definition: Some(_), .. }));
+    let Statement::Variable(a) = &result.ast[2] else {
+        panic!("expected `T * a` to declare a pointer, got {:?}", result.ast[2]);
+    };
+    assert_eq!(a.declarators[0].data_type.to_string(), "T *");
+
+    let Statement::Function(f) = &result.ast[4] else {
+        panic!("expected function, got {:?}", result.ast[4]);
+    };
+    let body = &f.body.as_ref().unwrap().block;
+    assert!(matches!(body[0], Statement::Expression(Expression::Infix(_))));
+    let Statement::Variable(x) = &body[1] else {
+        panic!("expected variable, got {:?}", body[1]);
+    };
+    let Some(Initializer::Expr(Expression::Infix(sum))) = &x.declarators[0].init else {
+        panic!("expected sum, got {:?}", x.declarators[0].init);
+    };
+    assert!(matches!(sum.left, Expression::Prefix(PrefixExpr { op: PreOperator::Cast(_), .. })));
+    assert!(matches!(sum.right, Expression::Ident { name: "b", .. }));
+    // The variable `T` hides the typedef in the inner block
+    let Statement::Block(inner) = &body[2] else {
+        panic!("expected block, got {:?}", body[2]);
+    };
+    assert!(matches!(inner.block[1], Statement::Expression(_)));
+    assert!(matches!(body[3], Statement::Variable(_)));
+
+    // Typedefs print back with their specifiers and attributes, also inside
+    // functions
+    let src = "typedef int T, *PT; typedef struct node { int v; } node, *pnode;
+    [[deprecated]] typedef const int *P __attribute__((aligned(8)));
+    void g(void) { typedef unsigned long U; U u = 1; }";
+    let options = ParserOptions {
+        gnu_extensions: true,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let printed = crate::ast_to_string(result.ast);
+    assert_eq!(
+        printed,
+        "typedef int T;typedef int *PT;typedef struct node {int v;} node, *pnode;\
+         [[deprecated]] typedef const int *P __attribute__((aligned(8)));\
+         void g(void) {typedef unsigned long U;U u = 1;}"
+    );
+    let reparsed = Parser::with_options(Lexer::new(&printed), &parse_arena, options).parse();
+    assert!(!reparsed.has_errors(), "{:#?}", reparsed.diagnostics);
+    assert_eq!(crate::ast_to_string(reparsed.ast), printed);
+}
+
+#[test]
+fn test_primitive_types() {
+    let cases = [
+        ("unsigned long long int", "unsigned long long"),
+        ("int long unsigned long", "unsigned long long"),
+        ("long double", "long double"),
+        ("signed char", "signed char"),
+        ("char", "char"),
+        ("signed", "int"),
+        ("short unsigned", "unsigned short"),
+        ("_Bool", "_Bool"),
+        ("_Complex double", "double _Complex"),
+        ("unsigned _BitInt(24)", "unsigned _BitInt(24)"),
+    ];
+    let parse_arena = Bump::new();
+    for (specifiers, canonical) in cases {
+        let src = format!("{specifiers} x;");
+        let result = Parser::new(Lexer::new(&src), &parse_arena).parse();
+        assert!(!result.has_errors(), "{src}: {:#?}", result.diagnostics);
+        let Statement::Variable(x) = &result.ast[0] else {
+            panic!("expected variable, got {:?}", result.ast[0]);
+        };
+        assert!(matches!(x.declarators[0].data_type, Type::Primitive { .. }));
+        assert_eq!(x.declarators[0].data_type.to_string(), canonical, "{src}");
+    }
+
+    for src in ["short long x;", "unsigned signed x;", "long long long x;", "double float x;", "_Complex int x;"] {
+        let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+        let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+        assert_eq!(codes, [ErrorCode::InvalidTypeSpecifiers], "{src}");
+    }
+}
+
+#[test]
+fn test_qualifiers() {
+    let src = "const char *const p;
+    static _Thread_local volatile int counter;
+    _Noreturn inline void die(int code);
+    _Alignas(16) alignas(double) unsigned char buf[32];
+    int *restrict _Atomic q, * const volatile *r;
+    _Atomic(long) ticks;
+    typedef const int CI;
+    CI volatile x;";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let reconstructed: Vec<_> = result.ast.iter().filter(|stmt| !matches!(stmt, Statement::Typedef(_))).map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "const char *const p;",
+            "static _Thread_local volatile int counter;",
+            "inline _Noreturn void die(int code);",
+            "_Alignas(16) _Alignas(double) unsigned char buf[32];",
+            "int *restrict _Atomic q;int *const volatile *r;",
+            "_Atomic long ticks;",
+            "volatile CI x;",
+        ]
+    );
+
+    let Statement::Variable(p) = &result.ast[0] else {
+        panic!("expected variable, got {:?}", result.ast[0]);
+    };
+    let Type::Pointer { data_type, qualifiers, .. } = p.declarators[0].data_type else {
+        panic!("expected pointer, got {:?}", p.declarators[0].data_type);
+    };
+    assert_eq!(qualifiers, Qualifiers::CONST);
+    assert_eq!(data_type.qualifiers(), Qualifiers::CONST);
+
+    let Statement::Variable(counter) = &result.ast[1] else {
+        panic!("expected variable, got {:?}", result.ast[1]);
+    };
+    assert_eq!(counter.specifiers.data_storage_class, DataStorageClass::Static);
+    assert!(counter.specifiers.is_thread_local);
+    let Statement::Function(die) = &result.ast[2] else {
+        panic!("expected function, got {:?}", result.ast[2]);
+    };
+    assert!(die.specifiers.is_inline && die.specifiers.is_noreturn);
+    let Statement::Variable(buf) = &result.ast[3] else {
+        panic!("expected variable, got {:?}", result.ast[3]);
+    };
+    assert!(matches!(
+        buf.specifiers.alignment[..],
+        [Alignment::Expr { .. }, Alignment::Type { .. }]
+    ));
+
+    let src = "static extern int x; static static int y;";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let diagnostics: Vec<_> =
+        result.diagnostics.iter().map(|diagnostic| (diagnostic.code, diagnostic.message.as_str())).collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (ErrorCode::ConflictingSpecifiers, "storage class specifier `extern` conflicts with `static`"),
+            (ErrorCode::DuplicateSpecifier, "duplicate storage class specifier `static`"),
+        ]
+    );
+}
+
+#[test]
+fn test_expression_grammar() {
+    let src = "a->b[i].c++ ? x : y, z;
+    a = b += c;
+    p ? q : r ? s : t;
+    x <<= 1, y >>= 2;
+    (a + b) * c - -d;
+    f(a, (b, c), g(h)[0]);";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let reconstructed: Vec<_> = result.ast.iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "a->b[i].c++ ? x : y, z;",
+            "a = b += c;",
+            "p ? q : r ? s : t;",
+            "x <<= 1, y >>= 2;",
+            "(a + b) * c - -d;",
+            "f(a, (b, c), g(h)[0]);",
+        ]
+    );
+
+    let Statement::Expression(Expression::Comma(comma)) = &result.ast[0] else {
+        panic!("expected comma expression, got {:?}", result.ast[0]);
+    };
+    let Expression::Conditional(conditional) = comma.left else {
+        panic!("expected conditional, got {:?}", comma.left);
+    };
+    let Expression::Post(PostExpr { val: Expression::Member(c), op: PostOperator::Incr, .. }) = conditional.cond else {
+        panic!("expected postfix increment, got {:?}", conditional.cond);
+    };
+    assert!(!c.is_arrow && c.member == "c");
+    let Expression::Index(IndexExpr { val: Expression::Member(b), .. }) = c.val else {
+        panic!("expected subscript, got {:?}", c.val);
+    };
+    assert!(b.is_arrow && b.member == "b");
+
+    let Statement::Expression(Expression::Infix(assign)) = &result.ast[1] else {
+        panic!("expected assignment, got {:?}", result.ast[1]);
+    };
+    assert!(matches!(assign.right, Expression::Infix(InfixExpr { op: InOperator::AssignAdd, .. })));
+    let Statement::Expression(Expression::Conditional(outer)) = &result.ast[2] else {
+        panic!("expected conditional, got {:?}", result.ast[2]);
+    };
+    assert!(matches!(outer.otherwise, Expression::Conditional(_)));
+    let Statement::Expression(Expression::Call(call)) = &result.ast[5] else {
+        panic!("expected call, got {:?}", result.ast[5]);
+    };
+    assert_eq!(call.args.len(), 3);
+}
+
+#[test]
+fn test_casts_and_compound_literals() {
+    let src = "typedef struct P { int x; } P;
+    int n = sizeof n + sizeof(n) * 2;
+    int m = sizeof(P) + sizeof (int[]){1, 2, 3} + _Alignof(double) + alignof(P *);
+    P *p = &(P){.x = 1};
+    int k = (int)(n) + (n) - ((P){2}).x;";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let reconstructed: Vec<_> = result.ast[1..].iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "int n = sizeof n + sizeof n * 2;",
+            "int m = sizeof(P) + sizeof(int[]){1, 2, 3} + _Alignof(double) + _Alignof(P *);",
+            "P *p = &(P){.x = 1};",
+            "int k = (int)n + n - (P){2}.x;",
+        ]
+    );
+
+    let Statement::Variable(m) = &result.ast[2] else {
+        panic!("expected variable, got {:?}", result.ast[2]);
+    };
+    let Some(Initializer::Expr(Expression::Infix(sum))) = &m.declarators[0].init else {
+        panic!("expected sum, got {:?}", m.declarators[0].init);
+    };
+    assert!(matches!(sum.right, Expression::AlignOfType { .. }));
+    // `sizeof(P) + sizeof (int[]){1, 2, 3}` is the leftmost sum
+    let Expression::Infix(InfixExpr { left: Expression::Infix(first), .. }) = sum.left else {
+        panic!("expected sum, got {:?}", sum.left);
+    };
+    assert!(matches!(first.left, Expression::SizeOfType { .. }));
+    let Expression::Prefix(PrefixExpr { op: PreOperator::SizeOf, val, .. }) = first.right else {
+        panic!("expected sizeof expression, got {:?}", first.right);
+    };
+    assert!(matches!(val, Expression::CompoundLiteral { .. }));
+
+    let src = "int a = _Alignof a;";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::ExtensionDisabled]);
+}
+
+#[test]
+fn test_c23_features() {
+    let src = "[[nodiscard, gnu::cold]] static inline int f(void);
+    static_assert(sizeof(int) >= 2, \"int too small\");
+    _Static_assert(1);
+    typeof(1 + 2) a = _Generic(a, int: 1, char *: 2, default: nullptr);
+    const typeof_unqual(int) b = true;
+    constexpr auto c = false;
+    [[fallthrough]];";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let reconstructed: Vec<_> = result.ast.iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "[[nodiscard, gnu::cold]] static inline int f(void);",
+            "_Static_assert(sizeof(int) >= 2, \"int too small\");",
+            "_Static_assert(1);",
+            "typeof(1 + 2) a = _Generic(a, int: 1, char *: 2, default: nullptr);",
+            "const typeof_unqual(int) b = true;",
+            "constexpr auto c = false;",
+            "[[fallthrough]];",
+        ]
+    );
+    let Statement::Variable(c) = &result.ast[5] else {
+        panic!("expected variable, got {:?}", result.ast[5]);
+    };
+    assert_eq!(c.specifiers.data_storage_class, DataStorageClass::None);
+    assert!(matches!(c.declarators[0].data_type, Type::Auto { .. }));
+
+    // Attributes after the declared name
+    let src = "int x [[deprecated]]; int g [[nodiscard]] (void); struct S { int x [[deprecated]]; };";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let reconstructed: Vec<_> = result.ast[..2].iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(reconstructed, ["int x [[deprecated]];", "[[nodiscard]] int g(void);"]);
+    let Statement::Struct(s) = &result.ast[2] else {
+        panic!("expected struct, got {:?}", result.ast[2]);
+    };
+    assert_eq!(s.fields.as_ref().unwrap()[0].attributes[0].name, "deprecated");
+
+    // Static assertions among the members
+    let src = "struct S { int x; _Static_assert(1, \"m\"); static_assert(sizeof(int)); int y; };";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Struct(s) = &result.ast[0] else {
+        panic!("expected struct, got {:?}", result.ast[0]);
+    };
+    assert_eq!(s.fields.as_ref().unwrap().len(), 2);
+
+    // C23 keywords are identifiers before C23, and newer features are
+    // diagnosed
+    let src = "int bool = true;
+    _Static_assert(1);
+    _Thread_local int t;";
+    let options = ParserOptions {
+        standard: CStandard::C99,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    // `_Static_assert` is C11 and omitting its message is C23
+    assert_eq!(codes, [ErrorCode::UnavailableInStandard; 3]);
+    let Statement::Variable(var) = &result.ast[0] else {
+        panic!("expected variable, got {:?}", result.ast[0]);
+    };
+    assert_eq!(var.declarators[0].name, "bool");
+    assert!(matches!(var.declarators[0].init, Some(Initializer::Expr(Expression::Ident { name: "true", .. }))));
+}
+
+#[test]
+fn test_gnu_extensions() {
+    let src = "struct __attribute__((packed)) P { int x; char c[0]; };
+    static __inline__ __attribute__((always_inline, const)) int f(int a __attribute__((unused)));
+    __extension__ unsigned __int128 big __attribute__((aligned(16)));
+    __asm__(\".globl f\");
+    void g(int n, __builtin_va_list ap) {
+        int v = ({ int t = n; t * 2; });
+        v += __builtin_va_arg(ap, int) + __builtin_offsetof(struct P, c[1]) + __alignof__(long);
+        __asm__ __volatile__(\"add %1, %0\" : \"=r\"(v) : [in] \"r\"(n), \"0\"(v) : \"cc\", \"memory\");
+        void *target = &&done;
+        goto *target;
+    done:
+        v = __extension__ sizeof(__typeof__(v));
+    }";
+    let parse_arena = Bump::new();
+    let options = ParserOptions {
+        gnu_extensions: true,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Struct(packed) = &result.ast[0] else {
+        panic!("expected struct, got {:?}", result.ast[0]);
+    };
+    assert_eq!(packed.attributes[0].name, "packed");
+    assert!(packed.attributes[0].is_gnu);
+    let reconstructed: Vec<_> = result.ast[1..4].iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "__attribute__((always_inline, const)) static inline int f(int a __attribute__((unused)));",
+            "__extension__ unsigned __int128 big __attribute__((aligned(16)));",
+            "asm(\".globl f\");",
+        ]
+    );
+
+    let Statement::Function(g) = &result.ast[4] else {
+        panic!("expected function, got {:?}", result.ast[4]);
+    };
+    let body = &g.body.as_ref().unwrap().block;
+    let reconstructed: Vec<_> = body[..5].iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(
+        reconstructed,
+        [
+            "int v = ({int t = n;t * 2;});",
+            "v += __builtin_va_arg(ap, int) + __builtin_offsetof(struct P, c[1]) + _Alignof(long);",
+            "asm volatile(\"add %1, %0\" : \"=r\"(v) : [in] \"r\"(n), \"0\"(v) : \"cc\", \"memory\");",
+            "void *target = &&done;",
+            "goto *target;",
+        ]
+    );
+    let Statement::Label(label) = &body[5] else {
+        panic!("expected label, got {:?}", body[5]);
+    };
+    assert_eq!(label.body.to_string(), "v = __extension__ sizeof(typeof(v));");
+
+    // Without GNU extensions, reserved spellings are reported and `asm` is an
+    // ordinary identifier
+    let src = "__attribute__((unused)) int x; int a[0]; asm(\"nop\");
+    int b[2] = {[0 ... 1] = 1}; void f(int n) { switch (n) { case 1 ... 2:; } }";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::ExtensionDisabled; 4]);
+    assert!(matches!(result.ast[2], Statement::Expression(Expression::Call(_))));
+    let messages: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "use of `__attribute__` is a GNU extension",
+            "use of zero-length arrays is a GNU extension",
+            "use of designator ranges is a GNU extension",
+            "use of case ranges is a GNU extension",
+        ]
+    );
+}
+
+#[test]
+fn test_function_prototypes() {
+    let src = "int none(void); int unknown(); int printf(const char *, ...);
+    void sort(int n, int a[static n], int (*cmp)(const void *, const void *), void visit(int));
+    long sum(a, b, c) long a; char *b; { a += *b + c; }";
+    let parse_arena = Bump::new();
+    let options = ParserOptions {
+        standard: CStandard::C17,
+        ..Default::default()
+    };
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    // `c` is not declared and defaults to `int`
+    assert_eq!(codes, [ErrorCode::InvalidParameters]);
+    assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    let functions: Vec<_> = result
+        .ast
+        .iter()
+        .map(|stmt| match stmt {
+            Statement::Function(function) => function,
+            stmt => panic!("expected function, got {stmt:?}"),
+        })
+        .collect();
+    assert!(functions[0].params.is_empty());
+    assert_eq!(functions[0].style, ParamStyle::Prototype);
+    assert_eq!(functions[1].style, ParamStyle::Unspecified);
+    assert!(functions[2].is_variadic && functions[2].params[0].name.is_none());
+
+    let adjusted: Vec<_> = functions[3].params.iter().map(|param| param.adjusted_type.to_string()).collect();
+    assert_eq!(
+        adjusted,
+        ["int", "int *", "int (*)(const void *, const void *)", "void (*)(int)"]
+    );
+
+    let sum = functions[4];
+    assert_eq!(sum.style, ParamStyle::Identifiers);
+    assert!(sum.body.is_some());
+    let types: Vec<_> = sum.params.iter().map(|param| param.data_type.to_string()).collect();
+    assert_eq!(types, ["long", "char *", "int"]);
+    let reconstructed: Vec<_> = result.ast.iter().map(|stmt| stmt.to_string()).collect();
+    assert_eq!(reconstructed[..2], ["int none(void);", "int unknown();"]);
+    assert_eq!(reconstructed[4], "long sum(a, b, c) long a; char *b; int c; {a += *b + c;}");
+
+    // Misplaced `void`, and old-style definitions in C23
+    let src = "void f(void, int); void g(void x); int h(a) int a; { return a; }";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(
+        codes,
+        [
+            ErrorCode::InvalidParameters,
+            ErrorCode::InvalidParameters,
+            ErrorCode::UnavailableInStandard
+        ]
+    );
+
+    // `(...)` needs a named parameter before C23
+    let src = "int l(...); int m(int n, ...);";
+    let result = Parser::with_options(Lexer::new(src), &parse_arena, options).parse();
+    let messages: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.message.as_str()).collect();
+    assert_eq!(messages, ["use of `...` without a named parameter requires C23, but C17 is selected"]);
+    assert_eq!(result.diagnostics[0].code, ErrorCode::UnavailableInStandard);
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(result.diagnostics.is_empty(), "{:#?}", result.diagnostics);
+    let Statement::Function(l) = &result.ast[0] else {
+        panic!("expected function, got {:?}", result.ast[0]);
+    };
+    assert!(l.is_variadic && l.params.is_empty());
+}
+
+#[test]
+fn test_unbraced_bodies() {
+    let src = "int f(int x, int y) {
+        if (x) if (y) return 1; else y = 2;
+        while (x--) ;
+        do x++; while (x < 10);
+        if (x) { return 3; } else if (y) y = 0; else return 4;
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Function(function) = &result.ast[0] else {
+        panic!("expected function, got {:?}", result.ast[0]);
+    };
+    let body = &function.body.as_ref().unwrap().block;
+    let [Statement::If(outer), Statement::While(while_stmt), Statement::DoWhile(do_while), Statement::If(chain)] =
+        &body[..]
+    else {
+        panic!("unexpected statements {body:#?}");
+    };
+    // The `else` belongs to the innermost `if`
+    assert!(outer.alt.is_none());
+    let Statement::If(inner) = outer.body else {
+        panic!("expected nested if, got {:?}", outer.body);
+    };
+    assert!(matches!(inner.body, Statement::Return(_)));
+    assert_eq!(inner.alt.unwrap().body.to_string(), "y = 2;");
+
+    assert!(matches!(while_stmt.body, Statement::Empty(_)));
+    assert_eq!(do_while.body.to_string(), "x++;");
+
+    assert!(matches!(chain.body, Statement::Block(_)));
+    let else_if = chain.alt.unwrap();
+    assert_eq!(else_if.if_type, IfType::ElseIf);
+    assert_eq!(else_if.body.to_string(), "y = 0;");
+    assert!(matches!(else_if.alt.unwrap().body, Statement::Return(_)));
+    assert_eq!(
+        result.ast[0].to_string(),
+        "int f(int x, int y) {if (x) if (y) return 1; else y = 2;while (x--) ;do x++; while (x < 10);if (x) {return 3;} else if (y) y = 0; else return 4;}"
+    );
+    // The reconstruction parses back to the same code
+    let reconstructed = result.ast[0].to_string();
+    let reparsed = Parser::new(Lexer::new(&reconstructed), &parse_arena).parse();
+    assert_eq!(reparsed.ast[0].to_string(), reconstructed);
+}
+
+#[test]
+fn test_for_loops() {
+    let src = "void f(int n) {
+        for (;;) break;
+        for (int i = 0, j = 10; i < j; i++, j--) n += i;
+        for (n = 0; n < 3;) { n++; }
+    }";
+    let parse_arena = Bump::new();
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    let Statement::Function(function) = &result.ast[0] else {
+        panic!("expected function, got {:?}", result.ast[0]);
+    };
+    let body = &function.body.as_ref().unwrap().block;
+    let [Statement::For(forever), Statement::For(counted), Statement::For(partial)] = &body[..] else {
+        panic!("unexpected statements {body:#?}");
+    };
+    assert!(forever.init.is_none() && forever.cond.is_none() && forever.increment.is_none());
+    assert!(matches!(forever.body, Statement::Break(_)));
+
+    let Some(ForInit::Declaration(declaration)) = &counted.init else {
+        panic!("expected declaration, got {:?}", counted.init);
+    };
+    assert_eq!(declaration.declarators.len(), 2);
+    assert_eq!(counted.cond.as_ref().unwrap().to_string(), "i < j");
+    assert_eq!(counted.increment.as_ref().unwrap().to_string(), "i++, j--");
+    assert_eq!(counted.body.to_string(), "n += i;");
+    assert_eq!(
+        result.ast[0].to_string(),
+        "void f(int n) {for (;;) break;for (int i = 0, j = 10; i < j; i++, j--) n += i;for (n = 0; n < 3;) {n++;}}"
+    );
+
+    assert!(matches!(partial.init, Some(ForInit::Expr(_))));
+    assert!(partial.increment.is_none());
+
+    let src = "void f(void) { for (static int i = 0; i < 3; i++) ; }";
+    let result = Parser::new(Lexer::new(src), &parse_arena).parse();
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::MisplacedStatement]);
+}
+
+#[test]
+fn test_fragments() {
+    let arena = Bump::new();
+    let options = ParserOptions::default();
+    let typedefs = ["size_t", "T"];
+    let result = parse_expression("(size_t)x * sizeof(T), y", &arena, options, &typedefs);
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    assert_eq!(result.ast.unwrap().to_string(), "(size_t)x * sizeof(T), y");
+
+    let result = parse_type_name("const T *[4]", &arena, options, &typedefs);
+    assert!(!result.has_errors(), "{:#?}", result.diagnostics);
+    assert!(matches!(result.ast, Some(Type::Array { .. })));
+
+    let result = parse_declaration("static T table[2] = {1, 2};", &arena, options, &typedefs);
+    assert!(matches!(result.ast, Some(Statement::Variable(_))));
+    let result = parse_statement("if (x) y = 1; else ;", &arena, options, &[]);
+    assert!(matches!(result.ast, Some(Statement::If(_))));
+    assert!(!parse_statement(";", &arena, options, &[]).has_errors());
+
+    // Without the typedef, `(T)` is a parenthesized expression
+    let result = parse_expression("(T)x", &arena, options, &[]);
+    let codes: Vec<_> = result.diagnostics.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, [ErrorCode::UnexpectedToken]);
+
+    let result = parse_type_name("int x", &arena, options, &[]);
+    assert_eq!(result.diagnostics[0].message, "expected end of input after type name, found `x`");
+    let result = parse_statement("x = 1; y = 2;", &arena, options, &[]);
+    assert!(result.has_errors());
+    let result = parse_expression("", &arena, options, &[]);
+    assert_eq!(result.diagnostics[0].code, ErrorCode::UnexpectedEof);
+}
+
+#[test]
+fn test_translation_unit() {
+    fn assert_send<T: Send>() {}
+    assert_send::<TranslationUnit>();
+
+    let src = String::from("int add(int a, int b) { return a + b; } int x = 1;");
+    let unit = TranslationUnit::parse_str(src);
+    // The AST stays valid when the unit is moved to another thread
+    let unit = std::thread::spawn(move || unit).join().unwrap();
+    assert!(!unit.has_errors(), "{:#?}", unit.diagnostics());
+    assert_eq!(unit.ast().len(), 2);
+    assert_eq!(unit.ast()[1].to_string(), "int x = 1;");
+    let Statement::Function(add) = &unit.ast()[0] else {
+        panic!("expected function, got {:?}", unit.ast()[0]);
+    };
+    assert_eq!(&unit.source()[add.span.start..add.span.end], "int add(int a, int b) { return a + b; }");
+
+    let unit = TranslationUnit::parse_file(TESTS_PATH).unwrap();
+    assert!(!unit.ast().is_empty());
+    assert!(TranslationUnit::parse_file("tests/missing.c").is_err());
+
+    // Files are preprocessed, with quoted includes found next to them
+    let dir = std::env::temp_dir().join(format!("parcer-unit-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("x.h"), "#define ONE 1\nint bad = (ONE +);\n").unwrap();
+    fs::write(dir.join("main.c"), "#include \"x.h\"\nint one = ONE;\n").unwrap();
+    let unit = TranslationUnit::parse_file(dir.join("main.c")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(unit.ast()[1].to_string(), "int one = 1;");
+    let error = unit.diagnostics().iter().find(|diagnostic| diagnostic.is_error()).unwrap();
+    assert_eq!(error.notes, [format!("in file included from {}:1:1", dir.join("main.c").display())]);
+    assert!(error.render_with(unit.source_map()).contains("int bad = (ONE +);"));
 }
@@ -0,0 +1,112 @@
+//! Parsed translation units that own their source text and AST
+
+use std::{fs, io, path::Path};
+
+use bumpalo::Bump;
+
+use crate::{
+    ast::stmt::Statement,
+    diagnostic::Diagnostic,
+    lexer::Lexer,
+    parser::{ParseResult, Parser, ParserOptions},
+    preprocessor::{Preprocessor, PreprocessorOptions},
+    span::SourceMap,
+};
+
+/// The AST of a whole preprocessed source file together with the source text
+/// and arena it borrows from, so it can be stored and sent to other threads
+#[derive(Debug)]
+pub struct TranslationUnit {
+    /// Borrows `source` and `arena`. The `'static` lifetime is never exposed,
+    /// and the field is declared first so it is dropped before them
+    ast: Vec<Statement<'static>>,
+    /// Included files and macro expansions. Borrows like `ast`
+    source_map: SourceMap<'static>,
+    diagnostics: Vec<Diagnostic>,
+    /// Boxed so the parser's reference stays valid when the unit is moved
+    arena: Box<Bump>,
+    source: String,
+}
+
+impl TranslationUnit {
+    pub fn parse_str(src: impl Into<String>) -> Self {
+        Self::parse_str_with_options(src, ParserOptions::default(), PreprocessorOptions::default())
+    }
+
+    pub fn parse_str_with_options(
+        src: impl Into<String>,
+        options: ParserOptions,
+        preprocessor_options: PreprocessorOptions,
+    ) -> Self {
+        let source = src.into();
+        let arena = Box::new(Bump::new());
+        // SAFETY: The AST and source map only point into the heap allocations
+        // of `source` and `arena`, which do not move when they are moved into
+        // the unit. Neither is modified or dropped while the unit holds them
+        let (src, arena_ref): (&'static str, &'static Bump) =
+            unsafe { (&*(source.as_str() as *const str), &*(&*arena as *const Bump)) };
+        // The preprocessor follows the standard the source is parsed with
+        let preprocessor_options = PreprocessorOptions {
+            standard: options.standard,
+            ..preprocessor_options
+        };
+        let lexer = Preprocessor::with_options(Lexer::new(src), arena_ref, preprocessor_options).preprocess();
+        let source_map = lexer.source_map.clone().unwrap_or_default();
+        let ParseResult { ast, diagnostics } = Parser::with_options(lexer, arena_ref, options).parse();
+        Self {
+            ast,
+            source_map,
+            diagnostics,
+            arena,
+            source,
+        }
+    }
+
+    pub fn parse_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse_file_with_options(path, ParserOptions::default(), PreprocessorOptions::default())
+    }
+
+    /// Quoted includes are searched next to the file first, since it becomes
+    /// the main file of the preprocessor
+    pub fn parse_file_with_options(
+        path: impl AsRef<Path>,
+        options: ParserOptions,
+        preprocessor_options: PreprocessorOptions,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)?;
+        let preprocessor_options = PreprocessorOptions {
+            file_path: Some(path.to_path_buf()),
+            ..preprocessor_options
+        };
+        Ok(Self::parse_str_with_options(src, options, preprocessor_options))
+    }
+
+    /// The top level declarations and definitions
+    pub fn ast(&self) -> &[Statement<'_>] {
+        &self.ast
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The files and macro expansions the AST was produced from, for
+    /// rendering diagnostics with [`Diagnostic::render_with`]
+    pub fn source_map(&self) -> &SourceMap<'_> {
+        &self.source_map
+    }
+
+    /// Bytes allocated for the AST
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+}